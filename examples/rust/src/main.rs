@@ -3,8 +3,10 @@ use accrete::Accrete;
 fn main() {
     let mut accrete = Accrete::new(33);
     accrete.stellar_mass = 2.0;
-    let system = accrete.planetary_system();
-    println!("{:#?}", system);
-    let planet = accrete.planet();
+    let system = accrete
+        .planetary_system()
+        .expect("Failed to generate planetary system");
     println!("{:#?}", system);
+    let planet = accrete.planet().expect("Failed to generate planet");
+    println!("{:#?}", planet);
 }
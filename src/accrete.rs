@@ -1,7 +1,14 @@
+use crate::config::{AccreteBuilder, AccreteConfig, ValidationMode};
 use crate::consts::*;
-use crate::events_log::accrete_event::AccreteEvents;
-use crate::events_log::event_source::EventSource;
+use crate::dynamics::{MigrationConfig, StabilityConfig};
+use crate::error::AccreteError;
+use crate::events_log::accrete_event::{AccreteEvent, AccreteEventKind, AccreteEvents};
+use crate::events_log::observer::AccreteObserver;
+use crate::structs::companion_star::CompanionStarConfig;
+use crate::structs::condensation::{CondensationConfig, CondensingProfile};
+use crate::structs::disk_profile::{DiskModel, DiskProfile};
 use crate::structs::planetesimal::Planetesimal;
+use crate::structs::primary_star::random_system_age;
 use crate::structs::system::System;
 use crate::utils::*;
 
@@ -14,9 +21,18 @@ use serde::{Deserialize, Serialize};
 /// **stellar_mass** - Primary star mass in solar masses.
 /// *Default: random f64 in a range of 0.6-1.3 (corresponds main sequence spectral classes of F-G-K)*
 ///
+/// **metallicity** - Primary star metallicity [Fe/H] in dex relative to the Sun. Scales dust density of cloud by 10^[Fe/H] with the same amount of gas, critical mass of gas giants follows opacity of dusty envelope. Giant planets become rare around metal-poor halo stars and frequent around metal-rich stars. Recommended range: -2.0-0.5
+/// *Default: 0.0*
+///
 /// **dust_density_coeff** - "A" in Dole's paper, recommended range according to Dole's paper is 0.00125-0.0015, aslo noted that binary stars produced by increasing coeff of dust density in cloud (Formation of Planetary Systems by Aggregation: A Computer Simulation by Stephen H. Dole).
 /// *Default: 0.0015*
 ///
+/// **disk_model** - Radial profile of dust density in cloud: Dole's exponential cloud, minimum-mass solar nebula power law or Hayashi nebula with more solids beyond snow line. Power law profiles put more dust into outer cloud. Use `planetary_system_with_disk_profile` for tabulated profiles and closures.
+/// *Default: DiskModel::Dole { alpha: 5.0, n: 3.0 }*
+///
+/// **condensation** - Water ice and optionally CO2 and CO ices condense beyond their lines in cloud, raising density of solids. Lines are reported in `System.condensation_lines` and bulk composition of planets follows them either way. Hayashi disk model already includes water ice line, only CO2 and CO ices are added to it.
+/// *Default: None*
+///
 /// **k** - The dust-to-gas ratio 50-100 (dust/gas = K), gas = hydrogen and helium, dust = other. Recommended range: 50.0-100.0
 /// *Default: 50.0*
 ///
@@ -29,6 +45,24 @@ use serde::{Deserialize, Serialize};
 /// **post_accretion_intensity** - Amount of random planetesimals that will bomb planets of created system after accretion.
 /// *Default: 1000*
 ///
+/// **companion_star** - Binary companion of primary star: mass in solar masses, separation in AU and eccentricity of binary orbit. Companion truncates dust cloud at orbital stability limits, planets can be formed on S-type orbits around primary star and P-type (circumbinary) orbits around both stars.
+/// *Default: None*
+///
+/// **migration** - Type I and type II migration of planets through gas left in dust bands after accretion, gas disk lifetime in years and viscosity alpha parameter. Gas giants move inward until gas is cleared and may end up as hot Jupiters at inner edge of disk.
+/// *Default: None*
+///
+/// **regular_moons** - Accrete regular moons of gas giants from circumplanetary disks by Dole's algorithm, on disk scaled to Hill sphere and mass of planet. Moons captured during post-accretion are kept as irregular moons.
+/// *Default: false*
+///
+/// **resonance_capture** - Move near-resonant neighbouring planets and moons into exact mean-motion resonance, imitating resonance capture during migration. Resonances are detected either way.
+/// *Default: false*
+///
+/// **stability** - Check dynamical stability of planets by mutual Hill radius spacing of neighbours and optionally by short N-body integration of planets and primary star. Unstable configurations are reported in `System.stability` or resolved through ejection, collision or scattering of planets. Circumbinary planets are checked around both stars, apart from planets of primary star.
+/// *Default: None*
+///
+/// **system_age** - Age of planetary system in years. Primary star evolves along its track to this age: brightens on main sequence, expands into subgiant and red giant and leaves white dwarf. Planets within largest radius of giant star are engulfed, the rest move outward as star loses mass, circumbinary planets as both stars lose mass. Planetary environment follows current luminosity of star. Also used for standalone planet generation.
+/// *Default: random f64 between 1 and 6 billion years, limited by main sequence lifetime of primary star*
+///
 /// Parameters specific for standalone planet generation
 /// **planet_a** - Planet orbital radius in AU.
 /// *Default: random f64 in a range of 0.3-50.0*
@@ -42,16 +76,25 @@ use serde::{Deserialize, Serialize};
 /// **stellar_luminosity** - Primary star luminosity.
 /// *Default: 1.0*
 ///
-/// **events_log** - AccreteEvents log.
+/// **events_log** - AccreteEvents log, filled by `planetary_system` and `planet`. Use `planetary_system_with_observer` and `planet_with_observer` to stream or filter events instead.
 /// *Default: []*
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Accrete {
     pub stellar_mass: f64,
+    pub metallicity: f64,
     pub dust_density_coeff: f64,
+    pub disk_model: DiskModel,
+    pub condensation: Option<CondensationConfig>,
     pub k: f64,
     pub cloud_eccentricity: f64,
     pub b: f64,
     pub post_accretion_intensity: u32,
+    pub companion_star: Option<CompanionStarConfig>,
+    pub migration: Option<MigrationConfig>,
+    pub regular_moons: bool,
+    pub resonance_capture: bool,
+    pub stability: Option<StabilityConfig>,
+    pub system_age: Option<f64>,
     pub planet_a: f64,
     pub planet_e: f64,
     pub planet_mass: f64,
@@ -71,11 +114,20 @@ impl Default for Accrete {
 
         Accrete {
             stellar_mass: random_stellar_mass,
+            metallicity: 0.0,
             dust_density_coeff: DUST_DENSITY_COEFF,
+            disk_model: DiskModel::default(),
+            condensation: None,
             k: K,
             cloud_eccentricity: 0.2,
             b: B,
             post_accretion_intensity: 1000,
+            companion_star: None,
+            migration: None,
+            regular_moons: false,
+            resonance_capture: false,
+            stability: None,
+            system_age: None,
             stellar_luminosity: 1.0,
             planet_a,
             planet_e,
@@ -97,11 +149,20 @@ impl Accrete {
 
         Accrete {
             stellar_mass: random_stellar_mass,
+            metallicity: 0.0,
             dust_density_coeff: DUST_DENSITY_COEFF,
+            disk_model: DiskModel::default(),
+            condensation: None,
             k: K,
             cloud_eccentricity: 0.2,
             b: B,
             post_accretion_intensity: 1000,
+            companion_star: None,
+            migration: None,
+            regular_moons: false,
+            resonance_capture: false,
+            stability: None,
+            system_age: None,
             stellar_luminosity: 1.0,
             planet_a,
             planet_e,
@@ -111,43 +172,206 @@ impl Accrete {
         }
     }
 
-    /// Generate planetary system.
-    pub fn planetary_system(&mut self) -> System {
+    /// Builder with validated configuration, see [AccreteBuilder].
+    pub fn builder(seed: u64) -> AccreteBuilder {
+        AccreteBuilder::new(seed)
+    }
+
+    /// Create Accrete from seed with given configuration.
+    pub fn from_config(seed: u64, config: AccreteConfig) -> Self {
+        let AccreteConfig {
+            stellar_mass,
+            metallicity,
+            dust_density_coeff,
+            disk_model,
+            condensation,
+            k,
+            cloud_eccentricity,
+            b,
+            post_accretion_intensity,
+            companion_star,
+            migration,
+            regular_moons,
+            resonance_capture,
+            stability,
+            system_age,
+            planet_a,
+            planet_e,
+            planet_mass,
+            stellar_luminosity,
+        } = config;
+
+        Accrete {
+            stellar_mass,
+            metallicity,
+            dust_density_coeff,
+            disk_model,
+            condensation,
+            k,
+            cloud_eccentricity,
+            b,
+            post_accretion_intensity,
+            companion_star,
+            migration,
+            regular_moons,
+            resonance_capture,
+            stability,
+            system_age,
+            planet_a,
+            planet_e,
+            planet_mass,
+            stellar_luminosity,
+            ..Accrete::new(seed)
+        }
+    }
+
+    /// Current configuration, serializable separately from RNG state.
+    pub fn config(&self) -> AccreteConfig {
+        AccreteConfig {
+            stellar_mass: self.stellar_mass,
+            metallicity: self.metallicity,
+            dust_density_coeff: self.dust_density_coeff,
+            disk_model: self.disk_model,
+            condensation: self.condensation,
+            k: self.k,
+            cloud_eccentricity: self.cloud_eccentricity,
+            b: self.b,
+            post_accretion_intensity: self.post_accretion_intensity,
+            companion_star: self.companion_star,
+            migration: self.migration,
+            regular_moons: self.regular_moons,
+            resonance_capture: self.resonance_capture,
+            stability: self.stability,
+            system_age: self.system_age,
+            planet_a: self.planet_a,
+            planet_e: self.planet_e,
+            planet_mass: self.planet_mass,
+            stellar_luminosity: self.stellar_luminosity,
+        }
+    }
+
+    /// Generate planetary system, all events are collected into events_log.
+    pub fn planetary_system(&mut self) -> Result<System, AccreteError> {
+        let mut events_log = std::mem::take(&mut self.events_log);
+        let planetary_system = self.planetary_system_with_observer(&mut events_log);
+        self.events_log = events_log;
+        planetary_system
+    }
+
+    /// Generate planetary system, events are passed to observer as they happen.
+    pub fn planetary_system_with_observer(
+        &mut self,
+        observer: &mut dyn AccreteObserver,
+    ) -> Result<System, AccreteError> {
+        let disk_model = self.disk_model;
+        self.planetary_system_with_disk_profile(&disk_model, observer)
+    }
+
+    /// Generate planetary system in cloud with given dust density profile instead of disk_model, events are passed to observer as they happen.
+    pub fn planetary_system_with_disk_profile(
+        &mut self,
+        disk_profile: &dyn DiskProfile,
+        observer: &mut dyn AccreteObserver,
+    ) -> Result<System, AccreteError> {
+        self.config().validate_system(ValidationMode::Permissive)?;
+
         let Accrete {
             stellar_mass,
+            metallicity,
             dust_density_coeff,
+            condensation,
             k,
             cloud_eccentricity,
             b,
             post_accretion_intensity,
+            companion_star,
+            migration,
+            regular_moons,
+            resonance_capture,
+            stability,
+            system_age,
             rng,
-            events_log,
             ..
         } = self;
 
         let mut planetary_system = System::set_initial_conditions(
             *stellar_mass,
+            *metallicity,
             *dust_density_coeff,
             *k,
             *cloud_eccentricity,
             *b,
+            *companion_star,
+            *condensation,
         );
+        let condensing_profile = CondensingProfile {
+            disk_profile,
+            condensation_lines: planetary_system.condensation_lines,
+        };
+        let disk_profile: &dyn DiskProfile = match condensation {
+            Some(_) => &condensing_profile,
+            None => disk_profile,
+        };
 
-        planetary_system.event("system_setup", events_log);
+        observer.emit(AccreteEventKind::PlanetarySystemSetup, || {
+            AccreteEvent::PlanetarySystemSetup {
+                system: planetary_system.clone(),
+            }
+        });
 
-        planetary_system.distribute_planetary_masses(rng, events_log);
-        planetary_system.post_accretion(*post_accretion_intensity, rng, events_log);
+        planetary_system.distribute_planetary_masses(disk_profile, rng, observer);
+        if let Some(migration) = migration {
+            planetary_system.migrate_planets(migration, disk_profile, rng, observer);
+        }
+        planetary_system.post_accretion(*post_accretion_intensity, rng, observer)?;
+        if *regular_moons {
+            planetary_system.form_regular_moons(rng, observer);
+        }
+        planetary_system.process_orbits(rng, observer);
+        planetary_system.process_resonances(*resonance_capture, observer);
+        let system_age = system_age
+            .unwrap_or_else(|| random_system_age(&planetary_system.primary_star.main_seq_age, rng));
+        planetary_system.evolve_stars(&system_age, observer);
+        if let Some(stability) = stability {
+            planetary_system.process_stability(stability, observer);
+            // Ejected, merged and scattered planets change pairs of neighbours
+            if stability.resolve {
+                planetary_system.process_resonances(false, observer);
+            }
+        }
         planetary_system.process_planets(rng);
+        planetary_system.process_belts(disk_profile, rng);
 
-        planetary_system.event("planetary_environment_generated", events_log);
+        observer.emit(AccreteEventKind::PlanetaryEnvironmentGenerated, || {
+            AccreteEvent::PlanetaryEnvironmentGenerated {
+                system: planetary_system.clone(),
+            }
+        });
 
-        planetary_system.event("system_complete", events_log);
+        observer.emit(AccreteEventKind::PlanetarySystemComplete, || {
+            AccreteEvent::PlanetarySystemComplete {
+                system: planetary_system.clone(),
+            }
+        });
 
-        planetary_system
+        Ok(planetary_system)
+    }
+
+    /// Generate planet, all events are collected into events_log.
+    pub fn planet(&mut self) -> Result<Planetesimal, AccreteError> {
+        let mut events_log = std::mem::take(&mut self.events_log);
+        let planet = self.planet_with_observer(&mut events_log);
+        self.events_log = events_log;
+        planet
     }
 
-    /// Generate planet.
-    pub fn planet(&mut self) -> Planetesimal {
+    /// Generate planet, events are passed to observer as they happen.
+    pub fn planet_with_observer(
+        &mut self,
+        observer: &mut dyn AccreteObserver,
+    ) -> Result<Planetesimal, AccreteError> {
+        self.config().validate_planet(ValidationMode::Permissive)?;
+
         let Accrete {
             stellar_mass,
             stellar_luminosity,
@@ -155,20 +379,23 @@ impl Accrete {
             planet_e,
             planet_mass,
             post_accretion_intensity,
+            system_age,
             rng,
-            events_log,
             ..
         } = self;
 
-        Planetesimal::random_planet(
+        let planet = Planetesimal::random_planet(
             *stellar_luminosity,
             *stellar_mass,
             *planet_a,
             *planet_e,
             *planet_mass,
             *post_accretion_intensity,
+            *system_age,
             rng,
-            events_log,
-        )
+            observer,
+        );
+
+        Ok(planet)
     }
 }
@@ -0,0 +1,653 @@
+use accrete::consts::MMSN_DENSITY_EXPONENT;
+use accrete::events_log::accrete_event::AccreteEvents;
+use accrete::events_log::observer::{AccreteObserver, NullObserver};
+use accrete::svg::{render_frames, render_system, SvgOptions};
+use accrete::{
+    AccreteBuilder, AccreteError, CompanionStarConfig, CondensationConfig, DiskModel,
+    MigrationConfig, Planetesimal, Preset, StabilityConfig, System, TabulatedProfile,
+    ValidationMode,
+};
+use clap::{CommandFactory, Parser, ValueEnum};
+use serde::Serialize;
+use std::ffi::OsString;
+use std::fmt;
+use std::io::{ErrorKind, Write};
+use std::ops::RangeInclusive;
+
+/// Generate planetary systems or single planets with Accrete.
+/// Parameters not set explicitly keep values derived from seed, see crate docs for recommended ranges.
+#[derive(Debug, Parser)]
+#[command(name = "accrete", version)]
+struct Cli {
+    /// Seed of generation, random if not set
+    #[arg(long, conflicts_with = "seeds")]
+    seed: Option<u64>,
+    /// Range of seeds for batch generation, "1..100" or "1..=100"
+    #[arg(long, value_parser = parse_seed_range)]
+    seeds: Option<RangeInclusive<u64>>,
+    /// Generate single planet instead of planetary system
+    #[arg(long)]
+    planet: bool,
+    #[arg(long, value_enum, default_value_t = Format::Table)]
+    format: Format,
+    /// Directory to write SVG frames of accretion replay into, one file per frame and seed
+    #[arg(long, conflicts_with = "planet")]
+    frames: Option<std::path::PathBuf>,
+    /// Number of events between SVG frames
+    #[arg(long, default_value_t = 100, requires = "frames")]
+    frame_interval: usize,
+    /// Reject parameters outside of recommended ranges
+    #[arg(long)]
+    strict: bool,
+    #[arg(long, value_enum)]
+    preset: Option<PresetArg>,
+    /// Primary star mass in solar masses
+    #[arg(long)]
+    stellar_mass: Option<f64>,
+    /// Primary star metallicity [Fe/H], dex
+    #[arg(long, allow_negative_numbers = true)]
+    metallicity: Option<f64>,
+    /// "A" in Dole's paper
+    #[arg(long)]
+    dust_density_coeff: Option<f64>,
+    /// Radial profile of dust density in cloud
+    #[arg(long, value_enum)]
+    disk_model: Option<DiskModelArg>,
+    /// CSV file with distance in AU and relative dust density per line, replaces disk model
+    #[arg(long, value_parser = parse_disk_table, conflicts_with_all = ["disk_model", "planet"])]
+    disk_table: Option<TabulatedProfile>,
+    /// Condense water ice beyond snow line, raising density of solids
+    #[arg(long)]
+    condensation: bool,
+    /// Condense CO2 and CO ices beyond their lines as well
+    #[arg(long, requires = "condensation")]
+    volatile_ices: bool,
+    /// Dust-to-gas ratio
+    #[arg(long)]
+    k: Option<f64>,
+    #[arg(long)]
+    cloud_eccentricity: Option<f64>,
+    /// Critical mass coefficient, threshold for planet to become gas giant
+    #[arg(long)]
+    b: Option<f64>,
+    #[arg(long)]
+    post_accretion_intensity: Option<u32>,
+    /// Companion star mass in solar masses
+    #[arg(long, requires = "companion_separation")]
+    companion_mass: Option<f64>,
+    /// Semi-major axis of binary orbit in AU
+    #[arg(long, requires = "companion_mass")]
+    companion_separation: Option<f64>,
+    #[arg(long, default_value_t = 0.0, requires = "companion_mass")]
+    companion_eccentricity: f64,
+    /// Migrate planets through gas disk after accretion
+    #[arg(long)]
+    migration: bool,
+    /// Gas disk lifetime in years
+    #[arg(long, requires = "migration")]
+    disk_lifetime: Option<f64>,
+    /// Viscosity alpha parameter of gas disk
+    #[arg(long, requires = "migration")]
+    disk_viscosity: Option<f64>,
+    /// Accrete regular moons of gas giants from circumplanetary disks
+    #[arg(long)]
+    regular_moons: bool,
+    /// Move near-resonant planets and moons into exact resonance
+    #[arg(long)]
+    resonance_capture: bool,
+    /// Check dynamical stability of planets
+    #[arg(long)]
+    stability: bool,
+    /// Resolve unstable planets by ejection, collision or scattering
+    #[arg(long, requires = "stability")]
+    resolve_instabilities: bool,
+    /// Duration of N-body integration in years
+    #[arg(long, requires = "stability")]
+    integration_years: Option<f64>,
+    /// Age of planetary system in years, star evolves to this age
+    #[arg(long)]
+    system_age: Option<f64>,
+    /// Planet semi-major axis in AU
+    #[arg(long)]
+    planet_a: Option<f64>,
+    #[arg(long)]
+    planet_e: Option<f64>,
+    /// Planet mass in solar masses
+    #[arg(long)]
+    planet_mass: Option<f64>,
+    /// Primary star luminosity for planet generation
+    #[arg(long)]
+    stellar_luminosity: Option<f64>,
+}
+
+impl Cli {
+    /// Parse arguments and check combinations clap can't express, SVG output renders single planetary system
+    fn try_parse_args<I, T>(args: I) -> Result<Self, clap::Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let cli = Self::try_parse_from(args)?;
+        match cli.format == Format::Svg && (cli.planet || cli.seeds.is_some()) {
+            true => Err(Self::command().error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "'--format svg' renders single planetary system and can't be used with '--seeds' or '--planet'",
+            )),
+            false => Ok(cli),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    Json,
+    Yaml,
+    Csv,
+    Table,
+    /// Radial plot of single planetary system
+    Svg,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum PresetArg {
+    SolLike,
+    MDwarfCompact,
+    Dole1970Original,
+    HighMetallicity,
+}
+
+impl From<PresetArg> for Preset {
+    fn from(preset: PresetArg) -> Self {
+        match preset {
+            PresetArg::SolLike => Preset::SolLike,
+            PresetArg::MDwarfCompact => Preset::MDwarfCompact,
+            PresetArg::Dole1970Original => Preset::Dole1970Original,
+            PresetArg::HighMetallicity => Preset::HighMetallicity,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum DiskModelArg {
+    Dole,
+    Mmsn,
+    Hayashi,
+}
+
+impl From<DiskModelArg> for DiskModel {
+    fn from(disk_model: DiskModelArg) -> Self {
+        match disk_model {
+            DiskModelArg::Dole => DiskModel::default(),
+            DiskModelArg::Mmsn => DiskModel::MinimumMassSolarNebula {
+                exponent: MMSN_DENSITY_EXPONENT,
+            },
+            DiskModelArg::Hayashi => DiskModel::Hayashi,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum Output {
+    System { seed: u64, system: System },
+    Planet { seed: u64, planet: Planetesimal },
+}
+
+/// Error of command-line run, generation errors are passed through
+#[derive(Debug)]
+enum CliError {
+    Accrete(AccreteError),
+    /// Failed to write output or frames
+    Io(std::io::Error),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Accrete(e) => write!(f, "{}", e),
+            CliError::Io(e) => write!(f, "Failed to write output: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+impl From<AccreteError> for CliError {
+    fn from(e: AccreteError) -> Self {
+        CliError::Accrete(e)
+    }
+}
+
+impl From<std::io::Error> for CliError {
+    fn from(e: std::io::Error) -> Self {
+        CliError::Io(e)
+    }
+}
+
+const CSV_HEADER: &str = "seed,id,a,e,earth_masses,earth_radii,is_gas_giant,is_dwarf_planet,is_moon,moons,rings,surface_temp_kelvin,surface_pressure_bar,hydrosphere,orbital_period_days,iron_fraction,silicate_fraction,ice_fraction,gas_fraction";
+
+fn main() {
+    let cli = Cli::try_parse_args(std::env::args_os()).unwrap_or_else(|e| e.exit());
+    if let Err(e) = run(&cli) {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run(cli: &Cli) -> Result<(), CliError> {
+    let formatted = generate(cli)?;
+    let mut stdout = std::io::stdout().lock();
+    match writeln!(stdout, "{}", formatted) {
+        Err(e) if e.kind() != ErrorKind::BrokenPipe => Err(CliError::Io(e)),
+        _ => Ok(()),
+    }
+}
+
+/// Systems or planets of all seeds in output format, frames are written to their directory
+fn generate(cli: &Cli) -> Result<String, CliError> {
+    let seeds = match (&cli.seeds, cli.seed) {
+        (Some(seeds), _) => seeds.clone(),
+        (None, Some(seed)) => seed..=seed,
+        (None, None) => {
+            let seed = rand::random();
+            seed..=seed
+        }
+    };
+
+    let mut outputs = Vec::new();
+    for seed in seeds {
+        let mut accrete = builder(cli, seed).build()?;
+        // Events are only needed to render frames
+        let mut events_log = AccreteEvents::new();
+        let mut null_observer = NullObserver;
+        let observer: &mut dyn AccreteObserver = match cli.frames {
+            Some(_) => &mut events_log,
+            None => &mut null_observer,
+        };
+        let output = match cli.planet {
+            true => Output::Planet {
+                seed,
+                planet: accrete.planet_with_observer(observer)?,
+            },
+            false => Output::System {
+                seed,
+                system: match &cli.disk_table {
+                    Some(disk_table) => {
+                        accrete.planetary_system_with_disk_profile(disk_table, observer)?
+                    }
+                    None => accrete.planetary_system_with_observer(observer)?,
+                },
+            },
+        };
+        if let Some(dir) = &cli.frames {
+            write_frames(dir, seed, &events_log, cli.frame_interval)?;
+        }
+        outputs.push(output);
+    }
+
+    let formatted = match cli.format {
+        Format::Json => serde_json::to_string_pretty(&outputs)
+            .map_err(|e| AccreteError::Serialization(e.to_string()))?,
+        Format::Yaml => serde_yaml::to_string(&outputs)
+            .map_err(|e| AccreteError::Serialization(e.to_string()))?,
+        Format::Csv => to_csv(&outputs),
+        Format::Table => to_table(&outputs),
+        // Arguments of SVG output are checked to produce single planetary system
+        Format::Svg => outputs
+            .iter()
+            .find_map(|output| match output {
+                Output::System { system, .. } => {
+                    Some(render_system(system, &SvgOptions::default()))
+                }
+                Output::Planet { .. } => None,
+            })
+            .unwrap_or_default(),
+    };
+    Ok(formatted)
+}
+
+fn write_frames(
+    dir: &std::path::Path,
+    seed: u64,
+    events_log: &AccreteEvents,
+    frame_interval: usize,
+) -> Result<(), CliError> {
+    let frames = render_frames(events_log, frame_interval, &SvgOptions::default())?;
+    std::fs::create_dir_all(dir)?;
+    for (i, frame) in frames.iter().enumerate() {
+        std::fs::write(dir.join(format!("{}_{:04}.svg", seed, i)), frame)?;
+    }
+    Ok(())
+}
+
+fn builder(cli: &Cli, seed: u64) -> AccreteBuilder {
+    let mut builder = AccreteBuilder::new(seed).validation(match cli.strict {
+        true => ValidationMode::Strict,
+        false => ValidationMode::Permissive,
+    });
+
+    if let Some(preset) = cli.preset {
+        builder = builder.preset(preset.into());
+    }
+    if let Some(stellar_mass) = cli.stellar_mass {
+        builder = builder.stellar_mass(stellar_mass);
+    }
+    if let Some(metallicity) = cli.metallicity {
+        builder = builder.metallicity(metallicity);
+    }
+    if let Some(dust_density_coeff) = cli.dust_density_coeff {
+        builder = builder.dust_density_coeff(dust_density_coeff);
+    }
+    if let Some(disk_model) = cli.disk_model {
+        builder = builder.disk_model(disk_model.into());
+    }
+    if cli.condensation {
+        builder = builder.condensation(CondensationConfig::new(cli.volatile_ices));
+    }
+    if let Some(k) = cli.k {
+        builder = builder.k(k);
+    }
+    if let Some(cloud_eccentricity) = cli.cloud_eccentricity {
+        builder = builder.cloud_eccentricity(cloud_eccentricity);
+    }
+    if let Some(b) = cli.b {
+        builder = builder.b(b);
+    }
+    if let Some(post_accretion_intensity) = cli.post_accretion_intensity {
+        builder = builder.post_accretion_intensity(post_accretion_intensity);
+    }
+    if let (Some(mass), Some(separation)) = (cli.companion_mass, cli.companion_separation) {
+        builder = builder.companion_star(CompanionStarConfig::new(
+            mass,
+            separation,
+            cli.companion_eccentricity,
+        ));
+    }
+    if cli.migration {
+        let default = MigrationConfig::default();
+        builder = builder.migration(MigrationConfig::new(
+            cli.disk_lifetime.unwrap_or(default.disk_lifetime),
+            cli.disk_viscosity.unwrap_or(default.viscosity),
+        ));
+    }
+    if cli.regular_moons {
+        builder = builder.regular_moons(true);
+    }
+    if cli.resonance_capture {
+        builder = builder.resonance_capture(true);
+    }
+    if cli.stability {
+        builder = builder.stability(StabilityConfig::new(
+            cli.resolve_instabilities,
+            cli.integration_years.unwrap_or(0.0),
+        ));
+    }
+    if let Some(system_age) = cli.system_age {
+        builder = builder.system_age(system_age);
+    }
+    if let Some(planet_a) = cli.planet_a {
+        builder = builder.planet_a(planet_a);
+    }
+    if let Some(planet_e) = cli.planet_e {
+        builder = builder.planet_e(planet_e);
+    }
+    if let Some(planet_mass) = cli.planet_mass {
+        builder = builder.planet_mass(planet_mass);
+    }
+    if let Some(stellar_luminosity) = cli.stellar_luminosity {
+        builder = builder.stellar_luminosity(stellar_luminosity);
+    }
+
+    builder
+}
+
+fn parse_seed_range(s: &str) -> Result<RangeInclusive<u64>, String> {
+    let (start, end, inclusive) = match s.split_once("..=") {
+        Some((start, end)) => (start, end, true),
+        None => match s.split_once("..") {
+            Some((start, end)) => (start, end, false),
+            None => return Err(format!("Expected START..END or START..=END, got '{}'", s)),
+        },
+    };
+    let start = start.trim().parse::<u64>().map_err(|e| e.to_string())?;
+    let end = end.trim().parse::<u64>().map_err(|e| e.to_string())?;
+    let end = match inclusive {
+        true => end,
+        false => end
+            .checked_sub(1)
+            .ok_or_else(|| format!("Empty seed range '{}'", s))?,
+    };
+    match start <= end {
+        true => Ok(start..=end),
+        false => Err(format!("Empty seed range '{}'", s)),
+    }
+}
+
+fn parse_disk_table(path: &str) -> Result<TabulatedProfile, String> {
+    let table = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let points = table
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let (r, density) = line
+                .split_once(',')
+                .ok_or_else(|| format!("Expected R,DENSITY, got '{}'", line))?;
+            let r = r.trim().parse::<f64>().map_err(|e| e.to_string())?;
+            let density = density.trim().parse::<f64>().map_err(|e| e.to_string())?;
+            Ok((r, density))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    match points.len() >= 2 {
+        true => Ok(TabulatedProfile::new(points)),
+        false => Err(format!("Disk table '{}' needs at least two points", path)),
+    }
+}
+
+fn planets(output: &Output) -> (u64, Vec<&Planetesimal>) {
+    match output {
+        Output::System { seed, system } => (*seed, system.planets.iter().collect()),
+        Output::Planet { seed, planet } => (*seed, vec![planet]),
+    }
+}
+
+/// One row per planet, moons are listed after their planet
+fn to_csv(outputs: &[Output]) -> String {
+    let mut rows = vec![CSV_HEADER.to_string()];
+    for output in outputs.iter() {
+        let (seed, planets) = planets(output);
+        for planet in planets {
+            rows.push(csv_row(seed, planet));
+            for moon in planet.moons.iter() {
+                rows.push(csv_row(seed, moon));
+            }
+        }
+    }
+    rows.join("\n")
+}
+
+fn csv_row(seed: u64, p: &Planetesimal) -> String {
+    format!(
+        "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+        seed,
+        p.id,
+        p.a,
+        p.e,
+        p.earth_masses,
+        p.earth_radii,
+        p.is_gas_giant,
+        p.is_dwarf_planet,
+        p.is_moon,
+        p.moons.len(),
+        p.rings.len(),
+        p.surface_temp_kelvin,
+        optional(p.surface_pressure_bar),
+        optional(p.hydrosphere),
+        p.orbital_period_days,
+        p.bulk_composition.iron_fraction(),
+        p.bulk_composition.silicate_fraction(),
+        p.bulk_composition.ice_fraction(),
+        p.bulk_composition.gas_fraction(),
+    )
+}
+
+/// Quantities not defined for the body are left empty
+fn optional(value: Option<f64>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+fn to_table(outputs: &[Output]) -> String {
+    let mut lines = vec![];
+    for output in outputs.iter() {
+        match output {
+            Output::System { seed, system } => {
+                let star = &system.primary_star;
+                lines.push(format!(
+                    "Seed {}: {} star, {:.2} solar masses, {:.3} solar luminosities, {} planets",
+                    seed,
+                    star.spectral_type(),
+                    star.stellar_mass,
+                    star.stellar_luminosity,
+                    system.planets.len()
+                ));
+            }
+            Output::Planet { seed, .. } => lines.push(format!("Seed {}:", seed)),
+        }
+        lines.push(format!(
+            "{:<9} {:>9} {:>6} {:>11} {:>8} {:>10} {:>9} {:>5} {:>5}",
+            "id", "a, AU", "e", "mass, M⊕", "R, R⊕", "temp, K", "P, bar", "moons", "rings"
+        ));
+        let (_, planets) = planets(output);
+        for p in planets {
+            let kind = match (p.is_gas_giant, p.is_dwarf_planet) {
+                (true, _) => "*",
+                (false, true) => ".",
+                (false, false) => " ",
+            };
+            // Surface pressure is not defined for gas giants
+            let pressure = p
+                .surface_pressure_bar
+                .map(|pressure| format!("{:.3}", pressure))
+                .unwrap_or_else(|| "-".to_string());
+            lines.push(format!(
+                "{:<9} {:>9.3} {:>6.3} {:>11.3} {:>8.3} {:>10} {:>9} {:>5} {:>5}",
+                format!("{}{}", p.id, kind),
+                p.a,
+                p.e,
+                p.earth_masses,
+                p.earth_radii,
+                format!("{:.1}", p.surface_temp_kelvin),
+                pressure,
+                p.moons.len(),
+                p.rings.len()
+            ));
+        }
+        lines.push(String::new());
+    }
+    lines.push("* gas giant, temperature at 1-bar level, . dwarf planet".to_string());
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_seed_ranges() {
+        assert_eq!(parse_seed_range("1..10"), Ok(1..=9));
+        assert_eq!(parse_seed_range("1..=10"), Ok(1..=10));
+        assert!(parse_seed_range("10..1").is_err());
+        assert!(parse_seed_range("0..0").is_err());
+        assert!(parse_seed_range("10").is_err());
+    }
+
+    #[test]
+    fn cli_parameters_passed_to_builder() {
+        let cli = Cli::parse_from([
+            "accrete",
+            "--seed",
+            "3",
+            "--k",
+            "60",
+            "--metallicity",
+            "-0.5",
+            "--disk-model",
+            "hayashi",
+            "--condensation",
+            "--volatile-ices",
+            "--companion-mass",
+            "0.5",
+            "--companion-separation",
+            "40",
+            "--regular-moons",
+        ]);
+        let config = builder(&cli, 3).config().expect("Invalid configuration");
+        assert_eq!(config.k, 60.0);
+        assert_eq!(config.metallicity, -0.5);
+        assert_eq!(config.disk_model, DiskModel::Hayashi);
+        assert_eq!(config.condensation, Some(CondensationConfig::new(true)));
+        assert_eq!(
+            config.companion_star,
+            Some(CompanionStarConfig::new(0.5, 40.0, 0.0))
+        );
+        assert!(config.regular_moons);
+        assert_eq!(config.stability, None);
+    }
+
+    #[test]
+    fn companion_eccentricity_requires_companion() {
+        assert!(Cli::try_parse_from(["accrete", "--companion-eccentricity", "0.3"]).is_err());
+    }
+
+    #[test]
+    fn csv_has_row_per_body() {
+        let mut accrete = AccreteBuilder::new(1)
+            .build()
+            .expect("Invalid configuration");
+        let system = accrete
+            .planetary_system()
+            .expect("Failed to generate planetary system");
+        let bodies =
+            system.planets.len() + system.planets.iter().map(|p| p.moons.len()).sum::<usize>();
+        let csv = to_csv(&[Output::System { seed: 1, system }]);
+        assert_eq!(csv.lines().count(), bodies + 1);
+    }
+
+    #[test]
+    fn svg_requires_single_system() {
+        for args in [
+            vec!["accrete", "--seeds", "1..3", "--format", "svg"],
+            vec!["accrete", "--seed", "1", "--planet", "--format", "svg"],
+        ] {
+            let error = Cli::try_parse_args(args).expect_err("SVG of several systems accepted");
+            assert_eq!(error.kind(), clap::error::ErrorKind::ArgumentConflict);
+        }
+    }
+
+    #[test]
+    fn frames_written_per_seed() {
+        let dir = std::env::temp_dir().join(format!("accrete_cli_frames_{}", std::process::id()));
+        let cli = Cli::parse_from([
+            "accrete",
+            "--seed",
+            "2",
+            "--post-accretion-intensity",
+            "10",
+            "--frames",
+            dir.to_str().expect("Invalid temp dir"),
+            "--frame-interval",
+            "1000",
+            "--format",
+            "svg",
+        ]);
+        let svg = generate(&cli).expect("Failed to render frames");
+        assert!(svg.starts_with("<svg"));
+        let frames = std::fs::read_dir(&dir)
+            .expect("Failed to read frames")
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with("2_"))
+            .count();
+        assert!(frames >= 2);
+        std::fs::remove_dir_all(&dir).expect("Failed to remove frames");
+    }
+}
@@ -0,0 +1,467 @@
+use crate::accrete::Accrete;
+use crate::consts::*;
+use crate::dynamics::{MigrationConfig, StabilityConfig};
+use crate::error::{validate_parameter, AccreteError};
+use crate::structs::companion_star::CompanionStarConfig;
+use crate::structs::condensation::CondensationConfig;
+use crate::structs::disk_profile::DiskModel;
+
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+
+/// Accrete configuration without RNG state and events log, see [Accrete](crate::Accrete) for parameters description.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct AccreteConfig {
+    pub stellar_mass: f64,
+    #[serde(default)]
+    pub metallicity: f64,
+    pub dust_density_coeff: f64,
+    #[serde(default)]
+    pub disk_model: DiskModel,
+    #[serde(default)]
+    pub condensation: Option<CondensationConfig>,
+    pub k: f64,
+    pub cloud_eccentricity: f64,
+    pub b: f64,
+    pub post_accretion_intensity: u32,
+    pub companion_star: Option<CompanionStarConfig>,
+    #[serde(default)]
+    pub migration: Option<MigrationConfig>,
+    #[serde(default)]
+    pub regular_moons: bool,
+    #[serde(default)]
+    pub resonance_capture: bool,
+    #[serde(default)]
+    pub stability: Option<StabilityConfig>,
+    #[serde(default)]
+    pub system_age: Option<f64>,
+    pub planet_a: f64,
+    pub planet_e: f64,
+    pub planet_mass: f64,
+    pub stellar_luminosity: f64,
+}
+
+/// How strictly configuration is checked by [AccreteBuilder]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ValidationMode {
+    /// Parameters must lie within recommended ranges from Dole's paper and Accrete docs
+    Strict,
+    /// Parameters must only be finite and physically valid
+    Permissive,
+}
+
+/// Named configuration presets
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Preset {
+    /// Solar mass star with default cloud parameters
+    SolLike,
+    /// Low mass red dwarf with less eccentric cloud, producing compact system of small planets
+    MDwarfCompact,
+    /// Parameters of original simulation from Dole's paper, without post-accretion
+    Dole1970Original,
+    /// Metal-rich solar mass star, [Fe/H] = +0.3, with dust-rich cloud producing more massive planets and gas giants
+    HighMetallicity,
+}
+
+/// Recommended ranges used by [ValidationMode::Strict]
+pub const RECOMMENDED_METALLICITY: RangeInclusive<f64> = -2.0..=0.5;
+pub const RECOMMENDED_DUST_DENSITY_COEFF: RangeInclusive<f64> = 1.25E-3..=1.5E-3;
+pub const RECOMMENDED_DISK_DENSITY_EXPONENT: RangeInclusive<f64> = 0.5..=2.2;
+pub const RECOMMENDED_K: RangeInclusive<f64> = 50.0..=100.0;
+pub const RECOMMENDED_CLOUD_ECCENTRICITY: RangeInclusive<f64> = 0.15..=0.25;
+pub const RECOMMENDED_B: RangeInclusive<f64> = 1.0E-5..=1.2E-5;
+pub const RECOMMENDED_DISK_LIFETIME: RangeInclusive<f64> = 1.0E6..=1.0E7;
+pub const RECOMMENDED_DISK_VISCOSITY: RangeInclusive<f64> = 1.0E-4..=1.0E-2;
+pub const RECOMMENDED_SYSTEM_AGE: RangeInclusive<f64> = 1.0E8..=1.38E10;
+pub const RECOMMENDED_PLANET_A: RangeInclusive<f64> = 0.3..=50.0;
+pub const RECOMMENDED_PLANET_MASS: RangeInclusive<f64> =
+    PROTOPLANET_MASS..=500.0 / EARTH_MASSES_PER_SOLAR_MASS;
+
+impl AccreteConfig {
+    /// Check parameters used for planetary system generation
+    pub fn validate_system(&self, mode: ValidationMode) -> Result<(), AccreteError> {
+        validate_parameter("stellar_mass", self.stellar_mass, f64::MIN_POSITIVE..)?;
+        validate_parameter("metallicity", self.metallicity, ..)?;
+        validate_parameter(
+            "dust_density_coeff",
+            self.dust_density_coeff,
+            f64::MIN_POSITIVE..,
+        )?;
+        match self.disk_model {
+            DiskModel::Dole { alpha, n } => {
+                validate_parameter("disk_model.alpha", alpha, 0.0..)?;
+                validate_parameter("disk_model.n", n, f64::MIN_POSITIVE..)?;
+            }
+            DiskModel::MinimumMassSolarNebula { exponent } => {
+                validate_parameter("disk_model.exponent", exponent, ..)?;
+            }
+            DiskModel::Hayashi => (),
+        }
+        validate_parameter("k", self.k, 1.0..)?;
+        validate_parameter("cloud_eccentricity", self.cloud_eccentricity, 0.0..1.0)?;
+        validate_parameter("b", self.b, f64::MIN_POSITIVE..)?;
+
+        if let Some(companion_star) = &self.companion_star {
+            validate_parameter(
+                "companion_star.stellar_mass",
+                companion_star.stellar_mass,
+                f64::MIN_POSITIVE..,
+            )?;
+            validate_parameter(
+                "companion_star.separation",
+                companion_star.separation,
+                f64::MIN_POSITIVE..,
+            )?;
+            validate_parameter(
+                "companion_star.eccentricity",
+                companion_star.eccentricity,
+                0.0..1.0,
+            )?;
+        }
+
+        if let Some(migration) = &self.migration {
+            validate_parameter("migration.disk_lifetime", migration.disk_lifetime, 0.0..)?;
+            validate_parameter(
+                "migration.viscosity",
+                migration.viscosity,
+                f64::MIN_POSITIVE..,
+            )?;
+        }
+
+        if let Some(stability) = &self.stability {
+            validate_parameter(
+                "stability.integration_years",
+                stability.integration_years,
+                0.0..,
+            )?;
+        }
+
+        if let Some(system_age) = self.system_age {
+            validate_parameter("system_age", system_age, 0.0..)?;
+        }
+
+        if mode == ValidationMode::Strict {
+            validate_recommended("metallicity", self.metallicity, RECOMMENDED_METALLICITY)?;
+            validate_recommended(
+                "dust_density_coeff",
+                self.dust_density_coeff,
+                RECOMMENDED_DUST_DENSITY_COEFF,
+            )?;
+            if let DiskModel::MinimumMassSolarNebula { exponent } = self.disk_model {
+                validate_recommended(
+                    "disk_model.exponent",
+                    exponent,
+                    RECOMMENDED_DISK_DENSITY_EXPONENT,
+                )?;
+            }
+            validate_recommended("k", self.k, RECOMMENDED_K)?;
+            validate_recommended(
+                "cloud_eccentricity",
+                self.cloud_eccentricity,
+                RECOMMENDED_CLOUD_ECCENTRICITY,
+            )?;
+            validate_recommended("b", self.b, RECOMMENDED_B)?;
+            if let Some(migration) = &self.migration {
+                validate_recommended(
+                    "migration.disk_lifetime",
+                    migration.disk_lifetime,
+                    RECOMMENDED_DISK_LIFETIME,
+                )?;
+                validate_recommended(
+                    "migration.viscosity",
+                    migration.viscosity,
+                    RECOMMENDED_DISK_VISCOSITY,
+                )?;
+            }
+            if let Some(system_age) = self.system_age {
+                validate_recommended("system_age", system_age, RECOMMENDED_SYSTEM_AGE)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check parameters used for standalone planet generation
+    pub fn validate_planet(&self, mode: ValidationMode) -> Result<(), AccreteError> {
+        validate_parameter("stellar_mass", self.stellar_mass, f64::MIN_POSITIVE..)?;
+        validate_parameter(
+            "stellar_luminosity",
+            self.stellar_luminosity,
+            f64::MIN_POSITIVE..,
+        )?;
+        validate_parameter("planet_a", self.planet_a, f64::MIN_POSITIVE..)?;
+        validate_parameter("planet_e", self.planet_e, 0.0..1.0)?;
+        validate_parameter("planet_mass", self.planet_mass, f64::MIN_POSITIVE..)?;
+        if let Some(system_age) = self.system_age {
+            validate_parameter("system_age", system_age, 0.0..)?;
+        }
+
+        if mode == ValidationMode::Strict {
+            validate_recommended("planet_a", self.planet_a, RECOMMENDED_PLANET_A)?;
+            validate_recommended("planet_mass", self.planet_mass, RECOMMENDED_PLANET_MASS)?;
+            if let Some(system_age) = self.system_age {
+                validate_recommended("system_age", system_age, RECOMMENDED_SYSTEM_AGE)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Overwrite planetary system parameters with preset values
+    pub fn apply_preset(&mut self, preset: Preset) {
+        let (
+            stellar_mass,
+            metallicity,
+            dust_density_coeff,
+            k,
+            cloud_eccentricity,
+            b,
+            post_accretion_intensity,
+        ) = match preset {
+            Preset::SolLike => (1.0, 0.0, DUST_DENSITY_COEFF, K, 0.2, B, 1000),
+            Preset::MDwarfCompact => (0.3, 0.0, DUST_DENSITY_COEFF, K, 0.15, B, 1000),
+            Preset::Dole1970Original => (1.0, 0.0, DUST_DENSITY_COEFF, K, 0.25, B, 0),
+            Preset::HighMetallicity => (1.0, 0.3, DUST_DENSITY_COEFF, K, 0.2, B, 1000),
+        };
+
+        self.stellar_mass = stellar_mass;
+        self.metallicity = metallicity;
+        self.dust_density_coeff = dust_density_coeff;
+        self.disk_model = DiskModel::default();
+        self.k = k;
+        self.cloud_eccentricity = cloud_eccentricity;
+        self.b = b;
+        self.post_accretion_intensity = post_accretion_intensity;
+        self.companion_star = None;
+    }
+}
+
+fn validate_recommended(
+    name: &'static str,
+    value: f64,
+    range: RangeInclusive<f64>,
+) -> Result<(), AccreteError> {
+    match range.contains(&value) {
+        true => Ok(()),
+        false => Err(AccreteError::OutOfRecommendedRange {
+            name,
+            value,
+            min: *range.start(),
+            max: *range.end(),
+        }),
+    }
+}
+
+/// Builder for [Accrete] with configuration checks.
+/// Parameters not set explicitly keep values of `Accrete::new(seed)`.
+///
+/// ```
+/// use accrete::{AccreteBuilder, Preset, ValidationMode};
+///
+/// let mut accrete = AccreteBuilder::new(1)
+///     .preset(Preset::SolLike)
+///     .k(60.0)
+///     .validation(ValidationMode::Strict)
+///     .build()
+///     .expect("Invalid configuration");
+/// let system = accrete.planetary_system();
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct AccreteBuilder {
+    seed: u64,
+    config: AccreteConfig,
+    validation: ValidationMode,
+}
+
+impl AccreteBuilder {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            config: Accrete::new(seed).config(),
+            validation: ValidationMode::Strict,
+        }
+    }
+
+    pub fn from_config(seed: u64, config: AccreteConfig) -> Self {
+        Self {
+            seed,
+            config,
+            validation: ValidationMode::Strict,
+        }
+    }
+
+    pub fn validation(mut self, validation: ValidationMode) -> Self {
+        self.validation = validation;
+        self
+    }
+
+    pub fn preset(mut self, preset: Preset) -> Self {
+        self.config.apply_preset(preset);
+        self
+    }
+
+    pub fn stellar_mass(mut self, stellar_mass: f64) -> Self {
+        self.config.stellar_mass = stellar_mass;
+        self
+    }
+
+    pub fn metallicity(mut self, metallicity: f64) -> Self {
+        self.config.metallicity = metallicity;
+        self
+    }
+
+    pub fn dust_density_coeff(mut self, dust_density_coeff: f64) -> Self {
+        self.config.dust_density_coeff = dust_density_coeff;
+        self
+    }
+
+    pub fn disk_model(mut self, disk_model: DiskModel) -> Self {
+        self.config.disk_model = disk_model;
+        self
+    }
+
+    pub fn condensation(mut self, condensation: CondensationConfig) -> Self {
+        self.config.condensation = Some(condensation);
+        self
+    }
+
+    pub fn k(mut self, k: f64) -> Self {
+        self.config.k = k;
+        self
+    }
+
+    pub fn cloud_eccentricity(mut self, cloud_eccentricity: f64) -> Self {
+        self.config.cloud_eccentricity = cloud_eccentricity;
+        self
+    }
+
+    pub fn b(mut self, b: f64) -> Self {
+        self.config.b = b;
+        self
+    }
+
+    pub fn post_accretion_intensity(mut self, post_accretion_intensity: u32) -> Self {
+        self.config.post_accretion_intensity = post_accretion_intensity;
+        self
+    }
+
+    pub fn companion_star(mut self, companion_star: CompanionStarConfig) -> Self {
+        self.config.companion_star = Some(companion_star);
+        self
+    }
+
+    pub fn migration(mut self, migration: MigrationConfig) -> Self {
+        self.config.migration = Some(migration);
+        self
+    }
+
+    pub fn regular_moons(mut self, regular_moons: bool) -> Self {
+        self.config.regular_moons = regular_moons;
+        self
+    }
+
+    pub fn resonance_capture(mut self, resonance_capture: bool) -> Self {
+        self.config.resonance_capture = resonance_capture;
+        self
+    }
+
+    pub fn stability(mut self, stability: StabilityConfig) -> Self {
+        self.config.stability = Some(stability);
+        self
+    }
+
+    pub fn system_age(mut self, system_age: f64) -> Self {
+        self.config.system_age = Some(system_age);
+        self
+    }
+
+    pub fn planet_a(mut self, planet_a: f64) -> Self {
+        self.config.planet_a = planet_a;
+        self
+    }
+
+    pub fn planet_e(mut self, planet_e: f64) -> Self {
+        self.config.planet_e = planet_e;
+        self
+    }
+
+    pub fn planet_mass(mut self, planet_mass: f64) -> Self {
+        self.config.planet_mass = planet_mass;
+        self
+    }
+
+    pub fn stellar_luminosity(mut self, stellar_luminosity: f64) -> Self {
+        self.config.stellar_luminosity = stellar_luminosity;
+        self
+    }
+
+    /// Validated configuration
+    pub fn config(&self) -> Result<AccreteConfig, AccreteError> {
+        self.config.validate_system(self.validation)?;
+        self.config.validate_planet(self.validation)?;
+        Ok(self.config)
+    }
+
+    pub fn build(self) -> Result<Accrete, AccreteError> {
+        let config = self.config()?;
+        Ok(Accrete::from_config(self.seed, config))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builder_matches_seeded_accrete() {
+        let mut built = AccreteBuilder::new(1)
+            .build()
+            .expect("Failed to build Accrete");
+        let mut accrete = Accrete::new(1);
+        assert_eq!(
+            format!("{:?}", built.planetary_system()),
+            format!("{:?}", accrete.planetary_system())
+        );
+    }
+
+    #[test]
+    fn strict_mode_rejects_out_of_range() {
+        let builder = AccreteBuilder::new(1).dust_density_coeff(0.05);
+        assert!(matches!(
+            builder.build(),
+            Err(AccreteError::OutOfRecommendedRange {
+                name: "dust_density_coeff",
+                ..
+            })
+        ));
+        assert!(builder
+            .validation(ValidationMode::Permissive)
+            .build()
+            .is_ok());
+    }
+
+    #[test]
+    fn presets_pass_strict_validation() {
+        for preset in [
+            Preset::SolLike,
+            Preset::MDwarfCompact,
+            Preset::Dole1970Original,
+            Preset::HighMetallicity,
+        ] {
+            assert!(AccreteBuilder::new(1).preset(preset).build().is_ok());
+        }
+    }
+
+    #[test]
+    fn config_serialization_roundtrip() {
+        let config = AccreteBuilder::new(1)
+            .preset(Preset::MDwarfCompact)
+            .companion_star(CompanionStarConfig::new(0.1, 20.0, 0.3))
+            .config()
+            .expect("Invalid configuration");
+        let json = serde_json::to_string(&config).expect("Failed to serialize config");
+        let restored: AccreteConfig =
+            serde_json::from_str(&json).expect("Failed to deserialize config");
+        assert_eq!(config, restored);
+    }
+}
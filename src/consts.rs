@@ -18,6 +18,37 @@ pub enum SpectralClass {
     O,
 }
 
+/// [Yerkes luminosity class](https://en.wikipedia.org/wiki/Stellar_classification#Yerkes_spectral_classification)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum LuminosityClass {
+    // I
+    Supergiant,
+    // II
+    BrightGiant,
+    // III
+    Giant,
+    // IV
+    Subgiant,
+    // V, brown dwarfs included
+    MainSequence,
+    // D
+    WhiteDwarf,
+}
+
+impl LuminosityClass {
+    /// Roman numeral of class, D for white dwarfs
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            LuminosityClass::Supergiant => "I",
+            LuminosityClass::BrightGiant => "II",
+            LuminosityClass::Giant => "III",
+            LuminosityClass::Subgiant => "IV",
+            LuminosityClass::MainSequence => "V",
+            LuminosityClass::WhiteDwarf => "D",
+        }
+    }
+}
+
 pub const PRECISION_FOR_RANDOM: f64 = 10e8;
 
 pub const PI: f64 = std::f64::consts::PI;
@@ -110,6 +141,8 @@ pub const K: f64 = 50.0;
 
 /// For crit_mass calculation, 1e-5 to 1.2e-5
 pub const B: f64 = 1.2E-5;
+/// Critical core mass for runaway gas accretion ~ opacity^0.25 of envelope, grain opacity ~ heavy element content (Ikoma et al. 2000)
+pub const CRITICAL_MASS_METALLICITY_EXPONENT: f64 = 0.25;
 
 /// "A" in Dole's paper
 /// Dole's paper tests ranges between 0.00125 and 0.0015
@@ -122,6 +155,48 @@ pub const ALPHA: f64 = 5.0;
 /// Used in calculating dust density (as the nth root of the radius, r)
 pub const N: f64 = 3.0;
 
+/// Exponent of power law dust density of minimum-mass solar nebula, surface density of Weidenschilling 1977 and Hayashi 1981 falls as r^-1.5
+pub const MMSN_DENSITY_EXPONENT: f64 = 1.5;
+
+/// Jump of solid density of Hayashi 1981 nebula beyond snow line, where water ice condenses
+pub const HAYASHI_ICE_FACTOR: f64 = 4.2;
+
+/// Midplane temperature of passive disk at 1 AU around the Sun, Kelvin. Temperature falls as r^-1/2 and scales as L^1/4 (Hayashi 1981)
+pub const DISK_TEMPERATURE_1AU: f64 = 280.0;
+
+/// Condensation temperatures of ices in disk, Kelvin. Water ice line lies at 2.7 AU around the Sun as in Hayashi 1981
+pub const WATER_CONDENSATION_TEMP: f64 = 170.0;
+pub const CO2_CONDENSATION_TEMP: f64 = 80.0;
+pub const CO_CONDENSATION_TEMP: f64 = 30.0;
+
+/// Mass of water ice condensed per unit mass of rock beyond snow line, same jump of solids as in Hayashi 1981
+pub const WATER_ICE_TO_ROCK: f64 = HAYASHI_ICE_FACTOR - 1.0;
+
+/// Masses of CO2 and CO ices relative to water ice, from abundances of interstellar ices (Oberg et al. 2011)
+pub const CO2_ICE_TO_WATER_ICE: f64 = 0.6;
+pub const CO_ICE_TO_WATER_ICE: f64 = 0.4;
+
+/// Outer edge of planetesimal zone of circumplanetary disk, fraction of Hill radius of gas giant. Regular moons of Jupiter and Saturn lie within ~0.05 Hill radii.
+pub const CIRCUMPLANETARY_DISK_RADIUS: f64 = 0.1;
+/// "A" of Dole's cloud for circumplanetary disk accreted around body of one solar mass, mass of regular moons is ~1e-4 of planet mass (Canup & Ward 2006)
+pub const CIRCUMPLANETARY_DUST_DENSITY_COEFF: f64 = 1.5 * DUST_DENSITY_COEFF;
+
+/// Mass fraction of iron in accreted rock, same as mass fraction of Earth's core
+pub const IRON_FRACTION_OF_ROCK: f64 = 0.325;
+
+/// Catastrophic disruption threshold of gravity dominated bodies, Q*_RD = c* * 4/5 * pi * rho1 * G * R_C1^2 (Leinhardt & Stewart 2012).
+/// c* is dimensionless, rho1 is density of 1 g/cc
+pub const DISRUPTION_COEFF: f64 = 1.9;
+/// Above this specific impact energy relative to Q*_RD largest remnant follows supercatastrophic power law
+pub const SUPERCATASTROPHIC_ENERGY: f64 = 1.8;
+
+/// Mass-radius relations of rock, iron and ice planets are fitted down to this mass, Earth masses (Fortney et al. 2007)
+pub const MIN_FITTED_SOLID_MASS: f64 = 0.01;
+/// Planets lighter than this mass, Earth masses, with hydrogen-helium envelope are sub-Neptunes, envelope radius follows Lopez & Fortney 2014
+pub const SUB_NEPTUNE_MAX_MASS: f64 = 20.0;
+/// Envelope is not inflated by stellar irradiation of young system younger than this age, years
+pub const MIN_ENVELOPE_AGE: f64 = 1.0E8;
+
 /// Used in day_hours-length calcs (cm2/sec2 g)
 pub const J: f64 = 1.46E-19;
 
@@ -201,11 +276,9 @@ pub const EARTH_SURF_PRES_IN_MMHG: f64 = 760.0;
 /// Pounds per square inch
 pub const EARTH_SURF_PRES_IN_PSI: f64 = 14.696;
 
-/// EARTH_SURF_PRES_IN_MMHG;
-pub const MMHG_TO_MILLIBARS: f64 = EARTH_SURF_PRES_IN_MILLIBARS;
+pub const MMHG_TO_MILLIBARS: f64 = EARTH_SURF_PRES_IN_MILLIBARS / EARTH_SURF_PRES_IN_MMHG;
 
-/// EARTH_SURF_PRES_IN_PSI;
-pub const PSI_TO_MILLIBARS: f64 = EARTH_SURF_PRES_IN_MILLIBARS;
+pub const PSI_TO_MILLIBARS: f64 = EARTH_SURF_PRES_IN_MILLIBARS / EARTH_SURF_PRES_IN_PSI;
 
 /// Dole p. 15
 pub const H20_ASSUMED_PRESSURE: f64 = 47.0 * MMHG_TO_MILLIBARS;
@@ -232,9 +305,124 @@ pub const MAX_NH3_IPP: f64 = 100.0 * PPM_PRSSURE;
 pub const MAX_O3_IPP: f64 = 0.1 * PPM_PRSSURE;
 pub const MAX_CH4_IPP: f64 = 50000.0 * PPM_PRSSURE;
 
+/// StarGen planet type thresholds
+/// Units of Earth masses
+pub const ASTEROID_MASS_LIMIT: f64 = 0.001;
+pub const SUB_JOVIAN_MASS_LIMIT: f64 = 20.0;
+/// Units of millibars
+pub const MIN_PLANET_TYPE_PRESSURE: f64 = 1.0;
+pub const MARTIAN_PRESSURE_LIMIT: f64 = 250.0;
+pub const GAS_DWARF_PRESSURE_LIMIT: f64 = 6000.0;
+/// Atmosphere retaining hydrogen, molecular weight
+pub const GAS_DWARF_MOLECULE_WEIGHT: f64 = 2.0;
+
+/// Black body temperature of zero albedo planet at 1 AU from Sun, Kelvin
+pub const BLACK_BODY_TEMP_AT_1AU: f64 = 278.6;
+/// Jupiter reference values for gas giant cooling
+/// Units of Earth masses
+pub const JUPITER_EARTH_MASSES: f64 = 317.8;
+/// Equatorial radius, km
+pub const JUPITER_RADIUS_IN_KM: f64 = 71492.0;
+/// Temperature of internal heat flux (5.4 W/m2), Kelvin
+pub const JUPITER_INTRINSIC_TEMP: f64 = 99.0;
+/// Years
+pub const JUPITER_AGE: f64 = 4.6E9;
+/// Intrinsic luminosity of gas giant ~ mass^2.64 * age^-1.3 (Burrows et al. 1997)
+pub const GAS_GIANT_COOLING_MASS_EXPONENT: f64 = 2.64;
+pub const GAS_GIANT_COOLING_AGE_EXPONENT: f64 = -1.3;
+/// Ratio of 1-bar level temperature to effective temperature, Jupiter 165K / 124K, Saturn 134K / 95K
+pub const ONE_BAR_TO_EFFECTIVE_TEMP_RATIO: f64 = 1.35;
+/// Equilibrium temperature limits of hot and warm jupiters, Kelvin
+pub const HOT_JUPITER_TEMP: f64 = 1000.0;
+pub const WARM_JUPITER_TEMP: f64 = 300.0;
+
+/// Square root of gravitational parameter of Sun, AU^(3/2) / day
+pub const GAUSSIAN_GRAVITATIONAL_CONSTANT: f64 = 0.01720209895;
+/// Kepler's equation solver, units of radians
+pub const KEPLER_TOLERANCE: f64 = 1.0E-12;
+pub const KEPLER_MAX_ITERATIONS: usize = 50;
+
+/// Time until gas disk is dissipated, years
+pub const GAS_DISK_LIFETIME: f64 = 3.0E6;
+/// Shakura-Sunyaev viscosity parameter of gas disk
+pub const DISK_VISCOSITY_ALPHA: f64 = 1.0E-3;
+/// Scale height of gas disk to distance from star
+pub const DISK_ASPECT_RATIO: f64 = 0.05;
+/// Rotation period of young star in days, gas disk is truncated at corotation radius
+pub const YOUNG_STAR_ROTATION_PERIOD: f64 = 4.0;
+/// Type I migration rate coefficient 2.7 + 1.1β of isothermal disk (Tanaka et al. 2002), for surface density slope β = 1.5
+pub const TYPE_I_MIGRATION_COEFF: f64 = 4.35;
+/// Population synthesis models reduce type I migration rate by an order of magnitude to reproduce observed planets (Ida & Lin 2008)
+pub const TYPE_I_MIGRATION_EFFICIENCY: f64 = 0.1;
+/// Time steps of migration during gas disk lifetime
+pub const MIGRATION_STEPS: usize = 1000;
+
+/// Neighbouring planets closer than 2√3 mutual Hill radii are unstable (Gladman 1993)
+pub const CRITICAL_HILL_SPACING: f64 = 3.4641016151377544;
+/// Spacing in mutual Hill radii between scattered body and its partner after encounter
+pub const SCATTERED_HILL_SPACING: f64 = 2.0 * CRITICAL_HILL_SPACING;
+/// Safronov number over which close encounters eject lighter body instead of scattering it, collisions prevail below 1.0
+pub const EJECTION_SAFRONOV_NUMBER: f64 = 5.0;
+/// N-body integration steps per orbital period at periapsis of innermost planet
+pub const NBODY_STEPS_PER_ORBIT: f64 = 50.0;
+/// Limit of N-body integration steps, integration of close-in or highly eccentric orbits is cut short
+pub const NBODY_MAX_STEPS: usize = 1_000_000;
+/// Unbound body is ejected when it is farther than this multiple of initial apoapsis of outermost planet
+pub const NBODY_EJECTION_DISTANCE: f64 = 10.0;
+
+/// Low order mean-motion resonances, p:q ratio of outer to inner orbital period
+pub const RESONANCES: [(u32, u32); 6] = [(2, 1), (3, 2), (4, 3), (5, 4), (3, 1), (5, 3)];
+/// Relative deviation of period ratio from exact resonance for bodies to be considered resonant
+pub const RESONANCE_WIDTH: f64 = 0.01;
+/// Relative deviation of period ratio from exact resonance for near-resonant bodies to be captured
+pub const RESONANCE_CAPTURE_WIDTH: f64 = 0.02;
+
+/// Number of largest bodies listed as belt members
+pub const BELT_LARGEST_MEMBERS: usize = 5;
+/// Integration steps for dust mass of belt
+pub const BELT_DUST_MASS_STEPS: usize = 100;
+
 /// Units of g/cc
 pub const EARTH_DENSITY: f64 = 5.52;
 
+/// Units of km/sec
+pub const EARTH_ESCAPE_VELOCITY_KM_PER_SEC: f64 = 11.186;
+
+/// StarGen limits age of planetary system to 6 billion years
+pub const MAX_SYSTEM_AGE: f64 = 6.0E9;
+
+/// StarGen lower limit of generated system age, years
+pub const MIN_SYSTEM_AGE: f64 = 1.0E9;
+
+/// Present age of the Sun relative to its main sequence lifetime, mass-luminosity relation gives luminosity of star at this point of its track
+pub const SUN_MAIN_SEQUENCE_AGE_FRACTION: f64 = 0.46;
+/// Brightening of star on main sequence, L(t) = L / (1 + 0.4 (1 - t / t_sun)) (Gough 1981)
+pub const MAIN_SEQUENCE_BRIGHTENING: f64 = 0.4;
+/// Duration of subgiant phase relative to main sequence lifetime
+pub const SUBGIANT_DURATION: f64 = 0.07;
+/// Growth of luminosity and radius of star from end of main sequence to base of red giant branch
+pub const SUBGIANT_LUMINOSITY_GROWTH: f64 = 1.5;
+pub const SUBGIANT_RADIUS_GROWTH: f64 = 2.0;
+/// Duration of red giant phase (red giant branch, helium burning and asymptotic giant branch) relative to main sequence lifetime
+pub const RED_GIANT_DURATION: f64 = 0.06;
+/// Luminosity at the tip of red giant branch, solar luminosities
+pub const RED_GIANT_TIP_LUMINOSITY: f64 = 2500.0;
+/// Red giant radius ~ luminosity^0.65, keeps surface temperature of giant at ~3000-4500K
+pub const RED_GIANT_RADIUS_EXPONENT: f64 = 0.65;
+/// Initial-final mass relation of white dwarfs, M_wd = 0.109 M + 0.394 (Kalirai et al. 2008)
+pub const WHITE_DWARF_MASS_SLOPE: f64 = 0.109;
+pub const WHITE_DWARF_MASS_INTERCEPT: f64 = 0.394;
+/// Units of solar masses
+pub const CHANDRASEKHAR_LIMIT: f64 = 1.44;
+/// Radius of 0.6 solar masses white dwarf in solar radii, radius ~ mass^(-1/3)
+pub const WHITE_DWARF_RADIUS: f64 = 0.0127;
+pub const WHITE_DWARF_TYPICAL_MASS: f64 = 0.6;
+/// Luminosity of 0.6 solar masses white dwarf after 1 billion years of cooling, solar luminosities. Luminosity ~ mass * age^-1.4 (Mestel 1952)
+pub const WHITE_DWARF_LUMINOSITY: f64 = 3.0E-4;
+pub const WHITE_DWARF_COOLING_EXPONENT: f64 = -1.4;
+/// Cooling age of white dwarf is limited from below to avoid infinite luminosity, years
+pub const WHITE_DWARF_MIN_COOLING_AGE: f64 = 1.0E6;
+
 /// Maximum angular inclination of dust cloud (Dole specifies as ~90 degrees)
 pub const OMEGA: f64 = PI / 2.01;
 
@@ -248,3 +436,9 @@ pub const SIGMA: f64 = 5.670374419e-8;
 
 /// Watt per 1 solar luminosity
 pub const WATT_PER_SOLAR_LUMINOSITY: f64 = 3.828e26;
+
+/// Luminosity of red giant above which it is classified as bright giant and supergiant, solar luminosities
+pub const BRIGHT_GIANT_LUMINOSITY: f64 = 5.0E3;
+pub const SUPERGIANT_LUMINOSITY: f64 = 3.0E4;
+/// White dwarf temperature index is 50400 / effective temperature
+pub const WHITE_DWARF_TEMPERATURE_INDEX: f64 = 50400.0;
@@ -0,0 +1,84 @@
+use crate::consts::*;
+use crate::structs::Planetesimal;
+
+/// Mass of largest remnant of collision of two planetesimals on orbits around central mass, solar masses (Leinhardt & Stewart 2012).
+/// Bodies approaching each other at mutual escape velocity merge perfectly, kinetic energy of their relative orbital velocity erodes the remnant.
+/// Encounter velocity is estimated from orbital eccentricities, impact is head-on.
+pub fn largest_remnant_mass(
+    target: &Planetesimal,
+    impactor: &Planetesimal,
+    central_mass: &f64,
+) -> f64 {
+    let total_mass = target.mass + impactor.mass;
+    let (larger, smaller) = match target.mass >= impactor.mass {
+        true => (target.mass, impactor.mass),
+        false => (impactor.mass, target.mass),
+    };
+    if smaller <= 0.0 {
+        return total_mass;
+    }
+
+    let mean_a = (target.a + impactor.a) / 2.0;
+    let keplerian_velocity =
+        (GRAV_CONSTANT * central_mass * SOLAR_MASS_IN_GRAMS / (mean_a * CM_PER_AU)).sqrt();
+    let encounter_velocity =
+        keplerian_velocity * (target.e.powf(2.0) + impactor.e.powf(2.0)).sqrt();
+    let reduced_mass = larger * smaller / total_mass;
+    let impact_energy = 0.5 * reduced_mass * encounter_velocity.powf(2.0) / total_mass;
+
+    // Radius of combined mass at density of 1 g/cc
+    let combined_radius = (3.0 * total_mass * SOLAR_MASS_IN_GRAMS / (4.0 * PI)).powf(1.0 / 3.0);
+    let mass_ratio = smaller / larger;
+    let disruption_energy = DISRUPTION_COEFF
+        * 0.8
+        * PI
+        * GRAV_CONSTANT
+        * combined_radius.powf(2.0)
+        * ((mass_ratio + 1.0).powf(2.0) / (4.0 * mass_ratio)).powf(2.0 / (3.0 * 0.36) - 1.0);
+
+    let relative_energy = impact_energy / disruption_energy;
+    let remnant_fraction = match relative_energy < SUPERCATASTROPHIC_ENERGY {
+        true => 1.0 - 0.5 * relative_energy,
+        false => 0.1 * (relative_energy / SUPERCATASTROPHIC_ENERGY).powf(-1.5),
+    };
+    total_mass * remnant_fraction
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+
+    fn planetesimal(a: f64, e: f64, earth_masses: f64) -> Planetesimal {
+        let mut rng = ChaChaRng::seed_from_u64(1);
+        let mut planet = Planetesimal::new(&0.3, &50.0, &mut rng);
+        planet.a = a;
+        planet.e = e;
+        planet.mass = earth_masses / EARTH_MASSES_PER_SOLAR_MASS;
+        planet
+    }
+
+    #[test]
+    fn violent_collisions_erode_remnant() {
+        let circular = largest_remnant_mass(
+            &planetesimal(1.0, 0.0, 0.5),
+            &planetesimal(1.0, 0.0, 0.5),
+            &1.0,
+        );
+        assert_eq!(circular, 1.0 / EARTH_MASSES_PER_SOLAR_MASS);
+
+        let gentle = largest_remnant_mass(
+            &planetesimal(1.0, 0.05, 0.5),
+            &planetesimal(1.0, 0.05, 0.5),
+            &1.0,
+        );
+        let violent = largest_remnant_mass(
+            &planetesimal(0.4, 0.3, 0.05),
+            &planetesimal(0.4, 0.3, 0.05),
+            &1.0,
+        );
+        assert!(gentle > 0.9 / EARTH_MASSES_PER_SOLAR_MASS);
+        assert!(violent < 0.5 * 0.1 / EARTH_MASSES_PER_SOLAR_MASS);
+    }
+}
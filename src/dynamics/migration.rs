@@ -0,0 +1,221 @@
+use crate::consts::*;
+use crate::structs::disk_profile::DiskProfile;
+use crate::structs::dust::{gas_present_at, DustBand};
+use crate::structs::Planetesimal;
+use crate::utils::*;
+
+use serde::{Deserialize, Serialize};
+
+/// Gas disk parameters for planetary migration.
+/// **disk_lifetime** - time in years until remaining gas is dissipated, gas surface density declines linearly.
+/// **viscosity** - Shakura-Sunyaev alpha parameter of disk viscosity, drives type II migration of gas giants.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct MigrationConfig {
+    pub disk_lifetime: f64,
+    pub viscosity: f64,
+}
+
+impl MigrationConfig {
+    pub fn new(disk_lifetime: f64, viscosity: f64) -> Self {
+        Self {
+            disk_lifetime,
+            viscosity,
+        }
+    }
+}
+
+impl Default for MigrationConfig {
+    fn default() -> Self {
+        Self {
+            disk_lifetime: GAS_DISK_LIFETIME,
+            viscosity: DISK_VISCOSITY_ALPHA,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum MigrationRegime {
+    // planet embedded in gas disk is pushed by density waves it excites
+    TypeI,
+    // gas giant opens gap and moves with viscous inflow of disk gas
+    TypeII,
+}
+
+/// Inner edge of gas disk, truncated by magnetosphere of young star at corotation radius. Migrating planets are parked there as hot Jupiters.
+pub fn disk_inner_edge(stellar_mass: &f64) -> f64 {
+    (stellar_mass * (YOUNG_STAR_ROTATION_PERIOD / DAYS_IN_A_YEAR).powf(2.0)).powf(1.0 / 3.0)
+}
+
+/// Surface density of gas at the beginning of migration from dust density of cloud and gas-to-dust ratio, solar masses per AU^2
+pub fn gas_surface_density(
+    disk_profile: &dyn DiskProfile,
+    dust_density_coeff: &f64,
+    k: &f64,
+    stellar_mass: &f64,
+    a: &f64,
+) -> f64 {
+    let scale_height = DISK_ASPECT_RATIO * a;
+    disk_profile.gas_to_dust_ratio(k, stellar_mass, a)
+        * disk_profile.dust_density(dust_density_coeff, stellar_mass, a)
+        * 2.0
+        * scale_height
+}
+
+/// Time in years for planet to migrate to the star at current rate. Masses are in solar masses, surface density in solar masses per AU^2.
+pub fn migration_timescale(
+    regime: &MigrationRegime,
+    mass: &f64,
+    a: &f64,
+    stellar_mass: &f64,
+    surface_density: &f64,
+    viscosity: &f64,
+) -> f64 {
+    let angular_velocity = 2.0 * PI * stellar_mass.sqrt() * a.powf(-1.5);
+    let local_disk_mass = surface_density * a.powf(2.0);
+    match regime {
+        // Tanaka et al. 2002, reduced as in population synthesis models
+        MigrationRegime::TypeI => {
+            (stellar_mass / mass) * (stellar_mass / local_disk_mass) * DISK_ASPECT_RATIO.powf(2.0)
+                / angular_velocity
+                / TYPE_I_MIGRATION_COEFF
+                / TYPE_I_MIGRATION_EFFICIENCY
+        }
+        // Viscous timescale, slowed down when planet outweighs local disk
+        MigrationRegime::TypeII => {
+            let viscous_timescale =
+                1.0 / (viscosity * DISK_ASPECT_RATIO.powf(2.0) * angular_velocity);
+            viscous_timescale * (mass / (4.0 * PI * local_disk_mass)).max(1.0)
+        }
+    }
+}
+
+/// Semi-major axis of planet after migration through remaining gas of disk. Planet moves inward while there is gas ahead of it, gas giants stop at gas cleared by other giants, all planets stop at inner edge of disk.
+pub fn migrate(
+    planet: &Planetesimal,
+    dust_bands: &[DustBand],
+    stellar_mass: &f64,
+    disk_profile: &dyn DiskProfile,
+    dust_density_coeff: &f64,
+    k: &f64,
+    cloud_eccentricity: &f64,
+    config: &MigrationConfig,
+) -> (f64, MigrationRegime) {
+    let regime = match planet.is_gas_giant {
+        true => MigrationRegime::TypeII,
+        false => MigrationRegime::TypeI,
+    };
+    let inner_edge = disk_inner_edge(stellar_mass);
+    let dt = config.disk_lifetime / MIGRATION_STEPS as f64;
+    let mut a = planet.a;
+
+    for step in 0..MIGRATION_STEPS {
+        // Gas giant has cleared gas of its own zone
+        let gas_ahead = match regime {
+            MigrationRegime::TypeI => a,
+            MigrationRegime::TypeII => {
+                inner_swept_limit(&a, &planet.e, &planet.mass, cloud_eccentricity)
+            }
+        };
+        if a <= inner_edge || !gas_present_at(dust_bands, &gas_ahead) {
+            break;
+        }
+
+        let remaining_gas = 1.0 - step as f64 / MIGRATION_STEPS as f64;
+        let surface_density =
+            gas_surface_density(disk_profile, dust_density_coeff, k, stellar_mass, &a)
+                * remaining_gas;
+        let timescale = migration_timescale(
+            &regime,
+            &planet.mass,
+            &a,
+            stellar_mass,
+            &surface_density,
+            &config.viscosity,
+        );
+        a = (a * (-dt / timescale).exp()).max(inner_edge);
+    }
+
+    (a, regime)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events_log::accrete_event::AccreteEvent;
+    use crate::events_log::accrete_replay::AccreteReplay;
+    use crate::AccreteBuilder;
+
+    #[test]
+    fn gas_giants_migrate_inward() {
+        let mut accrete = AccreteBuilder::new(1)
+            .migration(MigrationConfig::default())
+            .build()
+            .expect("Invalid configuration");
+        accrete
+            .planetary_system()
+            .expect("Failed to generate planetary system");
+        let migrations = accrete
+            .events_log
+            .iter()
+            .filter_map(|event| match event {
+                AccreteEvent::PlanetesimalMigrated {
+                    from_a,
+                    regime,
+                    result,
+                    ..
+                } => Some((*from_a, *regime, result)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert!(!migrations.is_empty());
+        for (from_a, regime, result) in migrations {
+            assert!(result.a < from_a);
+            assert!(result.a >= disk_inner_edge(&accrete.stellar_mass) - f64::EPSILON);
+            assert_eq!(regime == MigrationRegime::TypeII, result.is_gas_giant);
+        }
+
+        // Migration events can be replayed
+        let steps = accrete.events_log.len();
+        let mut replay = AccreteReplay::new(accrete.events_log).expect("Failed to replay events");
+        assert!(replay.seek(steps - 1).is_ok());
+    }
+
+    #[test]
+    fn type_two_migration_is_slowed_by_massive_planet() {
+        let fast = migration_timescale(
+            &MigrationRegime::TypeII,
+            &1.0e-4,
+            &5.0,
+            &1.0,
+            &1.0e-5,
+            &1.0e-3,
+        );
+        let slow = migration_timescale(
+            &MigrationRegime::TypeII,
+            &1.0e-2,
+            &5.0,
+            &1.0,
+            &1.0e-5,
+            &1.0e-3,
+        );
+        assert!(slow > fast);
+        // Type I is faster for heavier planets
+        let light = migration_timescale(
+            &MigrationRegime::TypeI,
+            &1.0e-7,
+            &1.0,
+            &1.0,
+            &1.0e-5,
+            &1.0e-3,
+        );
+        let heavy = migration_timescale(
+            &MigrationRegime::TypeI,
+            &1.0e-5,
+            &1.0,
+            &1.0,
+            &1.0e-5,
+            &1.0e-3,
+        );
+        assert!(heavy < light);
+    }
+}
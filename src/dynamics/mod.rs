@@ -0,0 +1,14 @@
+pub mod collision;
+pub mod migration;
+pub mod orbit;
+pub mod resonance;
+pub mod stability;
+
+pub use migration::MigrationConfig;
+pub use migration::MigrationRegime;
+pub use orbit::OrbitalElements;
+pub use orbit::StateVector;
+pub use resonance::Resonance;
+pub use resonance::ResonantChain;
+pub use stability::StabilityConfig;
+pub use stability::StabilityReport;
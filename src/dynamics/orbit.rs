@@ -0,0 +1,334 @@
+use crate::consts::*;
+
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+
+/// Orientation of orbit and position of body on it at epoch t = 0. Semi-major axis and eccentricity are kept by planetesimal itself.
+/// Planets are relative to invariable plane of the system, moons are relative to orbital plane of their planet.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub struct OrbitalElements {
+    // units of degrees
+    pub inclination: f64,
+    pub longitude_of_ascending_node: f64,
+    pub argument_of_periapsis: f64,
+    pub mean_anomaly_at_epoch: f64,
+}
+
+impl OrbitalElements {
+    /// Random orientation, inclination follows equipartition of random velocities with eccentricity (i ~ e / 2)
+    pub fn random(e: &f64, rng: &mut dyn RngCore) -> Self {
+        let inclination = (e / 2.0 * rng.gen_range(0.0..2.0)).to_degrees();
+
+        OrbitalElements {
+            inclination,
+            longitude_of_ascending_node: rng.gen_range(0.0..360.0),
+            argument_of_periapsis: rng.gen_range(0.0..360.0),
+            mean_anomaly_at_epoch: rng.gen_range(0.0..360.0),
+        }
+    }
+
+    /// Random orientation with isotropic distribution of orbit normals, half of orbits are retrograde
+    pub fn random_isotropic(rng: &mut dyn RngCore) -> Self {
+        let cos_inclination: f64 = rng.gen_range(-1.0..1.0);
+
+        OrbitalElements {
+            inclination: cos_inclination.acos().to_degrees(),
+            longitude_of_ascending_node: rng.gen_range(0.0..360.0),
+            argument_of_periapsis: rng.gen_range(0.0..360.0),
+            mean_anomaly_at_epoch: rng.gen_range(0.0..360.0),
+        }
+    }
+}
+
+/// Position in AU and velocity in AU/day, relative to central body
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub struct StateVector {
+    pub position: [f64; 3],
+    pub velocity: [f64; 3],
+}
+
+impl StateVector {
+    pub fn add(&self, other: &StateVector) -> StateVector {
+        StateVector {
+            position: [0, 1, 2].map(|i| self.position[i] + other.position[i]),
+            velocity: [0, 1, 2].map(|i| self.velocity[i] + other.velocity[i]),
+        }
+    }
+
+    /// State given in perifocal frame of orbit with given orientation, rotated to reference plane of that orbit.
+    /// Moon states relative to orbital plane of planet are rotated by orbital elements of planet.
+    pub fn rotate(&self, elements: &OrbitalElements) -> StateVector {
+        StateVector {
+            position: to_reference_frame(elements, &self.position),
+            velocity: to_reference_frame(elements, &self.velocity),
+        }
+    }
+}
+
+/// Eccentric anomaly from mean anomaly, solving Kepler's equation M = E - e sin(E) with Newton's method. Angles are in radians.
+pub fn eccentric_anomaly(mean_anomaly: &f64, e: &f64) -> f64 {
+    let mean_anomaly = mean_anomaly.rem_euclid(2.0 * PI);
+    let mut eccentric_anomaly = match *e > 0.8 {
+        true => PI,
+        false => mean_anomaly,
+    };
+    for _ in 0..KEPLER_MAX_ITERATIONS {
+        let delta = (eccentric_anomaly - e * eccentric_anomaly.sin() - mean_anomaly)
+            / (1.0 - e * eccentric_anomaly.cos());
+        eccentric_anomaly -= delta;
+        if delta.abs() < KEPLER_TOLERANCE {
+            break;
+        }
+    }
+    eccentric_anomaly
+}
+
+/// Position and velocity of body on Keplerian orbit at time t in days since epoch. Semi-major axis is in AU, masses in solar masses.
+pub fn state_vector(
+    a: &f64,
+    e: &f64,
+    elements: &OrbitalElements,
+    central_mass: &f64,
+    mass: &f64,
+    t: &f64,
+) -> StateVector {
+    let mu = GAUSSIAN_GRAVITATIONAL_CONSTANT.powf(2.0) * (central_mass + mass);
+    let mean_motion = (mu / a.powf(3.0)).sqrt();
+    let mean_anomaly = elements.mean_anomaly_at_epoch.to_radians() + mean_motion * t;
+    let eccentric_anomaly = eccentric_anomaly(&mean_anomaly, e);
+    let (sin_e, cos_e) = eccentric_anomaly.sin_cos();
+    let semi_minor = a * (1.0 - e.powf(2.0)).sqrt();
+    let eccentric_anomaly_rate = mean_motion / (1.0 - e * cos_e);
+
+    // Perifocal frame, x axis points to periapsis
+    let x = a * (cos_e - e);
+    let y = semi_minor * sin_e;
+    let vx = -a * sin_e * eccentric_anomaly_rate;
+    let vy = semi_minor * cos_e * eccentric_anomaly_rate;
+
+    StateVector {
+        position: [x, y, 0.0],
+        velocity: [vx, vy, 0.0],
+    }
+    .rotate(elements)
+}
+
+/// Vector in perifocal frame, rotated by argument of periapsis, inclination and longitude of ascending node to reference frame
+fn to_reference_frame(elements: &OrbitalElements, v: &[f64; 3]) -> [f64; 3] {
+    let (sin_w, cos_w) = elements.argument_of_periapsis.to_radians().sin_cos();
+    let (sin_o, cos_o) = elements.longitude_of_ascending_node.to_radians().sin_cos();
+    let (sin_i, cos_i) = elements.inclination.to_radians().sin_cos();
+    let [x, y, z] = *v;
+
+    [
+        (cos_o * cos_w - sin_o * sin_w * cos_i) * x - (cos_o * sin_w + sin_o * cos_w * cos_i) * y
+            + sin_o * sin_i * z,
+        (sin_o * cos_w + cos_o * sin_w * cos_i) * x + (cos_o * cos_w * cos_i - sin_o * sin_w) * y
+            - cos_o * sin_i * z,
+        sin_w * sin_i * x + cos_w * sin_i * y + cos_i * z,
+    ]
+}
+
+/// Osculating semi-major axis, eccentricity and orbit orientation from position and velocity relative to central body, state time becomes new epoch.
+/// Returns none for unbound orbit.
+pub fn elements_from_state(
+    state: &StateVector,
+    central_mass: &f64,
+    mass: &f64,
+) -> Option<(f64, f64, OrbitalElements)> {
+    let mu = GAUSSIAN_GRAVITATIONAL_CONSTANT.powf(2.0) * (central_mass + mass);
+    let StateVector {
+        position: r,
+        velocity: v,
+    } = state;
+    let r_norm = norm(r);
+    let energy = dot(v, v) / 2.0 - mu / r_norm;
+    if energy >= 0.0 {
+        return None;
+    }
+
+    let a = -mu / (2.0 * energy);
+    let h = cross(r, v);
+    let h_norm = norm(&h);
+    let v_cross_h = cross(v, &h);
+    let e_vec = [0, 1, 2].map(|i| v_cross_h[i] / mu - r[i] / r_norm);
+    let e = norm(&e_vec);
+
+    let inclination = (h[2] / h_norm).clamp(-1.0, 1.0).acos();
+    // Node line is undefined for orbits in reference plane, x axis is taken instead
+    let longitude_of_ascending_node = match h[0].hypot(h[1]) > f64::EPSILON * h_norm {
+        true => h[0].atan2(-h[1]),
+        false => 0.0,
+    };
+    let (sin_o, cos_o) = longitude_of_ascending_node.sin_cos();
+    let node = [cos_o, sin_o, 0.0];
+    // Angle from node line to vector in orbital plane, measured in direction of motion
+    let angle_from_node = |x: &[f64; 3]| (dot(&h, &cross(&node, x)) / h_norm).atan2(dot(&node, x));
+    let argument_of_periapsis = match e > f64::EPSILON {
+        true => angle_from_node(&e_vec),
+        false => 0.0,
+    };
+    let true_anomaly = angle_from_node(r) - argument_of_periapsis;
+    let eccentric_anomaly =
+        ((1.0 - e.powf(2.0)).sqrt() * true_anomaly.sin()).atan2(e + true_anomaly.cos());
+    let mean_anomaly = eccentric_anomaly - e * eccentric_anomaly.sin();
+
+    let elements = OrbitalElements {
+        inclination: inclination.to_degrees(),
+        longitude_of_ascending_node: longitude_of_ascending_node.to_degrees().rem_euclid(360.0),
+        argument_of_periapsis: argument_of_periapsis.to_degrees().rem_euclid(360.0),
+        mean_anomaly_at_epoch: mean_anomaly.to_degrees().rem_euclid(360.0),
+    };
+    Some((a, e, elements))
+}
+
+pub fn norm(v: &[f64; 3]) -> f64 {
+    dot(v, v).sqrt()
+}
+
+pub fn dot(u: &[f64; 3], v: &[f64; 3]) -> f64 {
+    u[0] * v[0] + u[1] * v[1] + u[2] * v[2]
+}
+
+fn cross(u: &[f64; 3], v: &[f64; 3]) -> [f64; 3] {
+    [
+        u[1] * v[2] - u[2] * v[1],
+        u[2] * v[0] - u[0] * v[2],
+        u[0] * v[1] - u[1] * v[0],
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Accrete;
+
+    #[test]
+    fn keplerian_orbit_state() {
+        let elements = OrbitalElements {
+            inclination: 30.0,
+            longitude_of_ascending_node: 45.0,
+            argument_of_periapsis: 90.0,
+            mean_anomaly_at_epoch: 0.0,
+        };
+        let e = 0.2;
+        let at_periapsis = state_vector(&1.0, &e, &elements, &1.0, &0.0, &0.0);
+        assert!((norm(&at_periapsis.position) - 0.8).abs() < 1e-9);
+
+        // Vis-viva equation
+        let mu = GAUSSIAN_GRAVITATIONAL_CONSTANT.powf(2.0);
+        let t = 100.0;
+        let state = state_vector(&1.0, &e, &elements, &1.0, &0.0, &t);
+        let r = norm(&state.position);
+        let v = norm(&state.velocity);
+        assert!((v.powf(2.0) - mu * (2.0 / r - 1.0)).abs() < 1e-12);
+
+        // Body returns to the same position after one orbital period
+        let period = 2.0 * PI / GAUSSIAN_GRAVITATIONAL_CONSTANT;
+        let after_period = state_vector(&1.0, &e, &elements, &1.0, &0.0, &period);
+        for i in 0..3 {
+            assert!((after_period.position[i] - at_periapsis.position[i]).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn elements_from_keplerian_state() {
+        let elements = OrbitalElements {
+            inclination: 10.0,
+            longitude_of_ascending_node: 120.0,
+            argument_of_periapsis: 300.0,
+            mean_anomaly_at_epoch: 45.0,
+        };
+        let state = state_vector(&2.5, &0.3, &elements, &1.0, &1.0e-3, &0.0);
+        let (a, e, restored) =
+            elements_from_state(&state, &1.0, &1.0e-3).expect("Failed to find bound orbit");
+        assert!((a - 2.5).abs() < 1e-9);
+        assert!((e - 0.3).abs() < 1e-9);
+        assert!((restored.inclination - elements.inclination).abs() < 1e-6);
+        assert!(
+            (restored.longitude_of_ascending_node - elements.longitude_of_ascending_node).abs()
+                < 1e-6
+        );
+        assert!((restored.argument_of_periapsis - elements.argument_of_periapsis).abs() < 1e-6);
+        assert!((restored.mean_anomaly_at_epoch - elements.mean_anomaly_at_epoch).abs() < 1e-6);
+
+        let escaping = StateVector {
+            position: [1.0, 0.0, 0.0],
+            velocity: [0.0, 0.1, 0.0],
+        };
+        assert!(elements_from_state(&escaping, &1.0, &0.0).is_none());
+    }
+
+    #[test]
+    fn moon_state_relative_to_primary_star() {
+        let mut accrete = Accrete::new(1);
+        let system = accrete
+            .planetary_system()
+            .expect("Failed to generate planetary system");
+        let planet = system
+            .planets
+            .iter()
+            .find(|p| p.moons.iter().any(|m| m.mass > 0.0))
+            .expect("Failed to find planet with moons");
+        let moon = planet
+            .moons
+            .iter()
+            .find(|m| m.mass > 0.0)
+            .expect("Failed to find moon");
+
+        let planet_state = system
+            .state_vector(&planet.id, &10.0)
+            .expect("Failed to find planet");
+        let moon_state = system
+            .state_vector(&moon.id, &10.0)
+            .expect("Failed to find moon");
+        let distance = norm(&[0, 1, 2].map(|i| moon_state.position[i] - planet_state.position[i]));
+        assert!(distance >= moon.a * (1.0 - moon.e) - 1e-12);
+        assert!(distance <= moon.a * (1.0 + moon.e) + 1e-12);
+        assert!(system.state_vector("unknown", &10.0).is_none());
+    }
+
+    #[test]
+    fn moon_orbits_in_plane_of_inclined_planet() {
+        let mut accrete = Accrete::new(1);
+        let mut system = accrete
+            .planetary_system()
+            .expect("Failed to generate planetary system");
+        let planet = system
+            .planets
+            .iter_mut()
+            .find(|p| p.moons.iter().any(|m| m.mass > 0.0))
+            .expect("Failed to find planet with moons");
+        planet.orbital_elements = OrbitalElements {
+            inclination: 60.0,
+            longitude_of_ascending_node: 30.0,
+            argument_of_periapsis: 45.0,
+            mean_anomaly_at_epoch: 0.0,
+        };
+        let moon = planet
+            .moons
+            .iter_mut()
+            .find(|m| m.mass > 0.0)
+            .expect("Failed to find moon");
+        moon.orbital_elements = OrbitalElements {
+            mean_anomaly_at_epoch: 90.0,
+            ..OrbitalElements::default()
+        };
+        let (planet_id, moon_id) = (planet.id.clone(), moon.id.clone());
+        let (sin_o, cos_o) = 30.0_f64.to_radians().sin_cos();
+        let (sin_i, cos_i) = 60.0_f64.to_radians().sin_cos();
+        let planet_normal = [sin_o * sin_i, -cos_o * sin_i, cos_i];
+
+        let planet_state = system
+            .state_vector(&planet_id, &10.0)
+            .expect("Failed to find planet");
+        let moon_state = system
+            .state_vector(&moon_id, &10.0)
+            .expect("Failed to find moon");
+        let position = [0, 1, 2].map(|i| moon_state.position[i] - planet_state.position[i]);
+        let velocity = [0, 1, 2].map(|i| moon_state.velocity[i] - planet_state.velocity[i]);
+        assert!(position[2].abs() > 0.1 * norm(&position));
+        assert!(dot(&position, &planet_normal).abs() < 1e-9 * norm(&position));
+        assert!(dot(&velocity, &planet_normal).abs() < 1e-9 * norm(&velocity));
+    }
+}
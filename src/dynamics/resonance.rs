@@ -0,0 +1,151 @@
+use crate::consts::*;
+use crate::structs::Planetesimal;
+
+use serde::{Deserialize, Serialize};
+
+/// Mean-motion resonance with neighbouring body
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Resonance {
+    pub partner_id: String,
+    // p:q ratio of orbital period of outer body to period of inner body
+    pub p: u32,
+    pub q: u32,
+    // relative deviation of period ratio from exact resonance
+    pub offset: f64,
+}
+
+/// Three or more neighbouring bodies, each in resonance with the next, like Laplace resonance of Io, Europa and Ganymede
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ResonantChain {
+    // id of planet for chain of moons, none for chain of planets
+    pub parent_id: Option<String>,
+    // ids of bodies from inner to outer
+    pub members: Vec<String>,
+    // p:q period ratios of neighbouring members
+    pub ratios: Vec<(u32, u32)>,
+}
+
+/// Ratio of orbital periods of two bodies around the same central body, from Kepler's third law
+pub fn period_ratio(inner_a: &f64, outer_a: &f64) -> f64 {
+    (outer_a / inner_a).powf(1.5)
+}
+
+/// Closest low order resonance to period ratio within relative width, with its offset
+pub fn nearest_resonance(period_ratio: &f64, width: &f64) -> Option<(u32, u32, f64)> {
+    RESONANCES
+        .iter()
+        .map(|(p, q)| (*p, *q, period_ratio / (*p as f64 / *q as f64) - 1.0))
+        .filter(|(_, _, offset)| offset.abs() <= *width)
+        .min_by(|r1, r2| r1.2.abs().total_cmp(&r2.2.abs()))
+}
+
+/// Detect mean-motion resonances between neighbouring bodies, sorted by semi-major axis. With capture enabled, near-resonant outer body is moved into exact resonance, as it would be captured during migration.
+/// Returns resonant chains of three and more bodies.
+pub fn resolve_resonances(
+    bodies: &mut [Planetesimal],
+    capture: bool,
+    parent_id: Option<&String>,
+) -> Vec<ResonantChain> {
+    let width = match capture {
+        true => RESONANCE_CAPTURE_WIDTH,
+        false => RESONANCE_WIDTH,
+    };
+    let mut chains = Vec::new();
+    let mut chain: Option<ResonantChain> = None;
+
+    for body in bodies.iter_mut() {
+        body.resonances.clear();
+    }
+
+    for i in 1..bodies.len() {
+        let (inner_bodies, outer_bodies) = bodies.split_at_mut(i);
+        let inner = &mut inner_bodies[i - 1];
+        let outer = &mut outer_bodies[0];
+
+        match nearest_resonance(&period_ratio(&inner.a, &outer.a), &width) {
+            Some((p, q, offset)) => {
+                let offset = match capture {
+                    true => {
+                        outer.set_orbit(inner.a * (p as f64 / q as f64).powf(2.0 / 3.0), outer.e);
+                        0.0
+                    }
+                    false => offset,
+                };
+                inner.resonances.push(Resonance {
+                    partner_id: outer.id.clone(),
+                    p,
+                    q,
+                    offset,
+                });
+                outer.resonances.push(Resonance {
+                    partner_id: inner.id.clone(),
+                    p,
+                    q,
+                    offset,
+                });
+
+                let chain = chain.get_or_insert_with(|| ResonantChain {
+                    parent_id: parent_id.cloned(),
+                    members: vec![inner.id.clone()],
+                    ratios: Vec::new(),
+                });
+                chain.members.push(outer.id.clone());
+                chain.ratios.push((p, q));
+            }
+            None => chains.extend(chain.take()),
+        }
+    }
+    chains.extend(chain.take());
+
+    chains
+        .into_iter()
+        .filter(|chain| chain.members.len() >= 3)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AccreteBuilder;
+
+    #[test]
+    fn nearest_low_order_resonance() {
+        assert_eq!(nearest_resonance(&2.0, &RESONANCE_WIDTH), Some((2, 1, 0.0)));
+        let (p, q, offset) =
+            nearest_resonance(&1.508, &RESONANCE_WIDTH).expect("Failed to find resonance");
+        assert_eq!((p, q), (3, 2));
+        assert!(offset > 0.0);
+        assert_eq!(nearest_resonance(&1.9, &RESONANCE_WIDTH), None);
+    }
+
+    #[test]
+    fn captured_resonances_are_exact() {
+        let mut accrete = AccreteBuilder::new(1)
+            .resonance_capture(true)
+            .build()
+            .expect("Invalid configuration");
+        let system = accrete
+            .planetary_system()
+            .expect("Failed to generate planetary system");
+        let resonant_pairs = system
+            .planets
+            .windows(2)
+            .filter_map(|w| {
+                w[0].resonances
+                    .iter()
+                    .find(|r| r.partner_id == w[1].id)
+                    .map(|r| (period_ratio(&w[0].a, &w[1].a), r))
+            })
+            .collect::<Vec<_>>();
+
+        assert!(!resonant_pairs.is_empty());
+        for (ratio, resonance) in resonant_pairs {
+            assert!((ratio - resonance.p as f64 / resonance.q as f64).abs() < 1e-9);
+            assert_eq!(resonance.offset, 0.0);
+        }
+        for chain in system.resonant_chains.iter() {
+            assert!(chain.members.len() >= 3);
+            assert_eq!(chain.ratios.len(), chain.members.len() - 1);
+        }
+    }
+}
@@ -0,0 +1,528 @@
+use crate::consts::*;
+use crate::dynamics::orbit::*;
+use crate::structs::Planetesimal;
+
+use serde::{Deserialize, Serialize};
+
+/// Stability analysis parameters.
+/// **resolve** - resolve unstable configurations by ejection, collision or scattering of planets, otherwise instabilities are only reported.
+/// **integration_years** - duration of N-body integration of planets and primary star in years. With 0.0 only Hill spacing of neighbouring planets is checked.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct StabilityConfig {
+    pub resolve: bool,
+    pub integration_years: f64,
+}
+
+impl StabilityConfig {
+    pub fn new(resolve: bool, integration_years: f64) -> Self {
+        Self {
+            resolve,
+            integration_years,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum InstabilityKind {
+    // body leaves the system on unbound orbit
+    Ejection,
+    // body merges with its partner
+    Collision,
+    // body is thrown to another orbit by close encounter with its partner
+    Scattering,
+}
+
+/// Planet affected by instability
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Instability {
+    pub kind: InstabilityKind,
+    pub body_id: String,
+    // heavier body responsible for instability, none if body was ejected without close encounter
+    pub partner_id: Option<String>,
+}
+
+/// Separation of neighbouring planets
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HillSpacing {
+    pub inner_id: String,
+    pub outer_id: String,
+    // difference of semi-major axes in mutual Hill radii
+    pub spacing: f64,
+    pub is_stable: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StabilityReport {
+    // neighbouring planets orbiting the same central body, after instabilities are resolved
+    pub hill_spacings: Vec<HillSpacing>,
+    // instabilities in order of occurrence
+    pub instabilities: Vec<Instability>,
+    // units of years, 0.0 if orbits were not integrated, shorter than requested if step limit was reached
+    pub integration_years: f64,
+    // instabilities were resolved and planets were changed accordingly
+    pub resolved: bool,
+}
+
+impl StabilityReport {
+    /// No instabilities found in initial configuration
+    pub fn is_stable(&self) -> bool {
+        self.instabilities.is_empty()
+    }
+}
+
+/// Final state of N-body integration
+#[derive(Debug, Clone, PartialEq)]
+pub struct Integration {
+    // instabilities in order of occurrence, scattering of surviving bodies is listed at the end
+    pub instabilities: Vec<Instability>,
+    // ids of surviving bodies with their position and velocity relative to central body
+    pub states: Vec<(String, StateVector)>,
+    // units of years, shorter than requested if step limit was reached
+    pub years: f64,
+}
+
+/// Mutual Hill radius of two bodies, AU. Masses are in solar masses.
+pub fn mutual_hill_radius(a1: &f64, m1: &f64, a2: &f64, m2: &f64, central_mass: &f64) -> f64 {
+    ((m1 + m2) / (3.0 * central_mass)).powf(1.0 / 3.0) * (a1 + a2) / 2.0
+}
+
+/// Spacing of neighbouring planets, sorted by semi-major axis
+pub fn hill_spacings(planets: &[Planetesimal], central_mass: &f64) -> Vec<HillSpacing> {
+    planets
+        .windows(2)
+        .map(|w| {
+            let (inner, outer) = (&w[0], &w[1]);
+            let spacing = (outer.a - inner.a)
+                / mutual_hill_radius(&inner.a, &inner.mass, &outer.a, &outer.mass, central_mass);
+            HillSpacing {
+                inner_id: inner.id.clone(),
+                outer_id: outer.id.clone(),
+                spacing,
+                is_stable: spacing >= CRITICAL_HILL_SPACING,
+            }
+        })
+        .collect()
+}
+
+/// Safronov number, half of squared ratio of escape velocity from body surface to its orbital velocity. Radius is in km.
+pub fn safronov_number(mass: &f64, radius: &f64, a: &f64, central_mass: &f64) -> f64 {
+    mass / central_mass * a * KM_PER_AU / radius
+}
+
+/// Outcome of close encounters with heavier body: small bodies collide, massive bodies on wide orbits eject their neighbours
+pub fn encounter_outcome(heavier: &Planetesimal, central_mass: &f64) -> InstabilityKind {
+    let safronov_number = safronov_number(&heavier.mass, &heavier.radius, &heavier.a, central_mass);
+    match safronov_number {
+        n if n < 1.0 => InstabilityKind::Collision,
+        n if n < EJECTION_SAFRONOV_NUMBER => InstabilityKind::Scattering,
+        _ => InstabilityKind::Ejection,
+    }
+}
+
+/// Instabilities expected from Hill spacing of neighbouring planets, lighter planet of unstable pair is affected
+pub fn hill_instabilities(planets: &[Planetesimal], central_mass: &f64) -> Vec<Instability> {
+    planets
+        .windows(2)
+        .zip(hill_spacings(planets, central_mass))
+        .filter(|(_, spacing)| !spacing.is_stable)
+        .map(|(w, _)| {
+            let (heavier, lighter) = match w[0].mass >= w[1].mass {
+                true => (&w[0], &w[1]),
+                false => (&w[1], &w[0]),
+            };
+            Instability {
+                kind: encounter_outcome(heavier, central_mass),
+                body_id: lighter.id.clone(),
+                partner_id: Some(heavier.id.clone()),
+            }
+        })
+        .collect()
+}
+
+/// Orbit of body scattered by heavier body to stable spacing from it. Former distance of body becomes periapsis of outward scattered orbit or apoapsis of inward scattered one.
+/// Returns semi-major axis and eccentricity.
+pub fn scattered_orbit(
+    body: &Planetesimal,
+    heavier: &Planetesimal,
+    central_mass: &f64,
+) -> (f64, f64) {
+    let x = ((body.mass + heavier.mass) / (3.0 * central_mass)).powf(1.0 / 3.0)
+        * SCATTERED_HILL_SPACING
+        / 2.0;
+    let a = match body.a >= heavier.a {
+        true => heavier.a * (1.0 + x) / (1.0 - x),
+        false => heavier.a * (1.0 - x) / (1.0 + x),
+    };
+    let e = (a - body.a).abs() / a;
+    (a, e)
+}
+
+#[derive(Debug, Clone)]
+struct Body {
+    id: String,
+    // units of solar masses
+    mass: f64,
+    // units of AU
+    radius: f64,
+    position: [f64; 3],
+    velocity: [f64; 3],
+    // heavier body of last close encounter
+    encounter_id: Option<String>,
+}
+
+/// Integrate orbits of planets around central body with kick-drift-kick leapfrog. Bodies merge when they touch, close encounters inside of mutual Hill radius scatter lighter body, unbound bodies far from central body are ejected.
+/// Planets must have oriented orbits. Duration is in years, number of steps is limited by NBODY_MAX_STEPS.
+pub fn integrate(planets: &[Planetesimal], central_mass: &f64, years: &f64) -> Integration {
+    let g = GAUSSIAN_GRAVITATIONAL_CONSTANT.powf(2.0);
+    let mut bodies = vec![Body {
+        id: String::new(),
+        mass: *central_mass,
+        radius: 0.0,
+        position: [0.0; 3],
+        velocity: [0.0; 3],
+        encounter_id: None,
+    }];
+    for planet in planets.iter() {
+        let state = planet.state_at(central_mass, &0.0);
+        bodies.push(Body {
+            id: planet.id.clone(),
+            mass: planet.mass,
+            radius: planet.radius / KM_PER_AU,
+            position: state.position,
+            velocity: state.velocity,
+            encounter_id: None,
+        });
+    }
+
+    // Barycentric frame
+    let total_mass: f64 = bodies.iter().map(|b| b.mass).sum();
+    let center =
+        [0, 1, 2].map(|i| bodies.iter().map(|b| b.mass * b.position[i]).sum::<f64>() / total_mass);
+    let center_velocity =
+        [0, 1, 2].map(|i| bodies.iter().map(|b| b.mass * b.velocity[i]).sum::<f64>() / total_mass);
+    for body in bodies.iter_mut() {
+        body.position = [0, 1, 2].map(|i| body.position[i] - center[i]);
+        body.velocity = [0, 1, 2].map(|i| body.velocity[i] - center_velocity[i]);
+    }
+
+    let shortest_period = planets
+        .iter()
+        .map(|p| 2.0 * PI * (p.a * (1.0 - p.e)).powf(1.5) / (g * central_mass).sqrt())
+        .fold(f64::INFINITY, f64::min);
+    let ejection_distance = planets
+        .iter()
+        .map(|p| p.a * (1.0 + p.e))
+        .fold(0.0, f64::max)
+        * NBODY_EJECTION_DISTANCE;
+    let dt = shortest_period / NBODY_STEPS_PER_ORBIT;
+    let steps = match planets.is_empty() {
+        true => 0,
+        false => ((years * DAYS_IN_A_YEAR / dt).ceil() as usize).min(NBODY_MAX_STEPS),
+    };
+    let integrated_years = match steps {
+        0 => 0.0,
+        _ => (steps as f64 * dt / DAYS_IN_A_YEAR).min(*years),
+    };
+
+    let mut instabilities = Vec::new();
+    let mut acceleration = accelerations(&bodies, &g);
+    for _ in 0..steps {
+        for (body, acc) in bodies.iter_mut().zip(acceleration.iter()) {
+            body.velocity = [0, 1, 2].map(|i| body.velocity[i] + acc[i] * dt / 2.0);
+            body.position = [0, 1, 2].map(|i| body.position[i] + body.velocity[i] * dt);
+        }
+
+        close_encounters(&mut bodies, &mut instabilities);
+        let star = bodies[0].clone();
+        bodies.retain(|body| {
+            let is_ejected = body.id != star.id
+                && relative_state(body, &star)
+                    .is_some_and(|(r, energy)| r > ejection_distance && energy >= 0.0);
+            if is_ejected {
+                instabilities.push(Instability {
+                    kind: InstabilityKind::Ejection,
+                    body_id: body.id.clone(),
+                    partner_id: body.encounter_id.clone(),
+                });
+            }
+            !is_ejected
+        });
+        if bodies.len() == 1 {
+            break;
+        }
+
+        acceleration = accelerations(&bodies, &g);
+        for (body, acc) in bodies.iter_mut().zip(acceleration.iter()) {
+            body.velocity = [0, 1, 2].map(|i| body.velocity[i] + acc[i] * dt / 2.0);
+        }
+    }
+
+    let star = bodies.remove(0);
+    let mut states = Vec::new();
+    for body in bodies.into_iter() {
+        let state = StateVector {
+            position: [0, 1, 2].map(|i| body.position[i] - star.position[i]),
+            velocity: [0, 1, 2].map(|i| body.velocity[i] - star.velocity[i]),
+        };
+        let is_bound = elements_from_state(&state, central_mass, &body.mass).is_some();
+        match (is_bound, body.encounter_id) {
+            (false, partner_id) => instabilities.push(Instability {
+                kind: InstabilityKind::Ejection,
+                body_id: body.id,
+                partner_id,
+            }),
+            (true, partner_id) => {
+                if partner_id.is_some() {
+                    instabilities.push(Instability {
+                        kind: InstabilityKind::Scattering,
+                        body_id: body.id.clone(),
+                        partner_id,
+                    });
+                }
+                states.push((body.id, state));
+            }
+        }
+    }
+
+    Integration {
+        instabilities,
+        states,
+        years: integrated_years,
+    }
+}
+
+/// Distance to central body and specific orbital energy relative to it
+fn relative_state(body: &Body, central_body: &Body) -> Option<(f64, f64)> {
+    let r = norm(&[0, 1, 2].map(|i| body.position[i] - central_body.position[i]));
+    let v = [0, 1, 2].map(|i| body.velocity[i] - central_body.velocity[i]);
+    let mu = GAUSSIAN_GRAVITATIONAL_CONSTANT.powf(2.0) * (central_body.mass + body.mass);
+    match r > 0.0 {
+        true => Some((r, dot(&v, &v) / 2.0 - mu / r)),
+        false => None,
+    }
+}
+
+fn accelerations(bodies: &[Body], g: &f64) -> Vec<[f64; 3]> {
+    let mut acceleration = vec![[0.0; 3]; bodies.len()];
+    for i in 0..bodies.len() {
+        for j in (i + 1)..bodies.len() {
+            let d = [0, 1, 2].map(|k| bodies[j].position[k] - bodies[i].position[k]);
+            let r = norm(&d);
+            let factor = g / r.powf(3.0);
+            for k in 0..3 {
+                acceleration[i][k] += factor * bodies[j].mass * d[k];
+                acceleration[j][k] -= factor * bodies[i].mass * d[k];
+            }
+        }
+    }
+    acceleration
+}
+
+/// Merge touching planets conserving momentum, remember heavier partner of planets passing inside of mutual Hill radius
+fn close_encounters(bodies: &mut Vec<Body>, instabilities: &mut Vec<Instability>) {
+    let star = bodies[0].clone();
+    let mut i = 1;
+    'bodies: while i < bodies.len() {
+        let mut j = i + 1;
+        while j < bodies.len() {
+            let (heavier, lighter) = match bodies[i].mass >= bodies[j].mass {
+                true => (i, j),
+                false => (j, i),
+            };
+            let distance = norm(&[0, 1, 2].map(|k| bodies[j].position[k] - bodies[i].position[k]));
+            let hill_radius = mutual_hill_radius(
+                &norm(&[0, 1, 2].map(|k| bodies[i].position[k] - star.position[k])),
+                &bodies[i].mass,
+                &norm(&[0, 1, 2].map(|k| bodies[j].position[k] - star.position[k])),
+                &bodies[j].mass,
+                &star.mass,
+            );
+
+            if distance <= bodies[i].radius + bodies[j].radius {
+                let lighter_body = bodies[lighter].clone();
+                let body = &mut bodies[heavier];
+                let mass = body.mass + lighter_body.mass;
+                for k in 0..3 {
+                    body.position[k] = (body.mass * body.position[k]
+                        + lighter_body.mass * lighter_body.position[k])
+                        / mass;
+                    body.velocity[k] = (body.mass * body.velocity[k]
+                        + lighter_body.mass * lighter_body.velocity[k])
+                        / mass;
+                }
+                body.mass = mass;
+                body.radius =
+                    (body.radius.powf(3.0) + lighter_body.radius.powf(3.0)).powf(1.0 / 3.0);
+                instabilities.push(Instability {
+                    kind: InstabilityKind::Collision,
+                    body_id: lighter_body.id,
+                    partner_id: Some(body.id.clone()),
+                });
+                bodies.remove(lighter);
+                match lighter == i {
+                    true => continue 'bodies,
+                    false => continue,
+                }
+            }
+
+            if distance < hill_radius {
+                bodies[lighter].encounter_id = Some(bodies[heavier].id.clone());
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AccreteBuilder, CompanionStarConfig};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn hill_spacing_of_close_pair() {
+        // Earth and Mars are stable, two Earth-mass planets 0.01 AU apart are not
+        let earth_mars = mutual_hill_radius(&1.0, &3.0e-6, &1.52, &3.2e-7, &1.0);
+        assert!((1.52 - 1.0) / earth_mars > CRITICAL_HILL_SPACING);
+        let close_pair = mutual_hill_radius(&1.0, &3.0e-6, &1.01, &3.0e-6, &1.0);
+        assert!((1.01 - 1.0) / close_pair < CRITICAL_HILL_SPACING);
+
+        // Jupiter at 5.2 AU
+        let jupiter = safronov_number(&9.55e-4, &71492.0, &5.2, &1.0);
+        assert!(jupiter > EJECTION_SAFRONOV_NUMBER);
+        let earth = safronov_number(&3.0e-6, &6371.0, &1.0, &1.0);
+        assert!(earth < 1.0);
+    }
+
+    #[test]
+    fn resolved_systems_are_stable() {
+        for seed in 0..5 {
+            let mut accrete = AccreteBuilder::new(seed)
+                .stability(StabilityConfig::new(true, 0.0))
+                .build()
+                .expect("Invalid configuration");
+            let system = accrete
+                .planetary_system()
+                .expect("Failed to generate planetary system");
+            let report = system.stability.expect("Failed to find stability report");
+            assert!(report.hill_spacings.iter().all(|s| s.is_stable));
+            assert_eq!(report.hill_spacings.len() + 1, system.planets.len());
+            assert!(report
+                .instabilities
+                .iter()
+                .filter(|i| i.kind != InstabilityKind::Scattering)
+                .all(|i| system.planets.iter().all(|p| p.id != i.body_id)));
+        }
+    }
+
+    #[test]
+    fn report_describes_final_planets() {
+        for seed in 1..8 {
+            let mut accrete = AccreteBuilder::new(seed)
+                .stellar_mass(1.0)
+                .stability(StabilityConfig::new(true, 0.0))
+                .resonance_capture(true)
+                .system_age(1.2e10)
+                .build()
+                .expect("Invalid configuration");
+            let system = accrete
+                .planetary_system()
+                .expect("Failed to generate planetary system");
+            let report = system.stability.expect("Failed to find stability report");
+            let exists = |id: &String| system.planets.iter().any(|p| &p.id == id);
+            assert_eq!(report.hill_spacings.len() + 1, system.planets.len());
+            assert!(report
+                .hill_spacings
+                .iter()
+                .all(|s| exists(&s.inner_id) && exists(&s.outer_id)));
+            assert!(system
+                .planets
+                .iter()
+                .flat_map(|p| p.resonances.iter())
+                .all(|r| exists(&r.partner_id)));
+        }
+    }
+
+    #[test]
+    fn circumbinary_planets_orbit_both_stars() {
+        let mut accrete = AccreteBuilder::new(1)
+            .companion_star(CompanionStarConfig::new(0.5, 2.0, 0.0))
+            .stability(StabilityConfig::new(false, 100.0))
+            .system_age(1.0e9)
+            .build()
+            .expect("Invalid configuration");
+        let system = accrete
+            .planetary_system()
+            .expect("Failed to generate planetary system");
+        let report = system.stability.expect("Failed to find stability report");
+        let companion = system
+            .companion_star
+            .expect("Failed to find companion star");
+        let is_circumbinary = |id: &String| {
+            system
+                .planets
+                .iter()
+                .any(|p| &p.id == id && companion.is_circumbinary(&p.a))
+        };
+
+        assert!(system.planets.iter().any(|p| is_circumbinary(&p.id)));
+        assert!(report
+            .hill_spacings
+            .iter()
+            .all(|s| is_circumbinary(&s.inner_id) == is_circumbinary(&s.outer_id)));
+        assert!(report
+            .instabilities
+            .iter()
+            .filter(|i| is_circumbinary(&i.body_id))
+            .all(|i| i.kind != InstabilityKind::Ejection));
+    }
+
+    fn planet(id: &str, a: f64, mass: f64, radius: f64) -> Planetesimal {
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let mut planet = Planetesimal::new(&0.3, &50.0, &mut rng);
+        planet.id = id.to_string();
+        planet.set_orbit(a, 0.0);
+        planet.mass = mass;
+        planet.radius = radius;
+        planet
+    }
+
+    #[test]
+    fn integration_of_planetary_orbits() {
+        // Earth keeps its orbit
+        let earth = planet("earth", 1.0, 3.0e-6, 6371.0);
+        let integration = integrate(&[earth], &1.0, &10.0);
+        assert!(integration.instabilities.is_empty());
+        let (id, state) = &integration.states[0];
+        let (a, e, _) =
+            elements_from_state(state, &1.0, &3.0e-6).expect("Failed to find bound orbit");
+        assert_eq!(id, "earth");
+        assert!((a - 1.0).abs() < 1e-4);
+        assert!(e < 1e-3);
+
+        // Two Jupiters on neighbouring orbits can't survive
+        let jupiters = [
+            planet("inner", 5.0, 9.55e-4, 71492.0),
+            planet("outer", 5.2, 9.55e-4, 71492.0),
+        ];
+        let integration = integrate(&jupiters, &1.0, &100.0);
+        assert!(!integration.instabilities.is_empty());
+    }
+
+    #[test]
+    fn integration_steps_are_limited() {
+        // Hot Jupiter needs ~50 steps per 4 days, billion years is cut short
+        let hot_jupiter = planet("hot", 0.05, 9.55e-4, 71492.0);
+        let integration = integrate(&[hot_jupiter], &1.0, &1.0e9);
+        let period_days = 2.0 * PI * 0.05_f64.powf(1.5) / GAUSSIAN_GRAVITATIONAL_CONSTANT;
+        let max_years =
+            NBODY_MAX_STEPS as f64 * period_days / NBODY_STEPS_PER_ORBIT / DAYS_IN_A_YEAR;
+        assert!((integration.years - max_years).abs() < 1e-6 * max_years);
+        assert_eq!(integration.states.len(), 1);
+
+        let earth = planet("earth", 1.0, 3.0e-6, 6371.0);
+        assert_eq!(integrate(&[earth], &1.0, &10.0).years, 10.0);
+    }
+}
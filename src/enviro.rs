@@ -1,6 +1,10 @@
 use rand::RngCore;
 
 use crate::consts::*;
+use crate::structs::atmosphere::*;
+use crate::structs::composition::*;
+use crate::structs::habitability::*;
+use crate::structs::planet_type::*;
 use crate::structs::planetesimal::*;
 use crate::utils::*;
 
@@ -71,6 +75,20 @@ pub fn kothari_radius(mass: &f64, giant: &bool, zone: &i32) -> f64 {
     float_to_precision(temp)
 }
 
+/// Returns the radius of the planet in kilometers. Gas giants heavier than sub-Neptunes follow Kothari's radius, radius of other bodies is derived from their iron, silicate and ice inventory.
+/// Envelope of sub-Neptunes depends on stellar flux and age of the system and is added later, when planetary environment is derived.
+pub fn bulk_radius(mass: &f64, giant: &bool, zone: &i32, composition: &BulkComposition) -> f64 {
+    match *giant && !is_sub_neptune(mass) {
+        true => kothari_radius(mass, giant, zone),
+        false => float_to_precision(composition.solid_radius()),
+    }
+}
+
+/// Planet of mass in solar masses is light enough to be sub-Neptune, if it holds hydrogen-helium envelope
+pub fn is_sub_neptune(mass: &f64) -> bool {
+    mass * EARTH_MASSES_PER_SOLAR_MASS <= SUB_NEPTUNE_MAX_MASS
+}
+
 /// The mass passed in is in units of solar masses, and the orbital radius is in units of AU. The density is returned in units of grams/cc.
 pub fn empirical_density(
     mass: &f64,
@@ -315,6 +333,20 @@ pub fn eff_temp(ecosphere_radius: &f64, orbital_radius: &f64, albedo: &f64) -> f
         * EARTH_EFFECTIVE_TEMP
 }
 
+/// Equilibrium temperature of planet with full heat redistribution, in Kelvin. Luminosity is in solar units, the orbital radius in AU.
+pub fn equilibrium_temp(stellar_luminosity: &f64, orbital_radius: &f64, albedo: &f64) -> f64 {
+    BLACK_BODY_TEMP_AT_1AU * stellar_luminosity.powf(0.25) * (1.0 - albedo).powf(0.25)
+        / orbital_radius.sqrt()
+}
+
+/// Temperature of internal heat flux of gas giant in Kelvin, scaled from Jupiter by cooling law of intrinsic luminosity. Mass is in Earth masses, radius in km and age in years.
+pub fn gas_giant_intrinsic_temp(earth_masses: &f64, radius: &f64, age: &f64) -> f64 {
+    let luminosity_ratio = (earth_masses / JUPITER_EARTH_MASSES)
+        .powf(GAS_GIANT_COOLING_MASS_EXPONENT)
+        * (age.max(1.0) / JUPITER_AGE).powf(GAS_GIANT_COOLING_AGE_EXPONENT);
+    JUPITER_INTRINSIC_TEMP * luminosity_ratio.powf(0.25) * (JUPITER_RADIUS_IN_KM / radius).sqrt()
+}
+
 /// This is Fogg's eq.20, and is also Hart's eq.20 in his "Evolution of Earth's Atmosphere" article. The effective temperature given is in units of Kelvin, as is the rise in temperature produced by the greenhouse effect, which is returned.
 pub fn green_rise(optical_depth: f64, effective_temp: f64, surface_pressure_bar: f64) -> f64 {
     let convection_factor = EARTH_CONVECTION_FACTOR * surface_pressure_bar.powf(0.25);
@@ -428,16 +460,19 @@ pub fn iterate_surface_temp(
     let mut clouds = 0.0;
     let mut ice = 0.0;
 
-    let mut optical_depth = opacity(planet.molecule_weight, planet.surface_pressure_bar);
+    let surface_pressure_bar = planet.surface_pressure_bar.unwrap_or_default();
+    let volatile_gas_inventory = planet.volatile_gas_inventory.unwrap_or_default();
+    let boiling_point_kelvin = planet.boiling_point_kelvin.unwrap_or_default();
+
+    let mut optical_depth = opacity(planet.molecule_weight, surface_pressure_bar);
     let mut effective_temp = eff_temp(ecosphere_radius, &planet.a, &EARTH_ALBEDO);
-    let mut greenhouse_rise =
-        green_rise(optical_depth, effective_temp, planet.surface_pressure_bar);
+    let mut greenhouse_rise = green_rise(optical_depth, effective_temp, surface_pressure_bar);
     let mut surface_temp_kelvin = effective_temp + greenhouse_rise;
     let mut previous_temp = surface_temp_kelvin - 5.0;
 
     while (surface_temp_kelvin - previous_temp).abs() > 1.0 {
         previous_temp = surface_temp_kelvin;
-        water = hydrosphere_fraction(&planet.volatile_gas_inventory, &planet.radius);
+        water = hydrosphere_fraction(&volatile_gas_inventory, &planet.radius);
         clouds = cloud_fraction(
             surface_temp_kelvin,
             planet.molecule_weight,
@@ -446,18 +481,18 @@ pub fn iterate_surface_temp(
         );
         ice = ice_fraction(&water, &surface_temp_kelvin);
 
-        if surface_temp_kelvin >= planet.boiling_point_kelvin
+        if surface_temp_kelvin >= boiling_point_kelvin
             || surface_temp_kelvin <= FREEZING_POINT_OF_WATER
         {
             water = 0.0;
         }
-        albedo = planet_albedo(&water, &clouds, &ice, &planet.surface_pressure_bar, rng);
-        optical_depth = opacity(planet.molecule_weight, planet.surface_pressure_bar);
+        albedo = planet_albedo(&water, &clouds, &ice, &surface_pressure_bar, rng);
+        optical_depth = opacity(planet.molecule_weight, surface_pressure_bar);
         effective_temp = eff_temp(ecosphere_radius, &planet.a, &albedo);
-        greenhouse_rise = green_rise(optical_depth, effective_temp, planet.surface_pressure_bar);
+        greenhouse_rise = green_rise(optical_depth, effective_temp, surface_pressure_bar);
         surface_temp_kelvin = effective_temp + greenhouse_rise;
     }
-    planet.hydrosphere = water;
+    planet.hydrosphere = Some(water);
     planet.cloud_cover = clouds;
     planet.ice_cover = ice;
     planet.albedo = albedo;
@@ -488,8 +523,9 @@ pub fn get_day_night_temp_kelvin(planet: &mut Planetesimal) {
         day_hours,
         ..
     } = planet;
-    let pressmod: f64 = 1.0 / (1.0 + 20.0 * *surface_pressure_bar).sqrt();
-    let ppmod = 1.0 / (10.0 + 5.0 * *surface_pressure_bar).sqrt();
+    let surface_pressure_bar = surface_pressure_bar.unwrap_or_default();
+    let pressmod: f64 = 1.0 / (1.0 + 20.0 * surface_pressure_bar).sqrt();
+    let ppmod = 1.0 / (10.0 + 5.0 * surface_pressure_bar).sqrt();
     let tiltmod = ((*axial_tilt * PI / 180.0).cos() * (1.0 + *e).powf(2.0)).abs();
     let daymod = 1.0 / (200.0 / *day_hours + 1.0);
     let mh = (1.0 + daymod).powf(pressmod);
@@ -522,3 +558,235 @@ pub fn get_day_night_temp_kelvin(planet: &mut Planetesimal) {
     planet.max_temp_kelvin = float_to_precision(max_temp);
     planet.min_temp_kelvin = float_to_precision(min_temp);
 }
+
+/// Earth Similarity Index of planet from its radius (in Earth radii), density (g/cc), escape velocity (km/sec) and surface temperature (Kelvin). Result lies in a range 0.0-1.0, where 1.0 is Earth.
+/// [Schulze-Makuch et al. (2011)](https://doi.org/10.1089/ast.2010.0592)
+pub fn earth_similarity_index(
+    earth_radii: &f64,
+    density: &f64,
+    escape_velocity_km_per_sec: &f64,
+    surface_temp_kelvin: &f64,
+) -> f64 {
+    let parameters = [
+        (*earth_radii, 1.0, 0.57),
+        (*density, EARTH_DENSITY, 1.07),
+        (
+            *escape_velocity_km_per_sec,
+            EARTH_ESCAPE_VELOCITY_KM_PER_SEC,
+            0.70,
+        ),
+        (*surface_temp_kelvin, EARTH_AVERAGE_KELVIN, 5.58),
+    ];
+    let n = parameters.len() as f64;
+    let esi = parameters
+        .iter()
+        .map(|(x, x0, weight)| (1.0 - ((x - x0) / (x + x0)).abs()).powf(weight / n))
+        .product::<f64>();
+
+    match esi.is_finite() {
+        true => float_to_precision(esi.clamp(0.0, 1.0)),
+        false => 0.0,
+    }
+}
+
+/// Inspired partial pressure of gas, taking into account humidification of the air in the nasal passage and throat. Pressures are in millibars. From StarGen.
+pub fn inspired_partial_pressure(surface_pressure_mb: &f64, gas_pressure_mb: &f64) -> f64 {
+    let fraction = gas_pressure_mb / surface_pressure_mb;
+    (surface_pressure_mb - H20_ASSUMED_PRESSURE) * fraction
+}
+
+/// Gases of planetary atmosphere with partial pressures, sorted by diminishing pressure. This is StarGen's calculate_gases.
+/// Gas is present if it is not condensed at the lowest surface temperature and heavier than lightest molecule retained by planet. Amount of gas depends on its solar abundance, escape rate over age of the system and reactivity.
+pub fn atmosphere_composition(planet: &Planetesimal, stellar_age: &f64) -> Vec<AtmosphericGas> {
+    let Planetesimal {
+        surface_temp_kelvin,
+        night_temp_kelvin,
+        min_temp_kelvin,
+        molecule_weight,
+        escape_velocity,
+        a,
+        ..
+    } = planet;
+
+    let surface_pressure_bar = match planet.surface_pressure_bar {
+        Some(surface_pressure_bar) if surface_pressure_bar > 0.0 => surface_pressure_bar,
+        _ => return vec![],
+    };
+
+    let low_temp = night_temp_kelvin.max(*min_temp_kelvin);
+    let amounts: Vec<f64> = GASES
+        .iter()
+        .map(|gas| {
+            let boiling_point = gas.boil
+                / (373.0 * (((surface_pressure_bar + 0.001).ln() / -5050.5) + (1.0 / 373.0)));
+            if boiling_point < 0.0 || boiling_point >= low_temp || gas.weight < *molecule_weight {
+                return 0.0;
+            }
+
+            let vrms = rms_vel(&gas.weight, a);
+            let pvrms = (1.0 / (1.0 + vrms / escape_velocity)).powf(stellar_age / 1.0e9);
+            let mut abund = gas.abunds;
+            let is_temperate = *stellar_age > 2.0e9
+                && *surface_temp_kelvin > 270.0
+                && *surface_temp_kelvin < 400.0;
+            let react = match gas.num {
+                n if n == AN_AR => 0.15 * stellar_age / 4.0e9,
+                n if n == AN_HE => {
+                    // terrestrial planets keep almost no primordial gas envelope
+                    abund *= 0.001;
+                    let pres2 = 0.75 + surface_pressure_bar;
+                    (1.0 / (1.0 + gas.reactivity)).powf(stellar_age / 2.0e9 * pres2)
+                }
+                n if n == AN_O && is_temperate => {
+                    let pres2 = 0.89 + surface_pressure_bar / 4.0;
+                    (1.0 / (1.0 + gas.reactivity)).powf((stellar_age / 2.0e9).powf(0.25) * pres2)
+                }
+                n if n == AN_CO2 && is_temperate => {
+                    let pres2 = 0.75 + surface_pressure_bar;
+                    (1.0 / (1.0 + gas.reactivity)).powf((stellar_age / 2.0e9).powf(0.5) * pres2)
+                        * 1.5
+                }
+                _ => {
+                    let pres2 = 0.75 + surface_pressure_bar;
+                    (1.0 / (1.0 + gas.reactivity)).powf(stellar_age / 2.0e9 * pres2)
+                }
+            };
+            let fract = 1.0 - molecule_weight / gas.weight;
+            let amount = abund * pvrms * react * fract;
+
+            match amount.is_finite() && amount > 0.0 {
+                true => amount,
+                false => 0.0,
+            }
+        })
+        .collect();
+    let total_amount: f64 = amounts.iter().sum();
+
+    let mut atmosphere: Vec<AtmosphericGas> = GASES
+        .iter()
+        .zip(amounts.iter())
+        .filter(|(_, amount)| **amount > 0.0)
+        .map(|(gas, amount)| {
+            let partial_pressure_bar = surface_pressure_bar * amount / total_amount;
+            AtmosphericGas::new(gas, partial_pressure_bar, surface_pressure_bar)
+        })
+        .collect();
+    atmosphere.sort_by(|g1, g2| g2.partial_pressure_bar.total_cmp(&g1.partial_pressure_bar));
+
+    atmosphere
+}
+
+/// Breathability of atmosphere. Atmosphere is poisonous if any gas is over its toxicity limit and breathable if it has enough oxygen. From StarGen.
+pub fn breathability(atmosphere: &[AtmosphericGas]) -> Breathability {
+    if atmosphere.is_empty() {
+        return Breathability::None;
+    }
+    if atmosphere.iter().any(|gas| gas.is_toxic) {
+        return Breathability::Poisonous;
+    }
+
+    match atmosphere.iter().any(|gas| gas.is_breathable) {
+        true => Breathability::Breathable,
+        false => Breathability::Unbreathable,
+    }
+}
+
+/// Habitable planet has breathable atmosphere under habitable pressure, liquid water and average surface temperature between freezing point of water and 50 C. Marginal planet has atmosphere and liquid water possible at some part of surface during the day or year.
+pub fn habitability_category(
+    planet: &Planetesimal,
+    breathability: &Breathability,
+) -> HabitabilityCategory {
+    let Planetesimal {
+        surface_temp_kelvin,
+        max_temp_kelvin,
+        min_temp_kelvin,
+        ..
+    } = planet;
+
+    let (surface_pressure_bar, boiling_point_kelvin, hydrosphere) = match (
+        planet.surface_pressure_bar,
+        planet.boiling_point_kelvin,
+        planet.hydrosphere,
+    ) {
+        (Some(pressure), Some(boiling_point), Some(hydrosphere)) if pressure > 0.0 => {
+            (pressure, boiling_point, hydrosphere)
+        }
+        _ => return HabitabilityCategory::Hostile,
+    };
+
+    let habitable_pressure = surface_pressure_bar * MILLIBARS_PER_BAR <= MAX_HABITABLE_PRESSURE;
+    let moderate_temp = *surface_temp_kelvin >= FREEZING_POINT_OF_WATER
+        && *surface_temp_kelvin <= FREEZING_POINT_OF_WATER + 50.0;
+    if *breathability == Breathability::Breathable
+        && habitable_pressure
+        && moderate_temp
+        && hydrosphere > 0.0
+    {
+        return HabitabilityCategory::Habitable;
+    }
+
+    let liquid_water_possible =
+        *max_temp_kelvin > FREEZING_POINT_OF_WATER && *min_temp_kelvin < boiling_point_kelvin;
+    match habitable_pressure && liquid_water_possible {
+        true => HabitabilityCategory::Marginal,
+        false => HabitabilityCategory::Hostile,
+    }
+}
+
+/// StarGen planet type by mass, surface pressure, temperature, hydrosphere and rotation. Must be called after planetary environment is generated.
+pub fn planet_type(planet: &Planetesimal) -> PlanetType {
+    let Planetesimal {
+        is_gas_giant,
+        is_moon,
+        earth_masses,
+        surface_pressure_bar,
+        molecule_weight,
+        is_tidally_locked,
+        resonant_period,
+        ice_cover,
+        max_temp_kelvin,
+        surface_temp_kelvin,
+        ..
+    } = planet;
+    let surface_pressure_mb = surface_pressure_bar.unwrap_or_default() * MILLIBARS_PER_BAR;
+    let hydrosphere = planet.hydrosphere.unwrap_or_default();
+    let boiling_point_kelvin = planet.boiling_point_kelvin.unwrap_or_default();
+
+    if *is_gas_giant {
+        return match *earth_masses < SUB_JOVIAN_MASS_LIMIT {
+            true => PlanetType::SubJovian,
+            false => PlanetType::GasGiant,
+        };
+    }
+
+    if surface_pressure_mb < MIN_PLANET_TYPE_PRESSURE {
+        return match !is_moon && *earth_masses < ASTEROID_MASS_LIMIT {
+            true => PlanetType::AsteroidBelt,
+            false => PlanetType::Rock,
+        };
+    }
+
+    if surface_pressure_mb > GAS_DWARF_PRESSURE_LIMIT
+        && *molecule_weight <= GAS_DWARF_MOLECULE_WEIGHT
+    {
+        return PlanetType::SubGasGiant;
+    }
+
+    if *is_tidally_locked || *resonant_period {
+        PlanetType::OneFace
+    } else if hydrosphere >= 0.95 {
+        PlanetType::WaterWorld
+    } else if *ice_cover >= 0.95 {
+        PlanetType::Ice
+    } else if hydrosphere > 0.05 {
+        PlanetType::Terrestrial
+    } else if *max_temp_kelvin > boiling_point_kelvin {
+        PlanetType::Venusian
+    } else if surface_pressure_mb <= MARTIAN_PRESSURE_LIMIT {
+        PlanetType::Martian
+    } else if *surface_temp_kelvin < FREEZING_POINT_OF_WATER {
+        PlanetType::Ice
+    } else {
+        PlanetType::Unknown
+    }
+}
@@ -0,0 +1,83 @@
+use std::fmt;
+
+/// Error emitted during planetary system generation or state restoration
+#[derive(Debug, Clone, PartialEq)]
+pub enum AccreteError {
+    /// Configuration parameter is NaN, infinite or out of valid range
+    InvalidParameter { name: &'static str, value: f64 },
+    /// Configuration parameter is outside of recommended range (strict validation only)
+    OutOfRecommendedRange {
+        name: &'static str,
+        value: f64,
+        min: f64,
+        max: f64,
+    },
+    /// Post-accretion can't pick planet to bomb (no planets or invalid planet masses)
+    PostAccretion(String),
+    /// Planetesimal referenced by event is missing in restored state
+    PlanetesimalNotFound(String),
+    /// AccreteState can't be restored from event without System data
+    NoSystemData,
+    /// Restored AccreteState diverged from generated System
+    StateMismatch,
+    /// Replay has no step with given index
+    StepOutOfRange(usize),
+    /// Failed to serialize or deserialize output
+    Serialization(String),
+}
+
+impl fmt::Display for AccreteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AccreteError::InvalidParameter { name, value } => {
+                write!(f, "Invalid value of parameter '{}': {}", name, value)
+            }
+            AccreteError::OutOfRecommendedRange {
+                name,
+                value,
+                min,
+                max,
+            } => write!(
+                f,
+                "Parameter '{}' is out of recommended range {}-{}: {}",
+                name, min, max, value
+            ),
+            AccreteError::PostAccretion(reason) => {
+                write!(f, "Failed to run post-accretion: {}", reason)
+            }
+            AccreteError::PlanetesimalNotFound(id) => {
+                write!(f, "Failed to find planetesimal by id: {}", id)
+            }
+            AccreteError::NoSystemData => write!(
+                f,
+                "Failed to restore state from AccreteEvent. No System data present with event."
+            ),
+            AccreteError::StateMismatch => {
+                write!(
+                    f,
+                    "Restored state does not match generated planetary system"
+                )
+            }
+            AccreteError::StepOutOfRange(step) => {
+                write!(f, "Replay step is out of range: {}", step)
+            }
+            AccreteError::Serialization(reason) => {
+                write!(f, "Failed to serialize output: {}", reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AccreteError {}
+
+/// Check that parameter is finite and lies in a range
+pub fn validate_parameter(
+    name: &'static str,
+    value: f64,
+    range: impl std::ops::RangeBounds<f64>,
+) -> Result<f64, AccreteError> {
+    match value.is_finite() && range.contains(&value) {
+        true => Ok(value),
+        false => Err(AccreteError::InvalidParameter { name, value }),
+    }
+}
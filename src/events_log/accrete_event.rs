@@ -1,57 +1,196 @@
-use crate::{structs::dust::DustBands, Planetesimal, Ring, System};
+use crate::{dynamics::MigrationRegime, structs::dust::DustBands, Planetesimal, Ring, System};
 use serde::{Deserialize, Serialize};
 
 pub type AccreteEvents = Vec<AccreteEvent>;
 
-/// Event emitted during system generation
+/// Event emitted during system generation.
+/// Serialized as an object with event name in `type` field and event data in named fields, for example:
+/// `{ "type": "planetesimals_coalesced", "target_id": "..", "source_id": "..", "a": 1.0, "mass_transferred": 1.0e-6, "result": { .. } }`
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum AccreteEvent {
     None,
     /// Once at the very start of accretion
-    PlanetarySystemSetup(String, System),
-    /// New planetesimal created during accretion process
-    PlanetesimalCreated(String, Planetesimal),
-    /// Planetesimal finished accretion of dust and gas
-    PlanetesimalUpdated(String, Planetesimal),
+    PlanetarySystemSetup {
+        system: System,
+    },
+    /// New planetesimal created during accretion process, if there is dust left in its swept zone
+    PlanetesimalCreated {
+        planetesimal: Planetesimal,
+    },
+    /// Planetesimal finished accretion of dust and gas, or its orbit was oriented, captured into resonance or expanded by stellar mass loss after accretion
+    PlanetesimalUpdated {
+        planetesimal: Planetesimal,
+    },
     /// Planetesimal become gas giant
-    PlanetesimalToGasGiant(String, Planetesimal),
+    PlanetesimalToGasGiant {
+        planetesimal: Planetesimal,
+    },
     /// Dust bands recalculated every time planetesimal finish accretion
-    DustBandsUpdated(String, DustBands),
-    /// Two planetesimals coalesce
-    PlanetesimalsCoalesced(String, String, String, Planetesimal),
-    /// Two moons coalesce
-    MoonsCoalesced(String, String, String, Planetesimal),
+    DustBandsUpdated {
+        dust_bands: DustBands,
+    },
+    /// Two planetesimals coalesce. Resulting body keeps id of target, source mass is transferred to it.
+    PlanetesimalsCoalesced {
+        target_id: String,
+        source_id: String,
+        // semi-major axis of resulting body, AU
+        a: f64,
+        // units of solar masses
+        mass_transferred: f64,
+        result: Planetesimal,
+    },
+    /// Two moons coalesce. Resulting moon keeps id of target, source mass is transferred to it.
+    MoonsCoalesced {
+        target_id: String,
+        source_id: String,
+        // semi-major axis of resulting moon, AU
+        a: f64,
+        // units of solar masses
+        mass_transferred: f64,
+        result: Planetesimal,
+    },
     /// One planetesimal catch another as moon
-    PlanetesimalCaptureMoon(String, String, String, Planetesimal),
+    PlanetesimalCaptureMoon {
+        planet_id: String,
+        moon_id: String,
+        // semi-major axis of planet after capture, AU
+        a: f64,
+        // units of solar masses
+        mass_transferred: f64,
+        result: Planetesimal,
+    },
+    /// Regular moons accreted from circumplanetary disk of gas giant
+    RegularMoonsFormed {
+        planet_id: String,
+        result: Planetesimal,
+    },
     /// Moons turned into rings
-    PlanetesimalMoonToRing(String, String, String, Ring),
+    PlanetesimalMoonToRing {
+        planet_id: String,
+        moon_id: String,
+        ring: Ring,
+    },
+    /// Planet migrated inward through gas disk
+    PlanetesimalMigrated {
+        // semi-major axis before migration, AU
+        from_a: f64,
+        regime: MigrationRegime,
+        result: Planetesimal,
+    },
     /// Once at the very end of accretion
-    PostAccretionStarted(String),
+    PostAccretionStarted,
     /// For every outer body injected into system
-    OuterBodyInjected(String, Planetesimal),
+    OuterBodyInjected {
+        planetesimal: Planetesimal,
+    },
+    /// Unstable planet leaves the system
+    PlanetesimalEjected {
+        planetesimal_id: String,
+    },
+    /// Unstable planet is scattered to another orbit by close encounter
+    PlanetesimalScattered {
+        planetesimal: Planetesimal,
+    },
+    /// Planet swallowed by primary star expanding into red giant
+    PlanetesimalEngulfed {
+        planetesimal_id: String,
+    },
     /// Planetary environment generated for all planets
-    PlanetaryEnvironmentGenerated(String, System),
+    PlanetaryEnvironmentGenerated {
+        system: System,
+    },
     /// Planetary system generation completed
-    PlanetarySystemComplete(String, System),
+    PlanetarySystemComplete {
+        system: System,
+    },
+}
+
+/// Kind of AccreteEvent without event data, used to filter events before they are created
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum AccreteEventKind {
+    None,
+    PlanetarySystemSetup,
+    PlanetesimalCreated,
+    PlanetesimalUpdated,
+    PlanetesimalToGasGiant,
+    DustBandsUpdated,
+    PlanetesimalsCoalesced,
+    MoonsCoalesced,
+    PlanetesimalCaptureMoon,
+    RegularMoonsFormed,
+    PlanetesimalMoonToRing,
+    PlanetesimalMigrated,
+    PostAccretionStarted,
+    OuterBodyInjected,
+    PlanetesimalEjected,
+    PlanetesimalScattered,
+    PlanetesimalEngulfed,
+    PlanetaryEnvironmentGenerated,
+    PlanetarySystemComplete,
+}
+
+impl AccreteEventKind {
+    /// Event name, same as `type` tag of serialized event
+    pub fn name(&self) -> &'static str {
+        match self {
+            AccreteEventKind::PlanetarySystemSetup => "planetary_system_setup",
+            AccreteEventKind::PlanetesimalCreated => "planetesimal_created",
+            AccreteEventKind::PlanetesimalUpdated => "planetesimal_updated",
+            AccreteEventKind::PlanetesimalToGasGiant => "planetesimal_to_gas_giant",
+            AccreteEventKind::DustBandsUpdated => "dust_bands_updated",
+            AccreteEventKind::PlanetesimalsCoalesced => "planetesimals_coalesced",
+            AccreteEventKind::MoonsCoalesced => "moons_coalesced",
+            AccreteEventKind::PlanetesimalCaptureMoon => "planetesimal_capture_moon",
+            AccreteEventKind::RegularMoonsFormed => "regular_moons_formed",
+            AccreteEventKind::PlanetesimalMoonToRing => "planetesimal_moon_to_ring",
+            AccreteEventKind::PlanetesimalMigrated => "planetesimal_migrated",
+            AccreteEventKind::PostAccretionStarted => "post_accretion_started",
+            AccreteEventKind::OuterBodyInjected => "outer_body_injected",
+            AccreteEventKind::PlanetesimalEjected => "planetesimal_ejected",
+            AccreteEventKind::PlanetesimalScattered => "planetesimal_scattered",
+            AccreteEventKind::PlanetesimalEngulfed => "planetesimal_engulfed",
+            AccreteEventKind::PlanetaryEnvironmentGenerated => "planetary_environment_generated",
+            AccreteEventKind::PlanetarySystemComplete => "planetary_system_complete",
+            AccreteEventKind::None => "none",
+        }
+    }
 }
 
 impl AccreteEvent {
-    pub fn name(&self) -> &str {
+    pub fn kind(&self) -> AccreteEventKind {
         match self {
-            AccreteEvent::PlanetarySystemSetup(name, _) => name,
-            AccreteEvent::PlanetesimalCreated(name, _) => name,
-            AccreteEvent::PlanetesimalUpdated(name, _) => name,
-            AccreteEvent::PlanetesimalToGasGiant(name, _) => name,
-            AccreteEvent::DustBandsUpdated(name, _) => name,
-            AccreteEvent::PlanetesimalsCoalesced(name, _, _, _) => name,
-            AccreteEvent::MoonsCoalesced(name, _, _, _) => name,
-            AccreteEvent::PlanetesimalCaptureMoon(name, _, _, _) => name,
-            AccreteEvent::PlanetesimalMoonToRing(name, _, _, _) => name,
-            AccreteEvent::PostAccretionStarted(name) => name,
-            AccreteEvent::OuterBodyInjected(name, _) => name,
-            AccreteEvent::PlanetaryEnvironmentGenerated(name, _) => name,
-            AccreteEvent::PlanetarySystemComplete(name, _) => name,
-            AccreteEvent::None => "",
+            AccreteEvent::PlanetarySystemSetup { .. } => AccreteEventKind::PlanetarySystemSetup,
+            AccreteEvent::PlanetesimalCreated { .. } => AccreteEventKind::PlanetesimalCreated,
+            AccreteEvent::PlanetesimalUpdated { .. } => AccreteEventKind::PlanetesimalUpdated,
+            AccreteEvent::PlanetesimalToGasGiant { .. } => AccreteEventKind::PlanetesimalToGasGiant,
+            AccreteEvent::DustBandsUpdated { .. } => AccreteEventKind::DustBandsUpdated,
+            AccreteEvent::PlanetesimalsCoalesced { .. } => AccreteEventKind::PlanetesimalsCoalesced,
+            AccreteEvent::MoonsCoalesced { .. } => AccreteEventKind::MoonsCoalesced,
+            AccreteEvent::PlanetesimalCaptureMoon { .. } => {
+                AccreteEventKind::PlanetesimalCaptureMoon
+            }
+            AccreteEvent::RegularMoonsFormed { .. } => AccreteEventKind::RegularMoonsFormed,
+            AccreteEvent::PlanetesimalMoonToRing { .. } => AccreteEventKind::PlanetesimalMoonToRing,
+            AccreteEvent::PlanetesimalMigrated { .. } => AccreteEventKind::PlanetesimalMigrated,
+            AccreteEvent::PostAccretionStarted => AccreteEventKind::PostAccretionStarted,
+            AccreteEvent::OuterBodyInjected { .. } => AccreteEventKind::OuterBodyInjected,
+            AccreteEvent::PlanetesimalEjected { .. } => AccreteEventKind::PlanetesimalEjected,
+            AccreteEvent::PlanetesimalScattered { .. } => AccreteEventKind::PlanetesimalScattered,
+            AccreteEvent::PlanetesimalEngulfed { .. } => AccreteEventKind::PlanetesimalEngulfed,
+            AccreteEvent::PlanetaryEnvironmentGenerated { .. } => {
+                AccreteEventKind::PlanetaryEnvironmentGenerated
+            }
+            AccreteEvent::PlanetarySystemComplete { .. } => {
+                AccreteEventKind::PlanetarySystemComplete
+            }
+            AccreteEvent::None => AccreteEventKind::None,
         }
     }
+
+    /// Event name, same as `type` tag of serialized event
+    pub fn name(&self) -> &'static str {
+        self.kind().name()
+    }
 }
@@ -0,0 +1,136 @@
+use super::accrete_event::{AccreteEvent, AccreteEvents};
+use super::accrete_state::{AccreteState, StateDiff};
+use crate::AccreteError;
+
+/// Default number of steps between stored snapshots
+pub const SNAPSHOT_INTERVAL: usize = 100;
+
+/// Replay of events log with random access to every step.
+/// Step N is a state after applying events 0..=N, step 0 is restored from PlanetarySystemSetup event.
+/// Snapshots of state are stored every `snapshot_interval` steps on the way forward, stepping backward restores closest earlier snapshot and replays events from it.
+///
+/// ```
+/// use accrete::Accrete;
+/// use accrete::events_log::accrete_replay::AccreteReplay;
+///
+/// let mut accrete = Accrete::new(1);
+/// accrete.planetary_system().unwrap();
+/// let mut replay = AccreteReplay::new(accrete.events_log).unwrap();
+/// let middle = replay.len() / 2;
+/// let planets = replay.seek(middle).unwrap().system.planets.len();
+/// let diff = replay.step_backward().unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct AccreteReplay {
+    events: AccreteEvents,
+    state: AccreteState,
+    step: usize,
+    snapshot_interval: usize,
+    snapshots: Vec<AccreteState>,
+}
+
+impl AccreteReplay {
+    pub fn new(events: AccreteEvents) -> Result<Self, AccreteError> {
+        Self::with_snapshot_interval(events, SNAPSHOT_INTERVAL)
+    }
+
+    pub fn with_snapshot_interval(
+        events: AccreteEvents,
+        snapshot_interval: usize,
+    ) -> Result<Self, AccreteError> {
+        let first_event = events.first().ok_or(AccreteError::NoSystemData)?;
+        let state = AccreteState::try_from(first_event)?;
+
+        Ok(Self {
+            events,
+            snapshots: vec![state.clone()],
+            state,
+            step: 0,
+            snapshot_interval: snapshot_interval.max(1),
+        })
+    }
+
+    /// Total amount of steps
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Current step
+    pub fn step(&self) -> usize {
+        self.step
+    }
+
+    pub fn state(&self) -> &AccreteState {
+        &self.state
+    }
+
+    pub fn events(&self) -> &[AccreteEvent] {
+        &self.events
+    }
+
+    /// Event applied at current step
+    pub fn event(&self) -> &AccreteEvent {
+        &self.events[self.step]
+    }
+
+    /// Move to given step
+    pub fn seek(&mut self, step: usize) -> Result<&AccreteState, AccreteError> {
+        if step >= self.len() {
+            return Err(AccreteError::StepOutOfRange(step));
+        }
+
+        if step < self.step {
+            let snapshot_idx = (step / self.snapshot_interval).min(self.snapshots.len() - 1);
+            self.state = self.snapshots[snapshot_idx].clone();
+            self.step = snapshot_idx * self.snapshot_interval;
+        }
+
+        while self.step < step {
+            self.step += 1;
+            self.state.set_from_event(&self.events[self.step])?;
+
+            let is_snapshot_step = self.step.is_multiple_of(self.snapshot_interval);
+            if is_snapshot_step && self.step / self.snapshot_interval == self.snapshots.len() {
+                self.snapshots.push(self.state.clone());
+            }
+        }
+
+        Ok(&self.state)
+    }
+
+    /// Move one step forward, returns changes made by the next event or None at the last step
+    pub fn step_forward(&mut self) -> Result<Option<StateDiff>, AccreteError> {
+        if self.step + 1 >= self.len() {
+            return Ok(None);
+        }
+        let prev_state = self.state.clone();
+        let state = self.seek(self.step + 1)?;
+        Ok(Some(prev_state.diff(state)))
+    }
+
+    /// Move one step backward, returns changes reverted or None at the first step
+    pub fn step_backward(&mut self) -> Result<Option<StateDiff>, AccreteError> {
+        if self.step == 0 {
+            return Ok(None);
+        }
+        let prev_state = self.state.clone();
+        let state = self.seek(self.step - 1)?;
+        Ok(Some(prev_state.diff(state)))
+    }
+
+    /// Changes made by event of given step, current step is kept
+    pub fn diff(&mut self, step: usize) -> Result<StateDiff, AccreteError> {
+        if step == 0 || step >= self.len() {
+            return Err(AccreteError::StepOutOfRange(step));
+        }
+        let current_step = self.step;
+        let prev_state = self.seek(step - 1)?.clone();
+        let diff = prev_state.diff(self.seek(step)?);
+        self.seek(current_step)?;
+        Ok(diff)
+    }
+}
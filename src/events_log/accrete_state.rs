@@ -1,126 +1,242 @@
 use super::accrete_event::AccreteEvent;
-use crate::{Planetesimal, System};
+use crate::{structs::dust::DustBands, AccreteError, Planetesimal, System};
+
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone)]
 pub struct AccreteState {
     pub system: System,
+    // true once outer bodies started to bomb planets
+    pub post_accretion_started: bool,
+}
+
+/// Difference between two consecutive states.
+/// Planet with changed moons or rings is listed as updated.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct StateDiff {
+    pub planets_added: Vec<Planetesimal>,
+    pub planets_removed: Vec<String>,
+    pub planets_updated: Vec<Planetesimal>,
+    // new dust bands, if changed
+    pub dust_bands: Option<DustBands>,
+}
+
+impl StateDiff {
+    pub fn is_empty(&self) -> bool {
+        self.planets_added.is_empty()
+            && self.planets_removed.is_empty()
+            && self.planets_updated.is_empty()
+            && self.dust_bands.is_none()
+    }
 }
 
 impl From<&System> for AccreteState {
     fn from(system: &System) -> Self {
         AccreteState {
             system: system.clone(),
+            post_accretion_started: false,
         }
     }
 }
 
 impl TryFrom<&AccreteEvent> for AccreteState {
-    type Error = String;
+    type Error = AccreteError;
 
     fn try_from(event: &AccreteEvent) -> Result<Self, Self::Error> {
         match event {
-            AccreteEvent::PlanetarySystemSetup(_, system)
-            | AccreteEvent::PlanetaryEnvironmentGenerated(_, system)
-            | AccreteEvent::PlanetarySystemComplete(_, system) => Ok(AccreteState {
+            AccreteEvent::PlanetarySystemSetup { system } => Ok(AccreteState::from(system)),
+            AccreteEvent::PlanetaryEnvironmentGenerated { system }
+            | AccreteEvent::PlanetarySystemComplete { system } => Ok(AccreteState {
                 system: system.clone(),
+                post_accretion_started: true,
             }),
-            _ => Err(
-                "Failed to restore state from AccreteEvent. No System data present with event."
-                    .to_string(),
-            ),
+            _ => Err(AccreteError::NoSystemData),
         }
     }
 }
 
 impl AccreteState {
-    pub fn set_from_event(&mut self, event: &AccreteEvent) {
+    /// Apply event to state. Events must be applied in order they were emitted, starting with PlanetarySystemSetup.
+    pub fn set_from_event(&mut self, event: &AccreteEvent) -> Result<(), AccreteError> {
         match event {
-            AccreteEvent::PlanetarySystemSetup(_, system)
-            | AccreteEvent::PlanetaryEnvironmentGenerated(_, system) => {
-                self.system = system.clone()
+            AccreteEvent::PlanetarySystemSetup { system } => *self = AccreteState::from(system),
+
+            AccreteEvent::PlanetaryEnvironmentGenerated { system } => self.system = system.clone(),
+
+            AccreteEvent::PlanetesimalCreated { planetesimal } => {
+                self.system.planets.push(planetesimal.clone());
+                self.system.planets.sort_by(|p1, p2| p1.a.total_cmp(&p2.a));
             }
 
-            AccreteEvent::OuterBodyInjected(_, planetesimal)
-            | AccreteEvent::PlanetesimalCreated(_, planetesimal) => {
+            // Outer body is compared against single planet and never sorted into planets
+            AccreteEvent::OuterBodyInjected { planetesimal } => {
                 self.system.planets.push(planetesimal.clone())
             }
 
-            AccreteEvent::PlanetesimalUpdated(_, planetesimal)
-            | AccreteEvent::PlanetesimalToGasGiant(_, planetesimal) => {
-                let planet = self.find_planetesimal_mut(&planetesimal.id);
+            AccreteEvent::PlanetesimalUpdated { planetesimal }
+            | AccreteEvent::PlanetesimalToGasGiant { planetesimal } => {
+                let planet = self.find_planetesimal_mut(&planetesimal.id)?;
                 *planet = planetesimal.clone();
             }
 
-            AccreteEvent::DustBandsUpdated(_, dust_bands) => {
+            AccreteEvent::PlanetesimalEjected { planetesimal_id }
+            | AccreteEvent::PlanetesimalEngulfed { planetesimal_id } => {
+                self.find_planetesimal_mut(planetesimal_id)?;
+                self.system.planets.retain(|p| &p.id != planetesimal_id);
+            }
+
+            AccreteEvent::PlanetesimalMigrated { result, .. } => {
+                let planet = self.find_planetesimal_mut(&result.id)?;
+                *planet = result.clone();
+                self.system.planets.sort_by(|p1, p2| p1.a.total_cmp(&p2.a));
+            }
+
+            AccreteEvent::PlanetesimalScattered { planetesimal } => {
+                let planet = self.find_planetesimal_mut(&planetesimal.id)?;
+                *planet = planetesimal.clone();
+                self.system.planets.sort_by(|p1, p2| p1.a.total_cmp(&p2.a));
+            }
+
+            AccreteEvent::DustBandsUpdated { dust_bands } => {
                 self.system.dust_bands = dust_bands.clone()
             }
 
-            AccreteEvent::PlanetesimalCaptureMoon(_, id1, id2, planetesimal)
-            | AccreteEvent::PlanetesimalsCoalesced(_, id1, id2, planetesimal) => {
-                self.system.planets = self
-                    .system
-                    .planets
-                    .iter()
-                    .filter(|p| &p.id != id1 && &p.id != id2)
-                    .cloned()
-                    .collect();
-                self.system.planets.push(planetesimal.clone());
+            AccreteEvent::PlanetesimalsCoalesced {
+                target_id,
+                source_id,
+                result,
+                ..
+            } => merge_planetesimals(&mut self.system.planets, target_id, source_id, result)?,
+
+            AccreteEvent::PlanetesimalCaptureMoon {
+                planet_id,
+                moon_id,
+                result,
+                ..
+            } => {
+                merge_planetesimals(&mut self.system.planets, planet_id, moon_id, result)?;
+                let planet = self.find_planetesimal_mut(planet_id)?;
+                planet.moons.sort_by(|p1, p2| p1.a.total_cmp(&p2.a));
             }
 
-            AccreteEvent::PlanetesimalMoonToRing(_, planet_id, moon_id, ring) => {
-                let planet = self.find_planetesimal_mut(planet_id);
-                planet.moons = planet
-                    .moons
-                    .iter()
-                    .filter(|m| &m.id != moon_id)
-                    .cloned()
-                    .collect();
+            AccreteEvent::MoonsCoalesced {
+                target_id,
+                source_id,
+                result,
+                ..
+            } => {
+                let planet = self.find_moon_parent_mut(target_id)?;
+                merge_planetesimals(&mut planet.moons, target_id, source_id, result)?;
+            }
+
+            AccreteEvent::RegularMoonsFormed { planet_id, result } => {
+                let planet = self.find_planetesimal_mut(planet_id)?;
+                *planet = result.clone();
+            }
+
+            AccreteEvent::PlanetesimalMoonToRing {
+                planet_id,
+                moon_id,
+                ring,
+            } => {
+                let planet = self.find_planetesimal_mut(planet_id)?;
+                planet.moons.retain(|m| &m.id != moon_id);
                 planet.rings.push(ring.clone());
             }
-            AccreteEvent::MoonsCoalesced(_, id1, id2, planetesimal) => {
-                let mut planet = None;
-                for p in self.system.planets.iter_mut() {
-                    if planet.is_some() {
-                        break;
-                    }
-
-                    planet = p.moons.iter_mut().find(|m| m.id == planetesimal.id);
+
+            AccreteEvent::PostAccretionStarted => self.post_accretion_started = true,
+
+            AccreteEvent::PlanetarySystemComplete { system } => {
+                if format!("{:?}", &self.system) != format!("{:?}", system) {
+                    return Err(AccreteError::StateMismatch);
                 }
-                if let Some(planet) = planet {
-                    planet.moons = planet
-                        .moons
-                        .iter()
-                        .filter(|m| &m.id != id1 && &m.id != id2)
-                        .cloned()
-                        .collect();
-                    planet.moons.push(planetesimal.clone());
+            }
+
+            AccreteEvent::None => (),
+        }
+
+        Ok(())
+    }
+
+    /// Changes from this state to the next one
+    pub fn diff(&self, next: &AccreteState) -> StateDiff {
+        let planets = &self.system.planets;
+        let next_planets = &next.system.planets;
+        let mut diff = StateDiff::default();
+
+        for next_planet in next_planets.iter() {
+            match planets.iter().find(|p| p.id == next_planet.id) {
+                // Debug output is compared, orbits of massless moons may be NaN
+                Some(planet) if format!("{:?}", planet) != format!("{:?}", next_planet) => {
+                    diff.planets_updated.push(next_planet.clone())
                 }
+                Some(_) => (),
+                None => diff.planets_added.push(next_planet.clone()),
             }
-            AccreteEvent::PostAccretionStarted(_) => (),
-            AccreteEvent::PlanetarySystemComplete(_, system) => {
-                assert_eq!(format!("{:?}", &self.system), format!("{:?}", system))
+        }
+        for planet in planets.iter() {
+            if !next_planets.iter().any(|p| p.id == planet.id) {
+                diff.planets_removed.push(planet.id.clone());
             }
-            AccreteEvent::None => (),
         }
-        self.system.planets.sort_by(|p1, p2| {
-            p1.a.partial_cmp(&p2.a)
-                .expect("Failed to sort planetesimals.")
-        })
+        if self.system.dust_bands != next.system.dust_bands {
+            diff.dust_bands = Some(next.system.dust_bands.clone());
+        }
+
+        diff
     }
 
-    pub fn find_planetesimal_mut(&mut self, id: &str) -> &mut Planetesimal {
+    pub fn find_planetesimal_mut(&mut self, id: &str) -> Result<&mut Planetesimal, AccreteError> {
         self.system
             .planets
             .iter_mut()
             .find(|p| p.id == id)
-            .expect("Failed to find planet by id")
+            .ok_or_else(|| AccreteError::PlanetesimalNotFound(id.to_string()))
     }
 
-    pub fn find_planetesimal(&self, id: &str) -> &Planetesimal {
+    pub fn find_planetesimal(&self, id: &str) -> Result<&Planetesimal, AccreteError> {
         self.system
             .planets
             .iter()
             .find(|p| p.id == id)
-            .expect("Failed to find planet by id")
+            .ok_or_else(|| AccreteError::PlanetesimalNotFound(id.to_string()))
+    }
+
+    /// Planet which has moon with given id
+    pub fn find_moon_parent_mut(
+        &mut self,
+        moon_id: &str,
+    ) -> Result<&mut Planetesimal, AccreteError> {
+        self.system
+            .planets
+            .iter_mut()
+            .find(|p| p.moons.iter().any(|m| m.id == moon_id))
+            .ok_or_else(|| AccreteError::PlanetesimalNotFound(moon_id.to_string()))
     }
 }
+
+/// Replace first of two merged planetesimals with result, remove second one
+fn merge_planetesimals(
+    planetesimals: &mut Vec<Planetesimal>,
+    id1: &str,
+    id2: &str,
+    result: &Planetesimal,
+) -> Result<(), AccreteError> {
+    let idx1 = find_index(planetesimals, id1)?;
+    let idx2 = find_index(planetesimals, id2)?;
+    let (keep, remove) = match idx1 < idx2 {
+        true => (idx1, idx2),
+        false => (idx2, idx1),
+    };
+    planetesimals[keep] = result.clone();
+    planetesimals.remove(remove);
+
+    Ok(())
+}
+
+fn find_index(planetesimals: &[Planetesimal], id: &str) -> Result<usize, AccreteError> {
+    planetesimals
+        .iter()
+        .position(|p| p.id == id)
+        .ok_or_else(|| AccreteError::PlanetesimalNotFound(id.to_string()))
+}
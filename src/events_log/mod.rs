@@ -1,4 +1,6 @@
 pub mod accrete_event;
+pub mod accrete_replay;
 pub mod accrete_state;
-pub mod event_source;
+pub mod observer;
+#[cfg(test)]
 mod tests;
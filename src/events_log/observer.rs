@@ -0,0 +1,86 @@
+use super::accrete_event::{AccreteEvent, AccreteEventKind, AccreteEvents};
+
+/// Receives AccreteEvents as they are emitted during system generation.
+/// Event data is cloned only for events of kinds observer accepts.
+pub trait AccreteObserver {
+    /// Return false to skip events of given kind
+    fn accepts(&self, _kind: AccreteEventKind) -> bool {
+        true
+    }
+
+    fn on_event(&mut self, event: AccreteEvent);
+}
+
+impl dyn AccreteObserver + '_ {
+    /// Create event and pass it to observer, if observer accepts events of given kind
+    pub fn emit(&mut self, kind: AccreteEventKind, event: impl FnOnce() -> AccreteEvent) {
+        if self.accepts(kind) {
+            self.on_event(event())
+        }
+    }
+}
+
+/// Events log accumulating all events
+impl AccreteObserver for AccreteEvents {
+    fn on_event(&mut self, event: AccreteEvent) {
+        self.push(event)
+    }
+}
+
+/// Observer dropping all events without creating them
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NullObserver;
+
+impl AccreteObserver for NullObserver {
+    fn accepts(&self, _kind: AccreteEventKind) -> bool {
+        false
+    }
+
+    fn on_event(&mut self, _event: AccreteEvent) {}
+}
+
+/// Observer passing events to closure, optionally filtered by event kind
+///
+/// ```
+/// use accrete::Accrete;
+/// use accrete::events_log::accrete_event::AccreteEventKind;
+/// use accrete::events_log::observer::FnObserver;
+///
+/// let mut accrete = Accrete::new(1);
+/// let mut coalesced = 0;
+/// let mut observer = FnObserver::new(|_event| coalesced += 1)
+///     .only(&[AccreteEventKind::PlanetesimalsCoalesced]);
+/// accrete.planetary_system_with_observer(&mut observer).unwrap();
+/// ```
+pub struct FnObserver<F: FnMut(AccreteEvent)> {
+    callback: F,
+    kinds: Option<Vec<AccreteEventKind>>,
+}
+
+impl<F: FnMut(AccreteEvent)> FnObserver<F> {
+    pub fn new(callback: F) -> Self {
+        Self {
+            callback,
+            kinds: None,
+        }
+    }
+
+    /// Pass only events of given kinds to closure
+    pub fn only(mut self, kinds: &[AccreteEventKind]) -> Self {
+        self.kinds = Some(kinds.to_vec());
+        self
+    }
+}
+
+impl<F: FnMut(AccreteEvent)> AccreteObserver for FnObserver<F> {
+    fn accepts(&self, kind: AccreteEventKind) -> bool {
+        match &self.kinds {
+            Some(kinds) => kinds.contains(&kind),
+            None => true,
+        }
+    }
+
+    fn on_event(&mut self, event: AccreteEvent) {
+        (self.callback)(event)
+    }
+}
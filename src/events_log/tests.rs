@@ -1,23 +1,362 @@
-#[cfg(test)]
-mod tests {
-    use crate::events_log::accrete_state::AccreteState;
-    use crate::Accrete;
-
-    #[test]
-    fn restore_state_default() {
-        let mut accrete = Accrete::new(Default::default());
-        accrete.post_accretion_intensity = 0;
-        let resulting_system = accrete.planetary_system();
+use crate::events_log::accrete_event::{AccreteEvent, AccreteEventKind};
+use crate::events_log::accrete_replay::AccreteReplay;
+use crate::events_log::accrete_state::AccreteState;
+use crate::events_log::observer::{FnObserver, NullObserver};
+use crate::Accrete;
+
+#[test]
+fn restore_state_default() {
+    let mut accrete = Accrete::new(Default::default());
+    accrete.post_accretion_intensity = 0;
+    let resulting_system = accrete
+        .planetary_system()
+        .expect("Failed to generate planetary system.");
+    let mut accrete_state =
+        AccreteState::try_from(&accrete.events_log[0]).expect("Failed to restore Accrete state.");
+
+    for e in accrete.events_log.iter() {
+        accrete_state
+            .set_from_event(e)
+            .expect("Failed to restore Accrete state.");
+    }
+
+    assert!(accrete_state.post_accretion_started);
+    assert_eq!(
+        format!("{:?}", resulting_system),
+        format!("{:?}", accrete_state.system)
+    );
+}
+
+#[test]
+fn events_serialized_with_type_tag() {
+    let mut accrete = Accrete::new(1);
+    accrete
+        .planetary_system()
+        .expect("Failed to generate planetary system.");
+
+    for e in accrete.events_log.iter() {
+        let json = serde_json::to_value(e).expect("Failed to serialize event.");
+        assert_eq!(json["type"], e.name());
+    }
+
+    let capture = accrete
+        .events_log
+        .iter()
+        .find(|e| matches!(e, AccreteEvent::PlanetesimalCaptureMoon { .. }))
+        .expect("No moons captured.");
+    let json = serde_json::to_value(capture).expect("Failed to serialize event.");
+    for field in ["planet_id", "moon_id", "a", "mass_transferred", "result"] {
+        assert!(json.get(field).is_some());
+    }
+    assert_eq!(json["result"]["id"], json["planet_id"]);
+}
+
+#[test]
+fn observer_receives_only_filtered_events() {
+    let mut accrete = Accrete::new(1);
+    let mut captured = vec![];
+    let mut observer = FnObserver::new(|e| captured.push(e)).only(&[
+        AccreteEventKind::PlanetesimalCaptureMoon,
+        AccreteEventKind::PlanetarySystemComplete,
+    ]);
+    accrete
+        .planetary_system_with_observer(&mut observer)
+        .expect("Failed to generate planetary system.");
+
+    assert!(accrete.events_log.is_empty());
+    assert!(!captured.is_empty());
+    assert!(captured.iter().all(|e| matches!(
+        e.kind(),
+        AccreteEventKind::PlanetesimalCaptureMoon | AccreteEventKind::PlanetarySystemComplete
+    )));
+    assert!(matches!(
+        captured.last(),
+        Some(AccreteEvent::PlanetarySystemComplete { .. })
+    ));
+}
+
+#[test]
+fn null_observer_generates_same_system() {
+    let mut accrete = Accrete::new(1);
+    let logged_system = accrete
+        .planetary_system()
+        .expect("Failed to generate planetary system.");
+
+    let mut accrete = Accrete::new(1);
+    let system = accrete
+        .planetary_system_with_observer(&mut NullObserver)
+        .expect("Failed to generate planetary system.");
+
+    assert_eq!(format!("{:?}", logged_system), format!("{:?}", system));
+}
+
+#[test]
+fn restore_state_with_post_accretion() {
+    for seed in 1..5 {
+        let mut accrete = Accrete::new(seed);
+        let resulting_system = accrete
+            .planetary_system()
+            .expect("Failed to generate planetary system.");
         let mut accrete_state = AccreteState::try_from(&accrete.events_log[0])
             .expect("Failed to restore Accrete state.");
 
         for e in accrete.events_log.iter() {
-            accrete_state.set_from_event(e);
+            // Environment is not derived from events, compare accreted bodies before it replaces restored system
+            if let AccreteEvent::PlanetaryEnvironmentGenerated { .. } = e {
+                assert!(accrete_state.post_accretion_started);
+                let restored = &accrete_state.system.planets;
+                let planets = &resulting_system.planets;
+                assert_eq!(restored.len(), planets.len());
+                for (p1, p2) in restored.iter().zip(planets.iter()) {
+                    assert_eq!(p1.id, p2.id);
+                    assert_eq!(p1.mass, p2.mass);
+                    assert_eq!(p1.a, p2.a);
+                    assert_eq!(
+                        p1.moons.iter().map(|m| &m.id).collect::<Vec<_>>(),
+                        p2.moons.iter().map(|m| &m.id).collect::<Vec<_>>()
+                    );
+                    assert_eq!(p1.rings.len(), p2.rings.len());
+                }
+                assert_eq!(accrete_state.system.dust_bands, resulting_system.dust_bands);
+            }
+            accrete_state
+                .set_from_event(e)
+                .expect("Failed to restore Accrete state.");
         }
+    }
+}
+
+#[test]
+fn restore_state_with_regular_moons() {
+    let mut accrete = Accrete::new(2);
+    accrete.regular_moons = true;
+    let resulting_system = accrete
+        .planetary_system()
+        .expect("Failed to generate planetary system.");
+    let mut accrete_state =
+        AccreteState::try_from(&accrete.events_log[0]).expect("Failed to restore Accrete state.");
+    let mut regular_moons_formed = 0;
+
+    for e in accrete.events_log.iter() {
+        if let AccreteEvent::RegularMoonsFormed { planet_id, result } = e {
+            regular_moons_formed += 1;
+            accrete_state
+                .set_from_event(e)
+                .expect("Failed to restore Accrete state.");
+            let planet = accrete_state
+                .system
+                .planets
+                .iter()
+                .find(|p| &p.id == planet_id)
+                .expect("Failed to find planet with regular moons.");
+            assert_eq!(planet, result);
+            continue;
+        }
+        // Environment is not derived from events, compare moons before it replaces restored system
+        if let AccreteEvent::PlanetaryEnvironmentGenerated { .. } = e {
+            let restored = &accrete_state.system.planets;
+            let planets = &resulting_system.planets;
+            assert_eq!(restored.len(), planets.len());
+            for (p1, p2) in restored.iter().zip(planets.iter()) {
+                assert_eq!(p1.id, p2.id);
+                assert_eq!(
+                    p1.moons
+                        .iter()
+                        .map(|m| (&m.id, m.moon_class))
+                        .collect::<Vec<_>>(),
+                    p2.moons
+                        .iter()
+                        .map(|m| (&m.id, m.moon_class))
+                        .collect::<Vec<_>>()
+                );
+            }
+        }
+        accrete_state
+            .set_from_event(e)
+            .expect("Failed to restore Accrete state.");
+    }
+
+    assert!(regular_moons_formed > 0);
+}
 
-        assert_eq!(
-            format!("{:?}", resulting_system),
-            format!("{:?}", accrete_state.system)
-        );
+#[test]
+fn restore_state_with_orbit_orientation() {
+    let mut accrete = Accrete::new(1);
+    let resulting_system = accrete
+        .planetary_system()
+        .expect("Failed to generate planetary system.");
+    let mut accrete_state =
+        AccreteState::try_from(&accrete.events_log[0]).expect("Failed to restore Accrete state.");
+
+    for e in accrete.events_log.iter() {
+        // Oriented orbits are restored before environment replaces restored system
+        if let AccreteEvent::PlanetaryEnvironmentGenerated { .. } = e {
+            let restored = &accrete_state.system.planets;
+            let planets = &resulting_system.planets;
+            assert_eq!(restored.len(), planets.len());
+            for (p1, p2) in restored.iter().zip(planets.iter()) {
+                assert_eq!(p1.orbital_elements, p2.orbital_elements);
+                assert_eq!(
+                    p1.moons
+                        .iter()
+                        .map(|m| &m.orbital_elements)
+                        .collect::<Vec<_>>(),
+                    p2.moons
+                        .iter()
+                        .map(|m| &m.orbital_elements)
+                        .collect::<Vec<_>>()
+                );
+            }
+        }
+        accrete_state
+            .set_from_event(e)
+            .expect("Failed to restore Accrete state.");
+    }
+}
+
+#[test]
+fn restore_state_with_resonance_capture() {
+    let mut accrete = Accrete::new(1);
+    accrete.resonance_capture = true;
+    accrete.system_age = Some(1.0e9);
+    let resulting_system = accrete
+        .planetary_system()
+        .expect("Failed to generate planetary system.");
+    let mut accrete_state =
+        AccreteState::try_from(&accrete.events_log[0]).expect("Failed to restore Accrete state.");
+
+    for e in accrete.events_log.iter() {
+        // Captured orbits are restored before environment replaces restored system
+        if let AccreteEvent::PlanetaryEnvironmentGenerated { .. } = e {
+            let restored = &accrete_state.system.planets;
+            let planets = &resulting_system.planets;
+            assert_eq!(restored.len(), planets.len());
+            for (p1, p2) in restored.iter().zip(planets.iter()) {
+                assert_eq!(p1.a, p2.a);
+                assert_eq!(p1.resonances, p2.resonances);
+                assert_eq!(
+                    p1.moons.iter().map(|m| m.a).collect::<Vec<_>>(),
+                    p2.moons.iter().map(|m| m.a).collect::<Vec<_>>()
+                );
+            }
+        }
+        accrete_state
+            .set_from_event(e)
+            .expect("Failed to restore Accrete state.");
+    }
+
+    assert!(resulting_system
+        .planets
+        .iter()
+        .any(|p| p.resonances.iter().any(|r| r.offset == 0.0)));
+}
+
+#[test]
+fn restore_state_with_star_evolution() {
+    let mut accrete = Accrete::new(1);
+    accrete.stellar_mass = 1.0;
+    accrete.system_age = Some(1.2e10);
+    let resulting_system = accrete
+        .planetary_system()
+        .expect("Failed to generate planetary system.");
+    let mut accrete_state =
+        AccreteState::try_from(&accrete.events_log[0]).expect("Failed to restore Accrete state.");
+
+    for e in accrete.events_log.iter() {
+        // Expanded orbits are restored before environment replaces restored system
+        if let AccreteEvent::PlanetaryEnvironmentGenerated { .. } = e {
+            let restored = &accrete_state.system.planets;
+            let planets = &resulting_system.planets;
+            assert_eq!(restored.len(), planets.len());
+            for (p1, p2) in restored.iter().zip(planets.iter()) {
+                assert_eq!(p1.id, p2.id);
+                assert_eq!(p1.a, p2.a);
+            }
+            assert_eq!(accrete_state.system.dust_bands, resulting_system.dust_bands);
+        }
+        accrete_state
+            .set_from_event(e)
+            .expect("Failed to restore Accrete state.");
+    }
+}
+
+#[test]
+fn replay_seek_and_step_backward() {
+    let mut accrete = Accrete::new(1);
+    accrete
+        .planetary_system()
+        .expect("Failed to generate planetary system.");
+    let mut sequential =
+        AccreteState::try_from(&accrete.events_log[0]).expect("Failed to restore Accrete state.");
+    let mut states = vec![];
+    for e in accrete.events_log.iter() {
+        sequential
+            .set_from_event(e)
+            .expect("Failed to restore Accrete state.");
+        states.push(format!("{:?}", sequential.system));
+    }
+
+    let mut replay = AccreteReplay::with_snapshot_interval(accrete.events_log.clone(), 10)
+        .expect("Failed to create replay.");
+    let last = replay.len() - 1;
+    let last_state = replay.seek(last).expect("Failed to seek.");
+    assert_eq!(format!("{:?}", last_state.system), states[last]);
+
+    for step in [last / 2, 3, last - 1, 0, 11] {
+        let state = replay.seek(step).expect("Failed to seek.");
+        assert_eq!(format!("{:?}", state.system), states[step]);
+    }
+
+    let diff = replay
+        .step_forward()
+        .expect("Failed to step forward.")
+        .expect("No next step.");
+    assert_eq!(Some(diff), replay.diff(12).ok());
+    replay.step_backward().expect("Failed to step backward.");
+    assert_eq!(replay.step(), 11);
+    assert_eq!(format!("{:?}", replay.state().system), states[11]);
+    assert!(replay.seek(last + 1).is_err());
+}
+
+#[test]
+fn replay_diff_reflects_event() {
+    let mut accrete = Accrete::new(1);
+    accrete
+        .planetary_system()
+        .expect("Failed to generate planetary system.");
+    let mut replay =
+        AccreteReplay::new(accrete.events_log.clone()).expect("Failed to create replay.");
+
+    loop {
+        let planet_ids = replay
+            .state()
+            .system
+            .planets
+            .iter()
+            .map(|p| p.id.clone())
+            .collect::<Vec<_>>();
+        let Some(diff) = replay.step_forward().expect("Failed to step forward.") else {
+            break;
+        };
+        match replay.event() {
+            AccreteEvent::PlanetesimalCreated { planetesimal } => {
+                assert_eq!(diff.planets_added, vec![planetesimal.clone()])
+            }
+            AccreteEvent::DustBandsUpdated { dust_bands } => {
+                assert!(diff.dust_bands.as_ref().is_some_and(|d| d == dust_bands))
+            }
+            AccreteEvent::PlanetesimalCaptureMoon {
+                planet_id, moon_id, ..
+            } => {
+                assert_eq!(diff.planets_updated.len(), 1);
+                assert_eq!(&diff.planets_updated[0].id, planet_id);
+                assert_eq!(
+                    diff.planets_removed.contains(moon_id),
+                    planet_ids.contains(moon_id)
+                );
+            }
+            AccreteEvent::PostAccretionStarted => assert!(diff.is_empty()),
+            _ => (),
+        }
     }
 }
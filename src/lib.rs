@@ -9,23 +9,50 @@
 )]
 #![allow(clippy::too_many_arguments, clippy::type_complexity)]
 pub mod accrete;
+pub mod config;
 pub mod consts;
+pub mod dynamics;
 pub mod enviro;
+pub mod error;
 pub mod events_log;
+#[cfg(feature = "population")]
+pub mod population;
 pub mod structs;
+#[cfg(feature = "svg")]
+pub mod svg;
 pub mod utils;
 pub mod wasm;
 
 pub use crate::accrete::Accrete;
+pub use crate::config::{AccreteBuilder, AccreteConfig, Preset, ValidationMode};
+pub use crate::dynamics::MigrationConfig;
+pub use crate::dynamics::StabilityConfig;
+pub use crate::error::AccreteError;
+pub use structs::Belt;
+pub use structs::BulkComposition;
+pub use structs::CompanionStar;
+pub use structs::CompanionStarConfig;
+pub use structs::CondensationConfig;
+pub use structs::CondensationLines;
+pub use structs::DiskModel;
+pub use structs::DiskProfile;
 pub use structs::DustBand;
+pub use structs::EvolutionaryStage;
+pub use structs::GasGiantStructure;
+pub use structs::Habitability;
+pub use structs::MoonClass;
+pub use structs::PlanetType;
 pub use structs::Planetesimal;
 pub use structs::PrimaryStar;
 pub use structs::Ring;
 pub use structs::System;
+pub use structs::TabulatedProfile;
 
 #[cfg(test)]
 mod tests {
-    use crate::Accrete;
+    use crate::{Accrete, AccreteError, CompanionStarConfig, System};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
     use std::{fs, env};
     use std::io::{Error, Write};
 
@@ -45,13 +72,18 @@ mod tests {
         fs::read_to_string(path).expect("Failed to read fixture")
     }
 
+    fn generate_system(accrete: &mut Accrete) -> System {
+        accrete
+            .planetary_system()
+            .expect("Failed to generate planetary system")
+    }
+
     fn get_fixture(path: &str, accrete: &mut Accrete) -> String {
         if generate_fixtures_env() {
-            write_to_file(&format!("{:?}", accrete.planetary_system()), path)
+            write_to_file(&format!("{:?}", generate_system(accrete)), path)
                 .expect("Failed to write fixture");
         }
-        let fixture = read_file(path);
-        fixture
+        read_file(path)
     }
 
     #[test]
@@ -59,7 +91,7 @@ mod tests {
         let mut accrete = Accrete::new(1);
         let path = "./src/fixtures/default";
         let fixture = get_fixture(path, &mut accrete);
-        let system = format!("{:?}", accrete.planetary_system());
+        let system = format!("{:?}", generate_system(&mut accrete));
         assert_eq!(system, fixture);
     }
 
@@ -67,10 +99,10 @@ mod tests {
     fn run_with_o_spectral_class() {
         let mut accrete = Accrete::new(1);
         accrete.stellar_mass = 60.0;
-        accrete.planetary_system();
+        generate_system(&mut accrete);
         let path = "./src/fixtures/o_spectral_class";
         let fixture = get_fixture(path, &mut accrete);
-        let system = format!("{:?}", accrete.planetary_system());
+        let system = format!("{:?}", generate_system(&mut accrete));
         assert_eq!(system, fixture);
     }
 
@@ -78,10 +110,10 @@ mod tests {
     fn run_with_b_spectral_class() {
         let mut accrete = Accrete::new(1);
         accrete.stellar_mass = 18.0;
-        accrete.planetary_system();
+        generate_system(&mut accrete);
         let path = "./src/fixtures/b_spectral_class";
         let fixture = get_fixture(path, &mut accrete);
-        let system = format!("{:?}", accrete.planetary_system());
+        let system = format!("{:?}", generate_system(&mut accrete));
         assert_eq!(system, fixture);
     }
 
@@ -89,10 +121,10 @@ mod tests {
     fn run_with_a_spectral_class() {
         let mut accrete = Accrete::new(1);
         accrete.stellar_mass = 2.1;
-        accrete.planetary_system();
+        generate_system(&mut accrete);
         let path = "./src/fixtures/a_spectral_class";
         let fixture = get_fixture(path, &mut accrete);
-        let system = format!("{:?}", accrete.planetary_system());
+        let system = format!("{:?}", generate_system(&mut accrete));
         assert_eq!(system, fixture);
     }
 
@@ -100,10 +132,10 @@ mod tests {
     fn run_with_f_spectral_class() {
         let mut accrete = Accrete::new(1);
         accrete.stellar_mass = 1.3;
-        accrete.planetary_system();
+        generate_system(&mut accrete);
         let path = "./src/fixtures/f_spectral_class";
         let fixture = get_fixture(path, &mut accrete);
-        let system = format!("{:?}", accrete.planetary_system());
+        let system = format!("{:?}", generate_system(&mut accrete));
         assert_eq!(system, fixture);
     }
 
@@ -111,10 +143,10 @@ mod tests {
     fn run_with_g_spectral_class() {
         let mut accrete = Accrete::new(1);
         accrete.stellar_mass = 1.0;
-        accrete.planetary_system();
+        generate_system(&mut accrete);
         let path = "./src/fixtures/g_spectral_class";
         let fixture = get_fixture(path, &mut accrete);
-        let system = format!("{:?}", accrete.planetary_system());
+        let system = format!("{:?}", generate_system(&mut accrete));
         assert_eq!(system, fixture);
     }
 
@@ -122,10 +154,10 @@ mod tests {
     fn run_with_k_spectral_class() {
         let mut accrete = Accrete::new(1);
         accrete.stellar_mass = 0.8;
-        accrete.planetary_system();
+        generate_system(&mut accrete);
         let path = "./src/fixtures/k_spectral_class";
         let fixture = get_fixture(path, &mut accrete);
-        let system = format!("{:?}", accrete.planetary_system());
+        let system = format!("{:?}", generate_system(&mut accrete));
         assert_eq!(system, fixture);
     }
 
@@ -133,10 +165,10 @@ mod tests {
     fn run_with_m_spectral_class() {
         let mut accrete = Accrete::new(1);
         accrete.stellar_mass = 0.3;
-        accrete.planetary_system();
+        generate_system(&mut accrete);
         let path = "./src/fixtures/m_spectral_class";
         let fixture = get_fixture(path, &mut accrete);
-        let system = format!("{:?}", accrete.planetary_system());
+        let system = format!("{:?}", generate_system(&mut accrete));
         assert_eq!(system, fixture);
     }
 
@@ -144,10 +176,10 @@ mod tests {
     fn run_with_brown_dwarf() {
         let mut accrete = Accrete::new(1);
         accrete.stellar_mass = 0.1;
-        accrete.planetary_system();
+        generate_system(&mut accrete);
         let path = "./src/fixtures/brown_dwarf";
         let fixture = get_fixture(path, &mut accrete);
-        let system = format!("{:?}", accrete.planetary_system());
+        let system = format!("{:?}", generate_system(&mut accrete));
         assert_eq!(system, fixture);
     }
 
@@ -155,10 +187,10 @@ mod tests {
     fn run_with_rogue_planet() {
         let mut accrete = Accrete::new(1);
         accrete.stellar_mass = 0.0005;
-        accrete.planetary_system();
+        generate_system(&mut accrete);
         let path = "./src/fixtures/rogue_planet";
         let fixture = get_fixture(path, &mut accrete);
-        let system = format!("{:?}", accrete.planetary_system());
+        let system = format!("{:?}", generate_system(&mut accrete));
         assert_eq!(system, fixture);
     }
 
@@ -166,10 +198,10 @@ mod tests {
     fn high_density_dust() {
         let mut accrete = Accrete::new(1);
         accrete.dust_density_coeff = 0.05;
-        accrete.planetary_system();
+        generate_system(&mut accrete);
         let path = "./src/fixtures/high_density_dust";
         let fixture = get_fixture(path, &mut accrete);
-        let system = format!("{:?}", accrete.planetary_system());
+        let system = format!("{:?}", generate_system(&mut accrete));
         assert_eq!(system, fixture);
     }
 
@@ -177,10 +209,10 @@ mod tests {
     fn low_density_dust() {
         let mut accrete = Accrete::new(1);
         accrete.dust_density_coeff = 0.00125;
-        accrete.planetary_system();
+        generate_system(&mut accrete);
         let path = "./src/fixtures/low_density_dust";
         let fixture = get_fixture(path, &mut accrete);
-        let system = format!("{:?}", accrete.planetary_system());
+        let system = format!("{:?}", generate_system(&mut accrete));
         assert_eq!(system, fixture);
     }
 
@@ -188,10 +220,10 @@ mod tests {
     fn high_cloud_ecentricity() {
         let mut accrete = Accrete::new(1);
         accrete.cloud_eccentricity = 0.5;
-        accrete.planetary_system();
+        generate_system(&mut accrete);
         let path = "./src/fixtures/high_cloud_ecentricity";
         let fixture = get_fixture(path, &mut accrete);
-        let system = format!("{:?}", accrete.planetary_system());
+        let system = format!("{:?}", generate_system(&mut accrete));
         assert_eq!(system, fixture);
     }
 
@@ -199,10 +231,10 @@ mod tests {
     fn low_cloud_ecentricity() {
         let mut accrete = Accrete::new(1);
         accrete.cloud_eccentricity = 0.1;
-        accrete.planetary_system();
+        generate_system(&mut accrete);
         let path = "./src/fixtures/low_cloud_ecentricity";
         let fixture = get_fixture(path, &mut accrete);
-        let system = format!("{:?}", accrete.planetary_system());
+        let system = format!("{:?}", generate_system(&mut accrete));
         assert_eq!(system, fixture);
     }
 
@@ -211,24 +243,80 @@ mod tests {
         let mut accrete = Accrete::new(1);
         accrete.cloud_eccentricity = 0.05;
         accrete.dust_density_coeff = 0.035;
-        accrete.planetary_system();
+        generate_system(&mut accrete);
         let path = "./src/fixtures/low_cloud_ecentricity_and_dust_density";
         let fixture = get_fixture(path, &mut accrete);
-        let system = format!("{:?}", accrete.planetary_system());
+        let system = format!("{:?}", generate_system(&mut accrete));
+        assert_eq!(system, fixture);
+    }
+
+    #[test]
+    fn run_with_companion_star() {
+        let mut accrete = Accrete::new(1);
+        accrete.stellar_mass = 1.0;
+        accrete.companion_star = Some(CompanionStarConfig::new(0.5, 8.0, 0.1));
+        generate_system(&mut accrete);
+        let path = "./src/fixtures/companion_star";
+        let fixture = get_fixture(path, &mut accrete);
+        let system = format!("{:?}", generate_system(&mut accrete));
         assert_eq!(system, fixture);
     }
 
     #[test]
     fn random_planet_default() {
         let mut accrete = Accrete::new(1);
-        accrete.planet();
+        accrete.planet().expect("Failed to generate planet");
         let path = "./src/fixtures/random_planet_default";
         if generate_fixtures_env() {
-            write_to_file(&format!("{:?}", accrete.planet()), path)
-                .expect("Failed to write fixture");
+            let planet = accrete.planet().expect("Failed to generate planet");
+            write_to_file(&format!("{:?}", planet), path).expect("Failed to write fixture");
         }
         let fixture = read_file(path);
-        let system = format!("{:?}", accrete.planet());
+        let planet = accrete.planet().expect("Failed to generate planet");
+        let system = format!("{:?}", planet);
         assert_eq!(system, fixture);
     }
+
+    #[test]
+    fn invalid_config_returns_error() {
+        let mut accrete = Accrete::new(1);
+        accrete.stellar_mass = f64::NAN;
+        assert!(matches!(
+            accrete.planetary_system(),
+            Err(AccreteError::InvalidParameter {
+                name: "stellar_mass",
+                ..
+            })
+        ));
+
+        let mut accrete = Accrete::new(1);
+        accrete.cloud_eccentricity = 1.5;
+        assert!(matches!(
+            accrete.planetary_system(),
+            Err(AccreteError::InvalidParameter {
+                name: "cloud_eccentricity",
+                ..
+            })
+        ));
+
+        let mut accrete = Accrete::new(1);
+        accrete.planet_mass = -1.0;
+        assert!(matches!(
+            accrete.planet(),
+            Err(AccreteError::InvalidParameter {
+                name: "planet_mass",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn post_accretion_without_planets_returns_error() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let mut events_log = vec![];
+        let mut system =
+            System::set_initial_conditions(1.0, 0.0, 0.0015, 50.0, 0.2, 1.2e-5, None, None);
+        let result = system.post_accretion(100, &mut rng, &mut events_log);
+        assert!(matches!(result, Err(AccreteError::PostAccretion(_))));
+    }
 }
@@ -0,0 +1,571 @@
+use crate::accrete::Accrete;
+use crate::config::AccreteConfig;
+use crate::consts::EARTH_MASSES_PER_SOLAR_MASS;
+use crate::error::AccreteError;
+use crate::events_log::observer::NullObserver;
+use crate::structs::System;
+
+#[cfg(not(target_arch = "wasm32"))]
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// Edges of semi-major axis bins of mass-vs-a histogram, AU
+pub const HISTOGRAM_A_EDGES: [f64; 9] = [0.1, 0.2, 0.5, 1.0, 2.0, 5.0, 10.0, 20.0, 50.0];
+/// Edges of mass bins of mass-vs-a histogram, Earth masses
+pub const HISTOGRAM_MASS_EDGES: [f64; 9] = [0.001, 0.01, 0.1, 0.5, 2.0, 10.0, 50.0, 300.0, 1000.0];
+
+/// Values of parameters to combine. Empty list keeps parameter value of seeded or base configuration.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ParameterGrid {
+    pub stellar_mass: Vec<f64>,
+    pub metallicity: Vec<f64>,
+    pub dust_density_coeff: Vec<f64>,
+    pub k: Vec<f64>,
+    pub cloud_eccentricity: Vec<f64>,
+    pub b: Vec<f64>,
+}
+
+/// Single combination of grid parameters, None for parameters kept from configuration
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub struct GridPoint {
+    pub stellar_mass: Option<f64>,
+    pub metallicity: Option<f64>,
+    pub dust_density_coeff: Option<f64>,
+    pub k: Option<f64>,
+    pub cloud_eccentricity: Option<f64>,
+    pub b: Option<f64>,
+}
+
+impl ParameterGrid {
+    /// All combinations of parameter values
+    pub fn points(&self) -> Vec<GridPoint> {
+        let mut points = vec![];
+        for stellar_mass in axis(&self.stellar_mass) {
+            for metallicity in axis(&self.metallicity) {
+                for dust_density_coeff in axis(&self.dust_density_coeff) {
+                    for k in axis(&self.k) {
+                        for cloud_eccentricity in axis(&self.cloud_eccentricity) {
+                            for b in axis(&self.b) {
+                                points.push(GridPoint {
+                                    stellar_mass,
+                                    metallicity,
+                                    dust_density_coeff,
+                                    k,
+                                    cloud_eccentricity,
+                                    b,
+                                });
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        points
+    }
+}
+
+fn axis(values: &[f64]) -> Vec<Option<f64>> {
+    match values.is_empty() {
+        true => vec![None],
+        false => values.iter().copied().map(Some).collect(),
+    }
+}
+
+impl GridPoint {
+    fn apply(&self, accrete: &mut Accrete) {
+        if let Some(stellar_mass) = self.stellar_mass {
+            accrete.stellar_mass = stellar_mass;
+        }
+        if let Some(metallicity) = self.metallicity {
+            accrete.metallicity = metallicity;
+        }
+        if let Some(dust_density_coeff) = self.dust_density_coeff {
+            accrete.dust_density_coeff = dust_density_coeff;
+        }
+        if let Some(k) = self.k {
+            accrete.k = k;
+        }
+        if let Some(cloud_eccentricity) = self.cloud_eccentricity {
+            accrete.cloud_eccentricity = cloud_eccentricity;
+        }
+        if let Some(b) = self.b {
+            accrete.b = b;
+        }
+    }
+}
+
+/// Generation of planetary systems for every seed of range and every point of parameter grid.
+/// Systems are generated in parallel on native targets.
+///
+/// ```
+/// use accrete::population::{ParameterGrid, Population};
+///
+/// let grid = ParameterGrid {
+///     k: vec![50.0, 100.0],
+///     ..Default::default()
+/// };
+/// let reports = Population::new(0..4).grid(grid).run().unwrap();
+/// let csv = accrete::population::reports_to_csv(&reports);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Population {
+    seeds: Range<u64>,
+    grid: ParameterGrid,
+    config: Option<AccreteConfig>,
+    // units of AU
+    a_edges: Vec<f64>,
+    // units of Earth masses
+    mass_edges: Vec<f64>,
+}
+
+impl Population {
+    pub fn new(seeds: Range<u64>) -> Self {
+        Self {
+            seeds,
+            grid: ParameterGrid::default(),
+            config: None,
+            a_edges: HISTOGRAM_A_EDGES.to_vec(),
+            mass_edges: HISTOGRAM_MASS_EDGES.to_vec(),
+        }
+    }
+
+    pub fn grid(mut self, grid: ParameterGrid) -> Self {
+        self.grid = grid;
+        self
+    }
+
+    /// Base configuration for every seed, instead of configuration derived from seed
+    pub fn config(mut self, config: AccreteConfig) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Bins of mass-vs-a histogram, semi-major axis edges in AU and mass edges in Earth masses, instead of HISTOGRAM_A_EDGES and HISTOGRAM_MASS_EDGES.
+    /// Edges must be finite and increasing, at least two per axis.
+    pub fn histogram_edges(mut self, a_edges: Vec<f64>, mass_edges: Vec<f64>) -> Self {
+        self.a_edges = a_edges;
+        self.mass_edges = mass_edges;
+        self
+    }
+
+    /// Generate systems and aggregate statistics, one report per grid point
+    pub fn run(&self) -> Result<Vec<PopulationReport>, AccreteError> {
+        let histogram = MassAxisHistogram::new(&self.a_edges, &self.mass_edges)?;
+        let points = self.grid.points();
+        let jobs: Vec<(usize, u64)> = (0..points.len())
+            .flat_map(|i| self.seeds.clone().map(move |seed| (i, seed)))
+            .collect();
+        let generate = |&(i, seed): &(usize, u64)| -> Result<SystemSummary, AccreteError> {
+            let mut accrete = match self.config {
+                Some(config) => Accrete::from_config(seed, config),
+                None => Accrete::new(seed),
+            };
+            points[i].apply(&mut accrete);
+            let system = accrete.planetary_system_with_observer(&mut NullObserver)?;
+            Ok(SystemSummary::new(&system))
+        };
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let summaries = jobs
+            .par_iter()
+            .map(generate)
+            .collect::<Result<Vec<_>, _>>()?;
+        #[cfg(target_arch = "wasm32")]
+        let summaries = jobs.iter().map(generate).collect::<Result<Vec<_>, _>>()?;
+
+        let systems_per_point = self.seeds.clone().count();
+        let reports = points
+            .iter()
+            .enumerate()
+            .map(|(i, point)| {
+                let start = i * systems_per_point;
+                PopulationReport::new(
+                    *point,
+                    &summaries[start..start + systems_per_point],
+                    histogram.clone(),
+                )
+            })
+            .collect();
+
+        Ok(reports)
+    }
+}
+
+impl Default for Population {
+    fn default() -> Self {
+        Self::new(0..0)
+    }
+}
+
+/// Data of single system used in aggregation
+#[derive(Debug, Clone)]
+struct SystemSummary {
+    planets: usize,
+    gas_giants: usize,
+    dwarf_planets: usize,
+    habitable_zone_planets: usize,
+    moons: Vec<usize>,
+    // (semi-major axis in AU, mass in Earth masses) of every planet
+    bodies: Vec<(f64, f64)>,
+}
+
+impl SystemSummary {
+    fn new(system: &System) -> Self {
+        let (ecosphere_inner, ecosphere_outer) = system.primary_star.ecosphere;
+        let planets = &system.planets;
+
+        Self {
+            planets: planets.len(),
+            gas_giants: planets.iter().filter(|p| p.is_gas_giant).count(),
+            dwarf_planets: planets.iter().filter(|p| p.is_dwarf_planet).count(),
+            habitable_zone_planets: planets
+                .iter()
+                .filter(|p| !p.is_gas_giant && p.a >= ecosphere_inner && p.a <= ecosphere_outer)
+                .count(),
+            moons: planets.iter().map(|p| p.moons.len()).collect(),
+            bodies: planets
+                .iter()
+                .map(|p| (p.a, p.mass * EARTH_MASSES_PER_SOLAR_MASS))
+                .collect(),
+        }
+    }
+}
+
+/// Distribution of integer value
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct CountDistribution {
+    pub mean: f64,
+    pub min: usize,
+    pub max: usize,
+    // counts[n] is number of samples with value n
+    pub counts: Vec<usize>,
+}
+
+impl CountDistribution {
+    pub fn new(values: impl Iterator<Item = usize>) -> Self {
+        let mut counts: Vec<usize> = vec![];
+        for value in values {
+            if counts.len() <= value {
+                counts.resize(value + 1, 0);
+            }
+            counts[value] += 1;
+        }
+        let samples: usize = counts.iter().sum();
+        let total: usize = counts.iter().enumerate().map(|(n, c)| n * c).sum();
+
+        Self {
+            mean: match samples {
+                0 => 0.0,
+                _ => total as f64 / samples as f64,
+            },
+            min: counts.iter().position(|c| *c > 0).unwrap_or(0),
+            max: counts.len().saturating_sub(1),
+            counts,
+        }
+    }
+}
+
+/// Number of planets in bins of semi-major axis and mass.
+/// Values outside of edges are counted in the first or the last bin.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MassAxisHistogram {
+    // units of AU
+    pub a_edges: Vec<f64>,
+    // units of Earth masses
+    pub mass_edges: Vec<f64>,
+    // counts[a_bin][mass_bin]
+    pub counts: Vec<Vec<usize>>,
+}
+
+impl MassAxisHistogram {
+    /// Edges must be finite and increasing, at least two per axis
+    pub fn new(a_edges: &[f64], mass_edges: &[f64]) -> Result<Self, AccreteError> {
+        validate_edges("a_edges", a_edges)?;
+        validate_edges("mass_edges", mass_edges)?;
+
+        Ok(Self {
+            a_edges: a_edges.to_vec(),
+            mass_edges: mass_edges.to_vec(),
+            counts: vec![vec![0; mass_edges.len() - 1]; a_edges.len() - 1],
+        })
+    }
+
+    pub fn add(&mut self, a: f64, earth_masses: f64) {
+        let a_bin = bin(&self.a_edges, a);
+        let mass_bin = bin(&self.mass_edges, earth_masses);
+        if let Some(count) = self
+            .counts
+            .get_mut(a_bin)
+            .and_then(|mass_bins| mass_bins.get_mut(mass_bin))
+        {
+            *count += 1;
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.counts.iter().flatten().sum()
+    }
+
+    /// One row per bin: a_min,a_max,mass_min,mass_max,count
+    pub fn to_csv(&self) -> String {
+        let mut rows = vec!["a_min,a_max,mass_min,mass_max,count".to_string()];
+        for (i, a_bins) in self.counts.iter().enumerate() {
+            for (j, count) in a_bins.iter().enumerate() {
+                rows.push(format!(
+                    "{},{},{},{},{}",
+                    self.a_edges[i],
+                    self.a_edges[i + 1],
+                    self.mass_edges[j],
+                    self.mass_edges[j + 1],
+                    count
+                ));
+            }
+        }
+        rows.join("\n")
+    }
+}
+
+fn validate_edges(name: &'static str, edges: &[f64]) -> Result<(), AccreteError> {
+    if edges.len() < 2 {
+        return Err(AccreteError::InvalidParameter {
+            name,
+            value: edges.len() as f64,
+        });
+    }
+    match edges
+        .iter()
+        .enumerate()
+        .find(|(i, edge)| !edge.is_finite() || (*i > 0 && **edge <= edges[i - 1]))
+    {
+        Some((_, edge)) => Err(AccreteError::InvalidParameter { name, value: *edge }),
+        None => Ok(()),
+    }
+}
+
+fn bin(edges: &[f64], value: f64) -> usize {
+    let bins = edges.len().saturating_sub(1);
+    edges
+        .iter()
+        .skip(1)
+        .take(bins.saturating_sub(1))
+        .position(|edge| value < *edge)
+        .unwrap_or(bins.saturating_sub(1))
+}
+
+/// Statistics of systems generated for one grid point
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PopulationReport {
+    pub parameters: GridPoint,
+    pub systems: usize,
+    // planets per system
+    pub planet_count: CountDistribution,
+    // moons per planet
+    pub moon_count: CountDistribution,
+    // share of gas giants among all planets
+    pub gas_giant_fraction: f64,
+    // share of dwarf planets among all planets
+    pub dwarf_planet_fraction: f64,
+    // share of systems with at least one rocky planet inside of ecosphere
+    pub habitable_zone_occupancy: f64,
+    pub mass_a_histogram: MassAxisHistogram,
+}
+
+impl PopulationReport {
+    /// Planets are counted into empty histogram
+    fn new(
+        parameters: GridPoint,
+        summaries: &[SystemSummary],
+        mut mass_a_histogram: MassAxisHistogram,
+    ) -> Self {
+        let planets: usize = summaries.iter().map(|s| s.planets).sum();
+        let gas_giants: usize = summaries.iter().map(|s| s.gas_giants).sum();
+        let dwarf_planets: usize = summaries.iter().map(|s| s.dwarf_planets).sum();
+        let occupied = summaries
+            .iter()
+            .filter(|s| s.habitable_zone_planets > 0)
+            .count();
+        for (a, earth_masses) in summaries.iter().flat_map(|s| s.bodies.iter()) {
+            mass_a_histogram.add(*a, *earth_masses);
+        }
+
+        Self {
+            parameters,
+            systems: summaries.len(),
+            planet_count: CountDistribution::new(summaries.iter().map(|s| s.planets)),
+            moon_count: CountDistribution::new(
+                summaries.iter().flat_map(|s| s.moons.iter().copied()),
+            ),
+            gas_giant_fraction: fraction(gas_giants, planets),
+            dwarf_planet_fraction: fraction(dwarf_planets, planets),
+            habitable_zone_occupancy: fraction(occupied, summaries.len()),
+            mass_a_histogram,
+        }
+    }
+}
+
+fn fraction(count: usize, total: usize) -> f64 {
+    match total {
+        0 => 0.0,
+        _ => count as f64 / total as f64,
+    }
+}
+
+/// Reports with all distributions as JSON array
+pub fn reports_to_json(reports: &[PopulationReport]) -> Result<String, AccreteError> {
+    serde_json::to_string_pretty(reports).map_err(|e| AccreteError::Serialization(e.to_string()))
+}
+
+/// One row per report with grid parameters and summary statistics, empty cells for parameters kept from configuration
+pub fn reports_to_csv(reports: &[PopulationReport]) -> String {
+    let mut rows = vec!["stellar_mass,metallicity,dust_density_coeff,k,cloud_eccentricity,b,systems,mean_planets,min_planets,max_planets,mean_moons,gas_giant_fraction,dwarf_planet_fraction,habitable_zone_occupancy".to_string()];
+    for r in reports.iter() {
+        let GridPoint {
+            stellar_mass,
+            metallicity,
+            dust_density_coeff,
+            k,
+            cloud_eccentricity,
+            b,
+        } = r.parameters;
+        let cell = |value: Option<f64>| value.map(|v| v.to_string()).unwrap_or_default();
+        rows.push(format!(
+            "{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            cell(stellar_mass),
+            cell(metallicity),
+            cell(dust_density_coeff),
+            cell(k),
+            cell(cloud_eccentricity),
+            cell(b),
+            r.systems,
+            r.planet_count.mean,
+            r.planet_count.min,
+            r.planet_count.max,
+            r.moon_count.mean,
+            r.gas_giant_fraction,
+            r.dwarf_planet_fraction,
+            r.habitable_zone_occupancy,
+        ));
+    }
+    rows.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_points_cover_all_combinations() {
+        let grid = ParameterGrid {
+            k: vec![50.0, 100.0],
+            b: vec![1.0e-5, 1.1e-5, 1.2e-5],
+            ..Default::default()
+        };
+        let points = grid.points();
+        assert_eq!(points.len(), 6);
+        assert!(points.iter().all(|p| p.stellar_mass.is_none()));
+        assert_eq!(
+            ParameterGrid::default().points(),
+            vec![GridPoint::default()]
+        );
+    }
+
+    #[test]
+    fn population_report_aggregates_systems() {
+        let grid = ParameterGrid {
+            cloud_eccentricity: vec![0.15, 0.25],
+            ..Default::default()
+        };
+        let population = Population::new(0..3).grid(grid);
+        let reports = population.run().expect("Failed to generate population");
+        assert_eq!(reports.len(), 2);
+
+        for report in reports.iter() {
+            assert_eq!(report.systems, 3);
+            assert_eq!(report.planet_count.counts.iter().sum::<usize>(), 3);
+            assert_eq!(
+                report.mass_a_histogram.total() as f64,
+                report.planet_count.mean * 3.0
+            );
+            assert!((0.0..=1.0).contains(&report.gas_giant_fraction));
+            assert!((0.0..=1.0).contains(&report.habitable_zone_occupancy));
+        }
+
+        assert_eq!(
+            reports,
+            population.run().expect("Failed to generate population")
+        );
+        assert_eq!(reports_to_csv(&reports).lines().count(), 3);
+        let json = reports_to_json(&reports).expect("Failed to serialize reports");
+        let restored: Vec<PopulationReport> =
+            serde_json::from_str(&json).expect("Failed to deserialize reports");
+        assert_eq!(restored.len(), 2);
+    }
+
+    #[test]
+    fn metal_rich_stars_host_more_gas_giants() {
+        let grid = ParameterGrid {
+            stellar_mass: vec![1.0],
+            metallicity: vec![-0.5, 0.3],
+            ..Default::default()
+        };
+        let reports = Population::new(0..3)
+            .grid(grid)
+            .run()
+            .expect("Failed to generate population");
+        assert!(reports[1].gas_giant_fraction > reports[0].gas_giant_fraction);
+        assert!(reports_to_csv(&reports).contains("\n1,-0.5,"));
+    }
+
+    #[test]
+    fn population_uses_histogram_edges() {
+        let reports = Population::new(0..2)
+            .histogram_edges(vec![0.1, 1.0, 100.0], vec![0.01, 1.0, 1000.0])
+            .run()
+            .expect("Failed to generate population");
+        let histogram = &reports[0].mass_a_histogram;
+        assert_eq!(histogram.a_edges, vec![0.1, 1.0, 100.0]);
+        assert_eq!(histogram.counts.len(), 2);
+        assert_eq!(histogram.total() as f64, reports[0].planet_count.mean * 2.0);
+
+        assert!(matches!(
+            Population::new(0..2)
+                .histogram_edges(vec![1.0, 0.1], vec![0.01, 1.0])
+                .run(),
+            Err(AccreteError::InvalidParameter {
+                name: "a_edges",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn count_distribution() {
+        let distribution = CountDistribution::new([2, 4, 4, 6].into_iter());
+        assert_eq!(distribution.mean, 4.0);
+        assert_eq!(distribution.min, 2);
+        assert_eq!(distribution.max, 6);
+        assert_eq!(distribution.counts, vec![0, 0, 1, 0, 2, 0, 1]);
+    }
+
+    #[test]
+    fn histogram_edges_are_validated() {
+        let mut histogram =
+            MassAxisHistogram::new(&[1.0, 10.0], &[0.1, 1.0, 10.0]).expect("Invalid edges");
+        histogram.add(0.5, 5.0);
+        histogram.add(20.0, 0.01);
+        assert_eq!(histogram.counts, vec![vec![1, 1]]);
+
+        for (a_edges, mass_edges) in [
+            (vec![1.0], vec![0.1, 1.0]),
+            (vec![], vec![0.1, 1.0]),
+            (vec![1.0, 10.0], vec![1.0, 0.1]),
+            (vec![1.0, f64::NAN], vec![0.1, 1.0]),
+        ] {
+            assert!(matches!(
+                MassAxisHistogram::new(&a_edges, &mass_edges),
+                Err(AccreteError::InvalidParameter { .. })
+            ));
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use crate::consts::*;
+use crate::enviro::inspired_partial_pressure;
+
+use serde::{Deserialize, Serialize};
+
+/// Properties of atmospheric gas, from StarGen gas table
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GasProperties {
+    // atomic number, or AN_* index for molecules
+    pub num: f64,
+    pub symbol: &'static str,
+    pub name: &'static str,
+    // atomic or molecular weight
+    pub weight: f64,
+    // melting point, Kelvin
+    pub melt: f64,
+    // boiling point, Kelvin
+    pub boil: f64,
+    // density, g/cc
+    pub density: f64,
+    // abundance in Earth crust
+    pub abunde: f64,
+    // abundance in Sun
+    pub abunds: f64,
+    pub reactivity: f64,
+    // maximum inspired partial pressure, millibars
+    pub max_ipp: f64,
+}
+
+/// Gases considered in atmosphere composition. Maximum inspired partial pressure of hydrogen and water vapor is not limited, hydrogen atmosphere is unbreathable because of lack of oxygen.
+#[rustfmt::skip]
+pub const GASES: [GasProperties; 13] = [
+    //  num      symbol name              weight    melt    boil    density    abunde       abunds       reactivity max_ipp
+    gas(AN_H,    "H",   "Hydrogen",       1.0079,   14.06,  20.40,  8.99e-05,  0.00125893,  27925.4,     1.0,       INCREDIBLY_LARGE_NUMBER),
+    gas(AN_HE,   "He",  "Helium",         4.0026,   3.46,   4.20,   0.0001787, 7.94328e-09, 2722.7,      0.0,       MAX_HE_IPP),
+    gas(AN_N,    "N",   "Nitrogen",       14.0067,  63.34,  77.40,  0.0012506, 1.99526e-05, 3.13329,     0.0,       MAX_N2_IPP),
+    gas(AN_O,    "O",   "Oxygen",         15.9994,  54.80,  90.20,  0.001429,  0.501187,    23.8232,     10.0,      MAX_O2_IPP),
+    gas(AN_NE,   "Ne",  "Neon",           20.1700,  24.53,  27.10,  0.0009,    5.01187e-09, 3.4435e-5,   0.0,       MAX_NE_IPP),
+    gas(AN_AR,   "Ar",  "Argon",          39.9480,  84.00,  87.30,  0.0017824, 3.16228e-06, 0.100925,    0.0,       MAX_AR_IPP),
+    gas(AN_KR,   "Kr",  "Krypton",        83.8000,  116.60, 119.70, 0.003708,  1e-10,       4.4978e-05,  0.0,       MAX_KR_IPP),
+    gas(AN_XE,   "Xe",  "Xenon",          131.3000, 161.30, 165.00, 0.00588,   3.16228e-11, 4.69894e-06, 0.0,       MAX_XE_IPP),
+    gas(AN_NH3,  "NH3", "Ammonia",        17.0000,  195.46, 239.66, 0.001,     0.002,       0.0001,      1.0,       MAX_NH3_IPP),
+    gas(AN_H2O,  "H2O", "Water",          18.0000,  273.16, 373.16, 1.000,     0.03,        0.001,       0.0,       INCREDIBLY_LARGE_NUMBER),
+    gas(AN_CO2,  "CO2", "Carbon dioxide", 44.0000,  194.66, 194.66, 0.001,     0.01,        0.0005,      0.0,       MAX_CO2_IPP),
+    gas(AN_O3,   "O3",  "Ozone",          48.0000,  80.16,  161.16, 0.001,     0.001,       0.000001,    2.0,       MAX_O3_IPP),
+    gas(AN_CH4,  "CH4", "Methane",        16.0000,  90.16,  109.16, 0.010,     0.005,       0.0001,      1.0,       MAX_CH4_IPP),
+];
+
+#[allow(clippy::too_many_arguments)]
+const fn gas(
+    num: f64,
+    symbol: &'static str,
+    name: &'static str,
+    weight: f64,
+    melt: f64,
+    boil: f64,
+    density: f64,
+    abunde: f64,
+    abunds: f64,
+    reactivity: f64,
+    max_ipp: f64,
+) -> GasProperties {
+    GasProperties {
+        num,
+        symbol,
+        name,
+        weight,
+        melt,
+        boil,
+        density,
+        abunde,
+        abunds,
+        reactivity,
+        max_ipp,
+    }
+}
+
+/// Gas in planetary atmosphere
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AtmosphericGas {
+    pub symbol: String,
+    pub name: String,
+    // units of bar
+    pub partial_pressure_bar: f64,
+    // inspired partial pressure over toxicity limit
+    pub is_toxic: bool,
+    // oxygen with inspired partial pressure in breathable range
+    pub is_breathable: bool,
+}
+
+impl AtmosphericGas {
+    pub fn new(gas: &GasProperties, partial_pressure_bar: f64, surface_pressure_bar: f64) -> Self {
+        let ipp = inspired_partial_pressure(
+            &(surface_pressure_bar * MILLIBARS_PER_BAR),
+            &(partial_pressure_bar * MILLIBARS_PER_BAR),
+        );
+        let is_oxygen = gas.num == AN_O;
+
+        AtmosphericGas {
+            symbol: gas.symbol.to_string(),
+            name: gas.name.to_string(),
+            partial_pressure_bar,
+            is_toxic: ipp > gas.max_ipp,
+            is_breathable: is_oxygen && (MIN_O2_IPP..=MAX_O2_IPP).contains(&ipp),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Accrete;
+
+    #[test]
+    fn partial_pressures_sum_to_surface_pressure() {
+        let mut accrete = Accrete::new(1);
+        let system = accrete
+            .planetary_system()
+            .expect("Failed to generate planetary system");
+        for planet in system.planets.iter().filter(|p| !p.atmosphere.is_empty()) {
+            let total: f64 = planet
+                .atmosphere
+                .iter()
+                .map(|gas| gas.partial_pressure_bar)
+                .sum();
+            let surface_pressure_bar = planet
+                .surface_pressure_bar
+                .expect("Failed to find surface pressure");
+            assert!((total - surface_pressure_bar).abs() < 1e-6 * surface_pressure_bar);
+            assert!(planet
+                .atmosphere
+                .windows(2)
+                .all(|w| w[0].partial_pressure_bar >= w[1].partial_pressure_bar));
+        }
+    }
+}
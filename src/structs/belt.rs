@@ -0,0 +1,198 @@
+use crate::consts::*;
+use crate::enviro::*;
+use crate::structs::condensation::CondensationLines;
+use crate::structs::disk_profile::DiskProfile;
+use crate::structs::dust::*;
+use crate::structs::Planetesimal;
+use crate::utils::*;
+
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum BeltKind {
+    // belt between planets
+    AsteroidBelt,
+    // belt beyond outermost planet
+    KuiperBelt,
+}
+
+/// Belt composition by position relative to snow line
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum BeltComposition {
+    Rocky,
+    Icy,
+}
+
+/// Belt of small bodies formed by leftover dust and dwarf planets not cleared their orbits
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Belt {
+    pub kind: BeltKind,
+    pub composition: BeltComposition,
+    // units of AU
+    pub inner_edge: f64,
+    pub outer_edge: f64,
+    // total mass of dust and members, solar masses
+    pub mass: f64,
+    pub earth_masses: f64,
+    // ids of largest dwarf planets in belt, sorted by diminishing mass
+    pub largest_members: Vec<String>,
+    pub id: String,
+}
+
+/// Part of belt before classification
+struct BeltSpan {
+    inner_edge: f64,
+    outer_edge: f64,
+    mass: f64,
+    members: Vec<(f64, String)>,
+}
+
+/// Form belts from dust bands left after accretion and dwarf planets. Overlapping sources are merged into one belt.
+pub fn form_belts(
+    planets: &[Planetesimal],
+    dust_bands: &[DustBand],
+    planetesimal_inner_bound: &f64,
+    disk_profile: &dyn DiskProfile,
+    dust_density_coeff: &f64,
+    stellar_mass: &f64,
+    condensation_lines: &CondensationLines,
+    rng: &mut dyn RngCore,
+) -> Vec<Belt> {
+    let dust_spans = dust_bands
+        .iter()
+        .filter(|band| band.dust_present && band.outer_edge > *planetesimal_inner_bound)
+        .map(|band| {
+            let inner_edge = band.inner_edge.max(*planetesimal_inner_bound);
+            BeltSpan {
+                inner_edge,
+                outer_edge: band.outer_edge,
+                mass: belt_dust_mass(
+                    &inner_edge,
+                    &band.outer_edge,
+                    disk_profile,
+                    dust_density_coeff,
+                    stellar_mass,
+                ),
+                members: Vec::new(),
+            }
+        });
+    let dwarf_planet_spans = planets
+        .iter()
+        .filter(|p| p.is_dwarf_planet)
+        .map(|p| BeltSpan {
+            inner_edge: inner_effect_limit(&p.a, &p.e, &p.mass),
+            outer_edge: outer_effect_limit(&p.a, &p.e, &p.mass),
+            mass: p.mass,
+            members: vec![(p.mass, p.id.clone())],
+        });
+
+    let mut spans: Vec<BeltSpan> = dust_spans.chain(dwarf_planet_spans).collect();
+    spans.sort_by(|s1, s2| s1.inner_edge.total_cmp(&s2.inner_edge));
+    let spans = spans
+        .into_iter()
+        .fold(Vec::new(), |mut acc: Vec<BeltSpan>, span| {
+            match acc.last_mut() {
+                Some(last) if span.inner_edge <= last.outer_edge => {
+                    last.outer_edge = last.outer_edge.max(span.outer_edge);
+                    last.mass += span.mass;
+                    last.members.extend(span.members);
+                }
+                _ => acc.push(span),
+            }
+            acc
+        });
+
+    let outermost_planet = planets
+        .iter()
+        .filter(|p| !p.is_dwarf_planet)
+        .map(|p| p.a)
+        .fold(0.0, f64::max);
+
+    spans
+        .into_iter()
+        .map(|mut span| {
+            span.members.sort_by(|m1, m2| m2.0.total_cmp(&m1.0));
+            let kind = match span.inner_edge > outermost_planet {
+                true => BeltKind::KuiperBelt,
+                false => BeltKind::AsteroidBelt,
+            };
+            let center = (span.inner_edge + span.outer_edge) / 2.0;
+            let composition = match center < condensation_lines.water {
+                true => BeltComposition::Rocky,
+                false => BeltComposition::Icy,
+            };
+
+            Belt {
+                kind,
+                composition,
+                inner_edge: span.inner_edge,
+                outer_edge: span.outer_edge,
+                mass: span.mass,
+                earth_masses: get_earth_mass(span.mass),
+                largest_members: span
+                    .members
+                    .into_iter()
+                    .take(BELT_LARGEST_MEMBERS)
+                    .map(|(_, id)| id)
+                    .collect(),
+                id: random_id(rng),
+            }
+        })
+        .collect()
+}
+
+/// Mass of dust between edges of belt, in solar masses. Dust is counted in the same volume as swept by protoplanet during accretion.
+pub fn belt_dust_mass(
+    inner_edge: &f64,
+    outer_edge: &f64,
+    disk_profile: &dyn DiskProfile,
+    dust_density_coeff: &f64,
+    stellar_mass: &f64,
+) -> f64 {
+    let step = (outer_edge - inner_edge) / BELT_DUST_MASS_STEPS as f64;
+    (0..BELT_DUST_MASS_STEPS)
+        .map(|i| {
+            let r = inner_edge + step * (i as f64 + 0.5);
+            4.0 * PI
+                * r.powf(2.0)
+                * reduced_mass(&PROTOPLANET_MASS)
+                * disk_profile.dust_density(dust_density_coeff, stellar_mass, &r)
+                * step
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Accrete;
+
+    #[test]
+    fn belts_do_not_overlap_and_list_dwarf_planets() {
+        for seed in 1..10 {
+            let mut accrete = Accrete::new(seed);
+            let system = accrete
+                .planetary_system()
+                .expect("Failed to generate planetary system");
+            assert!(!system.belts.is_empty());
+            assert!(system
+                .belts
+                .windows(2)
+                .all(|w| w[0].outer_edge < w[1].inner_edge));
+            for belt in system.belts.iter() {
+                assert!(belt.mass > 0.0);
+                assert!(belt.largest_members.len() <= BELT_LARGEST_MEMBERS);
+                for id in belt.largest_members.iter() {
+                    let member = system
+                        .planets
+                        .iter()
+                        .find(|p| &p.id == id)
+                        .expect("Failed to find belt member");
+                    assert!(member.is_dwarf_planet);
+                    assert!(member.a >= belt.inner_edge && member.a <= belt.outer_edge);
+                }
+            }
+        }
+    }
+}
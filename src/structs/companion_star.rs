@@ -0,0 +1,132 @@
+use crate::structs::PrimaryStar;
+
+use serde::{Deserialize, Serialize};
+
+/// Binary companion parameters.
+/// **stellar_mass** - companion mass in solar masses.
+/// **separation** - semi-major axis of the binary orbit in AU.
+/// **eccentricity** - eccentricity of the binary orbit.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct CompanionStarConfig {
+    pub stellar_mass: f64,
+    pub separation: f64,
+    pub eccentricity: f64,
+}
+
+impl CompanionStarConfig {
+    pub fn new(stellar_mass: f64, separation: f64, eccentricity: f64) -> Self {
+        Self {
+            stellar_mass,
+            separation,
+            eccentricity,
+        }
+    }
+}
+
+/// Companion star orbiting the primary.
+/// Planets can keep stable S-type orbits around primary star inside of `s_type_limit` and P-type (circumbinary) orbits around both stars outside of `p_type_limit`. Dust cloud between limits is cleared by companion.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct CompanionStar {
+    pub star: PrimaryStar,
+    pub separation: f64,
+    pub eccentricity: f64,
+    // companion mass / total mass of binary, unitless
+    pub mass_ratio: f64,
+    // units of AU
+    pub s_type_limit: f64,
+    // units of AU
+    pub p_type_limit: f64,
+}
+
+impl CompanionStar {
+    pub fn new(primary_star_mass: f64, metallicity: f64, config: &CompanionStarConfig) -> Self {
+        // Both stars are formed from the same cloud
+        let star = PrimaryStar::new(config.stellar_mass, metallicity);
+        Self::with_star(star, primary_star_mass, config)
+    }
+
+    /// Companion of given initial mass evolved to age in years, stability limits follow current masses of both stars
+    pub fn at_age(
+        primary_star_mass: f64,
+        metallicity: f64,
+        age: f64,
+        config: &CompanionStarConfig,
+    ) -> Self {
+        let star = PrimaryStar::at_age(config.stellar_mass, metallicity, age);
+        Self::with_star(star, primary_star_mass, config)
+    }
+
+    fn with_star(star: PrimaryStar, primary_star_mass: f64, config: &CompanionStarConfig) -> Self {
+        let CompanionStarConfig {
+            separation,
+            eccentricity,
+            ..
+        } = *config;
+        let mass_ratio = star.stellar_mass / (primary_star_mass + star.stellar_mass);
+        let s_type_limit = s_type_critical_axis(separation, eccentricity, mass_ratio);
+        let p_type_limit = p_type_critical_axis(separation, eccentricity, mass_ratio);
+
+        Self {
+            star,
+            separation,
+            eccentricity,
+            mass_ratio,
+            s_type_limit,
+            p_type_limit,
+        }
+    }
+
+    /// Planet orbit of semi-major axis "a" encloses both stars
+    pub fn is_circumbinary(&self, a: &f64) -> bool {
+        *a >= self.p_type_limit
+    }
+}
+
+/// Largest stable orbit around primary star in AU.
+/// [Holman & Wiegert (1999), eq. 1](https://arxiv.org/abs/astro-ph/9809315)
+pub fn s_type_critical_axis(separation: f64, eccentricity: f64, mass_ratio: f64) -> f64 {
+    let (e, mu) = (eccentricity, mass_ratio);
+    let critical_axis = 0.464 - 0.380 * mu - 0.631 * e + 0.586 * mu * e + 0.150 * e.powf(2.0)
+        - 0.198 * mu * e.powf(2.0);
+    separation * critical_axis.max(0.0)
+}
+
+/// Smallest stable circumbinary orbit in AU.
+/// [Holman & Wiegert (1999), eq. 3](https://arxiv.org/abs/astro-ph/9809315)
+pub fn p_type_critical_axis(separation: f64, eccentricity: f64, mass_ratio: f64) -> f64 {
+    let (e, mu) = (eccentricity, mass_ratio);
+    let critical_axis =
+        1.60 + 5.10 * e - 2.22 * e.powf(2.0) + 4.12 * mu - 4.27 * e * mu - 5.09 * mu.powf(2.0)
+            + 4.61 * e.powf(2.0) * mu.powf(2.0);
+    separation * critical_axis
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_alpha_centauri_limits() {
+        // Alpha Centauri A/B: 1.1 + 0.9 solar masses, a = 23.4 AU, e = 0.52
+        let config = CompanionStarConfig::new(0.9, 23.4, 0.52);
+        let companion = CompanionStar::new(1.1, 0.0, &config);
+        assert!(companion.s_type_limit > 2.5 && companion.s_type_limit < 3.0);
+        assert!(companion.p_type_limit > 85.0 && companion.p_type_limit < 90.0);
+    }
+
+    #[test]
+    fn evolved_companion_limits_follow_masses() {
+        let config = CompanionStarConfig::new(0.9, 23.4, 0.52);
+        let companion = CompanionStar::at_age(0.6, 0.0, 1.0e9, &config);
+        assert_eq!(companion.star.age, 1.0e9);
+        assert_eq!(companion.mass_ratio, 0.9 / 1.5);
+        assert_eq!(
+            companion.s_type_limit,
+            s_type_critical_axis(23.4, 0.52, companion.mass_ratio)
+        );
+        assert_eq!(
+            companion.p_type_limit,
+            p_type_critical_axis(23.4, 0.52, companion.mass_ratio)
+        );
+    }
+}
@@ -0,0 +1,169 @@
+use crate::consts::*;
+
+use serde::{Deserialize, Serialize};
+
+/// Bulk composition of planetesimal, masses of accreted iron, silicates, ices and hydrogen-helium gas in solar masses.
+/// Inventories are summed on mergers, collisions strip envelope and mantle before iron core.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub struct BulkComposition {
+    pub iron: f64,
+    pub silicate: f64,
+    pub ice: f64,
+    pub gas: f64,
+}
+
+impl BulkComposition {
+    /// Inventory of body of given mass, accreted solids with given ice-to-rock ratio and gas
+    pub fn accreted(mass: &f64, gas_mass: &f64, ice_to_rock: &f64) -> Self {
+        let gas = gas_mass.clamp(0.0, *mass);
+        let solids = mass - gas;
+        let rock = solids / (1.0 + ice_to_rock);
+
+        Self {
+            iron: rock * IRON_FRACTION_OF_ROCK,
+            silicate: rock * (1.0 - IRON_FRACTION_OF_ROCK),
+            ice: solids - rock,
+            gas,
+        }
+    }
+
+    pub fn total(&self) -> f64 {
+        self.iron + self.silicate + self.ice + self.gas
+    }
+
+    /// Inventory of body formed by merger of two bodies
+    pub fn merge(&self, other: &BulkComposition) -> Self {
+        Self {
+            iron: self.iron + other.iron,
+            silicate: self.silicate + other.silicate,
+            ice: self.ice + other.ice,
+            gas: self.gas + other.gas,
+        }
+    }
+
+    /// Inventory left after collision removed given mass. Hydrogen-helium envelope is blown off first, then icy and silicate mantle, iron core is lost last.
+    pub fn stripped(&self, lost_mass: &f64) -> Self {
+        let mut lost_mass = lost_mass.max(0.0);
+        let mut strip = |component: f64| {
+            let lost = component.min(lost_mass);
+            lost_mass -= lost;
+            component - lost
+        };
+
+        Self {
+            gas: strip(self.gas),
+            ice: strip(self.ice),
+            silicate: strip(self.silicate),
+            iron: strip(self.iron),
+        }
+    }
+
+    pub fn iron_fraction(&self) -> f64 {
+        self.fraction(self.iron)
+    }
+
+    pub fn silicate_fraction(&self) -> f64 {
+        self.fraction(self.silicate)
+    }
+
+    pub fn ice_fraction(&self) -> f64 {
+        self.fraction(self.ice)
+    }
+
+    pub fn gas_fraction(&self) -> f64 {
+        self.fraction(self.gas)
+    }
+
+    fn fraction(&self, component: f64) -> f64 {
+        let total = self.total();
+        match total > 0.0 {
+            true => component / total,
+            false => 0.0,
+        }
+    }
+
+    /// Radius of iron, silicate and ice body without gas envelope, km.
+    /// Rock-iron and ice-rock mass-radius relations of Fortney et al. 2007, smaller bodies keep density of the lightest fitted one.
+    pub fn solid_radius(&self) -> f64 {
+        let solids = self.iron + self.silicate + self.ice;
+        let rock = self.iron + self.silicate;
+        let earth_masses = solids * EARTH_MASSES_PER_SOLAR_MASS;
+        let log_mass = earth_masses.max(MIN_FITTED_SOLID_MASS).log10();
+        let (silicate_fraction, ice_fraction) = match solids > 0.0 {
+            true => (
+                self.silicate / rock.max(f64::MIN_POSITIVE),
+                self.ice / solids,
+            ),
+            false => (1.0 - IRON_FRACTION_OF_ROCK, 0.0),
+        };
+        let ice_rock_radius = |ice_fraction: f64| {
+            (0.0912 * ice_fraction + 0.1603) * log_mass.powf(2.0)
+                + (0.3330 * ice_fraction + 0.7387) * log_mass
+                + (0.4639 * ice_fraction + 1.1193)
+        };
+        let rock_iron_radius = (0.0592 * silicate_fraction + 0.0975) * log_mass.powf(2.0)
+            + (0.2337 * silicate_fraction + 0.4938) * log_mass
+            + (0.3102 * silicate_fraction + 0.7932);
+
+        let mut earth_radii =
+            rock_iron_radius * ice_rock_radius(ice_fraction) / ice_rock_radius(0.0);
+        if earth_masses < MIN_FITTED_SOLID_MASS {
+            earth_radii *= (earth_masses / MIN_FITTED_SOLID_MASS).powf(1.0 / 3.0);
+        }
+        earth_radii * EARTH_RADIUS_IN_KM
+    }
+
+    /// Thickness of hydrogen-helium envelope of sub-Neptune, km (Lopez & Fortney 2014).
+    /// Stellar flux is in units of flux received by Earth, system age in years.
+    pub fn envelope_thickness(&self, stellar_flux: &f64, system_age: &f64) -> f64 {
+        let earth_masses = self.total() * EARTH_MASSES_PER_SOLAR_MASS;
+        let earth_radii = 2.06
+            * earth_masses.powf(-0.21)
+            * (self.gas_fraction() / 0.05).powf(0.59)
+            * stellar_flux.powf(0.044)
+            * (system_age.max(MIN_ENVELOPE_AGE) / 5.0E9).powf(-0.11);
+        earth_radii * EARTH_RADIUS_IN_KM
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn earth_like_and_icy_radii() {
+        let earth = BulkComposition::accreted(&(1.0 / EARTH_MASSES_PER_SOLAR_MASS), &0.0, &0.0);
+        assert!((earth.solid_radius() / EARTH_RADIUS_IN_KM - 1.0).abs() < 0.01);
+        assert!((earth.iron_fraction() - IRON_FRACTION_OF_ROCK).abs() < 1.0E-9);
+
+        let water_world = BulkComposition::accreted(
+            &(1.0 / EARTH_MASSES_PER_SOLAR_MASS),
+            &0.0,
+            &WATER_ICE_TO_ROCK,
+        );
+        assert!(water_world.solid_radius() > 1.2 * earth.solid_radius());
+
+        let sub_neptune = BulkComposition::accreted(
+            &(5.0 / EARTH_MASSES_PER_SOLAR_MASS),
+            &(0.25 / EARTH_MASSES_PER_SOLAR_MASS),
+            &0.0,
+        );
+        let envelope = sub_neptune.envelope_thickness(&1.0, &5.0E9);
+        assert!((envelope / EARTH_RADIUS_IN_KM - 2.06 * 5.0_f64.powf(-0.21)).abs() < 1.0E-9);
+    }
+
+    #[test]
+    fn stripping_enriches_iron() {
+        let body = BulkComposition::accreted(&1.0, &0.1, &0.5);
+        let merged = body.merge(&body);
+        assert!((merged.total() - 2.0).abs() < 1.0E-12);
+
+        let stripped = merged.stripped(&1.2);
+        assert_eq!(stripped.gas, 0.0);
+        assert_eq!(stripped.ice, 0.0);
+        assert_eq!(stripped.iron, merged.iron);
+        assert!((stripped.total() - 0.8).abs() < 1.0E-12);
+        assert!(stripped.iron_fraction() > merged.iron_fraction());
+        assert_eq!(merged.stripped(&3.0).total(), 0.0);
+    }
+}
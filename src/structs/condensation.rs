@@ -0,0 +1,182 @@
+use crate::consts::*;
+use crate::structs::disk_profile::DiskProfile;
+
+use serde::{Deserialize, Serialize};
+
+/// Condensation of ices in cloud.
+/// **volatile_ices** - CO2 and CO ices condense beyond their lines in addition to water ice.
+/// Beyond each line density of solids is raised by condensed ice, while density of gas is kept.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CondensationConfig {
+    pub volatile_ices: bool,
+}
+
+impl CondensationConfig {
+    pub fn new(volatile_ices: bool) -> Self {
+        Self { volatile_ices }
+    }
+}
+
+/// Condensation fronts of ices in cloud around young primary star, AU
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub struct CondensationLines {
+    // snow line
+    pub water: f64,
+    // none if volatile ices are not condensed
+    pub co2: Option<f64>,
+    pub co: Option<f64>,
+}
+
+impl CondensationLines {
+    pub fn new(stellar_luminosity: &f64, volatile_ices: bool) -> Self {
+        let volatile_line = |condensation_temp: f64| match volatile_ices {
+            true => Some(condensation_line(stellar_luminosity, &condensation_temp)),
+            false => None,
+        };
+
+        Self {
+            water: condensation_line(stellar_luminosity, &WATER_CONDENSATION_TEMP),
+            co2: volatile_line(CO2_CONDENSATION_TEMP),
+            co: volatile_line(CO_CONDENSATION_TEMP),
+        }
+    }
+
+    /// Mass of ices condensed per unit mass of rock at given distance from star
+    pub fn ice_to_rock(&self, r: &f64) -> f64 {
+        let beyond = |line: Option<f64>, ice_to_rock: f64| match line {
+            Some(line) if *r >= line => ice_to_rock,
+            _ => 0.0,
+        };
+
+        beyond(Some(self.water), WATER_ICE_TO_ROCK)
+            + beyond(self.co2, WATER_ICE_TO_ROCK * CO2_ICE_TO_WATER_ICE)
+            + beyond(self.co, WATER_ICE_TO_ROCK * CO_ICE_TO_WATER_ICE)
+    }
+}
+
+/// Distance in AU, where midplane of passive disk cools down to condensation temperature
+pub fn condensation_line(stellar_luminosity: &f64, condensation_temp: &f64) -> f64 {
+    (DISK_TEMPERATURE_1AU / condensation_temp).powf(2.0) * stellar_luminosity.sqrt()
+}
+
+/// Dust density profile with solids raised by ices condensed beyond condensation lines, gas follows original profile.
+/// Water ice is not added to profiles with their own snow line.
+pub struct CondensingProfile<'a> {
+    pub disk_profile: &'a dyn DiskProfile,
+    pub condensation_lines: CondensationLines,
+}
+
+impl CondensingProfile<'_> {
+    /// Mass of ices condensed per unit mass of solids of original profile
+    fn condensed_ice(&self, r: &f64) -> f64 {
+        let ice_to_rock = self.condensation_lines.ice_to_rock(r);
+        match self.disk_profile.has_snow_line() && *r >= self.condensation_lines.water {
+            true => ice_to_rock - WATER_ICE_TO_ROCK,
+            false => ice_to_rock,
+        }
+    }
+}
+
+impl DiskProfile for CondensingProfile<'_> {
+    fn dust_density(&self, dust_density_coeff: &f64, stellar_mass: &f64, r: &f64) -> f64 {
+        self.disk_profile
+            .dust_density(dust_density_coeff, stellar_mass, r)
+            * (1.0 + self.condensed_ice(r))
+    }
+
+    fn gas_to_dust_ratio(&self, k: &f64, stellar_mass: &f64, r: &f64) -> f64 {
+        let k = self.disk_profile.gas_to_dust_ratio(k, stellar_mass, r);
+        let condensed_ice = self.condensed_ice(r);
+        (k + condensed_ice) / (1.0 + condensed_ice)
+    }
+
+    fn has_snow_line(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Accrete, AccreteBuilder, DiskModel};
+
+    #[test]
+    fn solar_condensation_lines() {
+        let lines = CondensationLines::new(&1.0, true);
+        assert!((lines.water - 2.7).abs() < 0.05);
+        let co2 = lines.co2.expect("Failed to place CO2 line");
+        let co = lines.co.expect("Failed to place CO line");
+        assert!(lines.water < co2 && co2 < co);
+        assert_eq!(lines.ice_to_rock(&1.0), 0.0);
+        assert_eq!(lines.ice_to_rock(&5.0), WATER_ICE_TO_ROCK);
+        assert!(lines.ice_to_rock(&(co + 1.0)) > lines.ice_to_rock(&(co2 + 1.0)));
+        assert_eq!(CondensationLines::new(&1.0, false).co2, None);
+
+        let profile = CondensingProfile {
+            disk_profile: &DiskModel::default(),
+            condensation_lines: lines,
+        };
+        let dust_inside = profile.dust_density(&DUST_DENSITY_COEFF, &1.0, &2.6);
+        let gas_inside = dust_inside * (profile.gas_to_dust_ratio(&K, &1.0, &2.6) - 1.0);
+        let dust_beyond = profile.dust_density(&DUST_DENSITY_COEFF, &1.0, &2.8);
+        let gas_beyond = dust_beyond * (profile.gas_to_dust_ratio(&K, &1.0, &2.8) - 1.0);
+        assert!(dust_beyond > 3.0 * dust_inside);
+        assert!(gas_beyond < gas_inside);
+    }
+
+    #[test]
+    fn planets_beyond_snow_line_are_icy() {
+        for seed in 1..5 {
+            let mut accrete = Accrete::new(seed);
+            let system = accrete
+                .planetary_system()
+                .expect("Failed to generate planetary system");
+            for planet in system.planets.iter() {
+                let composition = planet.bulk_composition;
+                assert!((composition.total() / planet.mass - 1.0).abs() < 1.0E-6);
+                if planet.is_gas_giant {
+                    assert!(composition.gas > 0.0);
+                }
+                if !planet.has_collision && planet.a < system.condensation_lines.water * 0.5 {
+                    assert_eq!(composition.ice, 0.0);
+                }
+                if !planet.has_collision && planet.a > system.condensation_lines.water * 2.0 {
+                    assert!(composition.ice > 0.0);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn hayashi_snow_line_is_not_doubled() {
+        let lines = CondensationLines::new(&1.0, true);
+        let co2 = lines.co2.expect("Failed to place CO2 line");
+        let hayashi = DiskModel::Hayashi;
+        let profile = CondensingProfile {
+            disk_profile: &hayashi,
+            condensation_lines: lines,
+        };
+        let density =
+            |profile: &dyn DiskProfile, r: f64| profile.dust_density(&DUST_DENSITY_COEFF, &1.0, &r);
+
+        let r = (lines.water + co2) / 2.0;
+        assert_eq!(density(&profile, r), density(&hayashi, r));
+        assert_eq!(
+            profile.gas_to_dust_ratio(&K, &1.0, &r),
+            hayashi.gas_to_dust_ratio(&K, &1.0, &r)
+        );
+        let r = co2 + 1.0;
+        let volatile_ice = WATER_ICE_TO_ROCK * CO2_ICE_TO_WATER_ICE;
+        assert!((density(&profile, r) / density(&hayashi, r) - 1.0 - volatile_ice).abs() < 1e-9);
+
+        let mut accrete = AccreteBuilder::new(1)
+            .disk_model(DiskModel::Hayashi)
+            .condensation(CondensationConfig::new(false))
+            .build()
+            .expect("Invalid configuration");
+        let system = accrete
+            .planetary_system()
+            .expect("Failed to generate planetary system");
+        assert!(!system.planets.is_empty());
+    }
+}
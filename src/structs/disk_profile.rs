@@ -0,0 +1,206 @@
+use crate::consts::*;
+use crate::structs::condensation::condensation_line;
+use crate::structs::primary_star::luminosity;
+
+use serde::{Deserialize, Serialize};
+
+/// Radial profile of dust density in protoplanetary cloud, solar masses per AU^3.
+/// Gas density is K times dust density, unless profile changes gas-to-dust ratio across the cloud.
+/// Implemented by [DiskModel], [TabulatedProfile] and closures `Fn(&dust_density_coeff, &stellar_mass, &r) -> f64`.
+///
+/// ```
+/// use accrete::{Accrete, DiskProfile};
+/// use accrete::events_log::observer::NullObserver;
+///
+/// // Truncated Dole's cloud without dust beyond 30 AU
+/// let truncated = |dust_density_coeff: &f64, stellar_mass: &f64, r: &f64| match *r < 30.0 {
+///     true => dust_density_coeff * stellar_mass.sqrt() * (-5.0 * r.powf(1.0 / 3.0)).exp(),
+///     false => 0.0,
+/// };
+/// let mut accrete = Accrete::new(1);
+/// let system = accrete.planetary_system_with_disk_profile(&truncated, &mut NullObserver);
+/// ```
+pub trait DiskProfile {
+    fn dust_density(&self, dust_density_coeff: &f64, stellar_mass: &f64, r: &f64) -> f64;
+
+    /// Ratio of total density of gas and dust to density of dust
+    fn gas_to_dust_ratio(&self, k: &f64, _stellar_mass: &f64, _r: &f64) -> f64 {
+        *k
+    }
+
+    /// Profile raises density of solids by water ice beyond snow line, condensation adds only volatile ices to it
+    fn has_snow_line(&self) -> bool {
+        false
+    }
+}
+
+impl<F: Fn(&f64, &f64, &f64) -> f64> DiskProfile for F {
+    fn dust_density(&self, dust_density_coeff: &f64, stellar_mass: &f64, r: &f64) -> f64 {
+        self(dust_density_coeff, stellar_mass, r)
+    }
+}
+
+/// Built-in dust density profiles. Density of every profile is A * sqrt(M) * f(r), where A is dust_density_coeff and M is stellar mass.
+/// Power law profiles are given by exponent of surface density of flat disk. Accrete sweeps spherical shells of cloud, so shell of density r^-(exponent + 1) holds the same mass as disk annulus.
+/// Power law profiles are scaled to density of Dole's cloud at 1 AU, so recommended range of dust_density_coeff holds for all of them.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum DiskModel {
+    /// f(r) = exp(-alpha * r^(1/n)), original cloud of Dole's paper with alpha = 5 and n = 3
+    Dole { alpha: f64, n: f64 },
+    /// Minimum-mass solar nebula, surface density r^-exponent. Weidenschilling 1977 and Hayashi 1981 give exponent of 1.5
+    MinimumMassSolarNebula { exponent: f64 },
+    /// Hayashi 1981 nebula, surface density r^-1.5 with 4.2 times more solids beyond snow line. Snow line lies at 2.7 AU around the Sun and follows luminosity of main sequence star.
+    Hayashi,
+}
+
+impl Default for DiskModel {
+    fn default() -> Self {
+        DiskModel::Dole { alpha: ALPHA, n: N }
+    }
+}
+
+impl DiskProfile for DiskModel {
+    fn dust_density(&self, dust_density_coeff: &f64, stellar_mass: &f64, r: &f64) -> f64 {
+        let relative_density = match self {
+            DiskModel::Dole { alpha, n } => (-alpha * r.powf(1.0 / n)).exp(),
+            DiskModel::MinimumMassSolarNebula { exponent } => {
+                (-ALPHA).exp() * r.powf(-exponent - 1.0)
+            }
+            DiskModel::Hayashi => {
+                (-ALPHA).exp()
+                    * r.powf(-MMSN_DENSITY_EXPONENT - 1.0)
+                    * hayashi_ice_factor(stellar_mass, r)
+            }
+        };
+        dust_density_coeff * stellar_mass.sqrt() * relative_density
+    }
+
+    /// Gas of Hayashi nebula follows the same power law across snow line, only solids jump
+    fn gas_to_dust_ratio(&self, k: &f64, stellar_mass: &f64, r: &f64) -> f64 {
+        match self {
+            DiskModel::Hayashi => {
+                let ice_factor = hayashi_ice_factor(stellar_mass, r);
+                (k - 1.0 + ice_factor) / ice_factor
+            }
+            _ => *k,
+        }
+    }
+
+    fn has_snow_line(&self) -> bool {
+        matches!(self, DiskModel::Hayashi)
+    }
+}
+
+/// Increase of solids density by condensed water ice of Hayashi nebula
+fn hayashi_ice_factor(stellar_mass: &f64, r: &f64) -> f64 {
+    let snow_line = condensation_line(&luminosity(*stellar_mass), &WATER_CONDENSATION_TEMP);
+    match *r < snow_line {
+        true => 1.0,
+        false => HAYASHI_ICE_FACTOR,
+    }
+}
+
+/// Dust density tabulated by distance from star: pairs of distance in AU and relative density f(r), see [DiskModel].
+/// Density is interpolated linearly between points, there is no dust outside of table.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TabulatedProfile {
+    points: Vec<(f64, f64)>,
+}
+
+impl TabulatedProfile {
+    pub fn new(mut points: Vec<(f64, f64)>) -> Self {
+        points.sort_by(|p1, p2| p1.0.total_cmp(&p2.0));
+        Self { points }
+    }
+
+    pub fn points(&self) -> &[(f64, f64)] {
+        &self.points
+    }
+}
+
+impl DiskProfile for TabulatedProfile {
+    fn dust_density(&self, dust_density_coeff: &f64, stellar_mass: &f64, r: &f64) -> f64 {
+        let relative_density = self
+            .points
+            .windows(2)
+            .find(|w| (w[0].0..=w[1].0).contains(r))
+            .map_or(0.0, |w| {
+                let (r1, f1) = w[0];
+                let (r2, f2) = w[1];
+                match r2 > r1 {
+                    true => f1 + (f2 - f1) * (r - r1) / (r2 - r1),
+                    false => f1,
+                }
+            });
+        dust_density_coeff * stellar_mass.sqrt() * relative_density
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Accrete;
+
+    #[test]
+    fn profiles_match_dole_at_one_au() {
+        let dole = DiskModel::default().dust_density(&DUST_DENSITY_COEFF, &1.0, &1.0);
+        let mmsn = DiskModel::MinimumMassSolarNebula {
+            exponent: MMSN_DENSITY_EXPONENT,
+        };
+        assert_eq!(mmsn.dust_density(&DUST_DENSITY_COEFF, &1.0, &1.0), dole);
+        assert_eq!(
+            DiskModel::Hayashi.dust_density(&DUST_DENSITY_COEFF, &1.0, &1.0),
+            dole
+        );
+
+        let hayashi_inner = DiskModel::Hayashi.dust_density(&DUST_DENSITY_COEFF, &1.0, &2.6);
+        let hayashi_outer = DiskModel::Hayashi.dust_density(&DUST_DENSITY_COEFF, &1.0, &2.8);
+        assert!(hayashi_outer > 3.0 * hayashi_inner);
+        let gas_inner =
+            hayashi_inner * (DiskModel::Hayashi.gas_to_dust_ratio(&K, &1.0, &2.6) - 1.0);
+        let gas_outer =
+            hayashi_outer * (DiskModel::Hayashi.gas_to_dust_ratio(&K, &1.0, &2.8) - 1.0);
+        assert!((gas_outer / gas_inner - (2.6_f64 / 2.8).powf(2.5)).abs() < 1.0E-9);
+        assert!(
+            mmsn.dust_density(&DUST_DENSITY_COEFF, &1.0, &10.0)
+                > DiskModel::default().dust_density(&DUST_DENSITY_COEFF, &1.0, &10.0)
+        );
+    }
+
+    #[test]
+    fn tabulated_profile_interpolates() {
+        let table = TabulatedProfile::new(vec![(10.0, 0.0), (1.0, 1.0), (5.0, 0.5)]);
+        assert_eq!(table.dust_density(&1.0, &1.0, &3.0), 0.75);
+        assert_eq!(table.dust_density(&1.0, &4.0, &7.5), 0.5);
+        assert_eq!(table.dust_density(&1.0, &1.0, &0.5), 0.0);
+        assert_eq!(table.dust_density(&1.0, &1.0, &20.0), 0.0);
+    }
+
+    #[test]
+    fn disk_profile_shapes_planetary_system() {
+        let mut dole = Accrete::new(1);
+        let mut closure = Accrete::new(1);
+        let dole_profile = DiskModel::default();
+        let closure_profile = |dust_density_coeff: &f64, stellar_mass: &f64, r: &f64| {
+            dole_profile.dust_density(dust_density_coeff, stellar_mass, r)
+        };
+        assert_eq!(
+            format!("{:?}", dole.planetary_system()),
+            format!(
+                "{:?}",
+                closure.planetary_system_with_disk_profile(&closure_profile, &mut Vec::new())
+            )
+        );
+
+        let mut truncated = Accrete::new(1);
+        let table = TabulatedProfile::new(vec![(0.0, 0.01), (3.0, 0.01)]);
+        let system = truncated
+            .planetary_system_with_disk_profile(&table, &mut Vec::new())
+            .expect("Failed to generate planetary system");
+        // Planetesimals beyond table keep their seed mass
+        assert!(system
+            .planets
+            .iter()
+            .all(|p| p.a < 3.0 || p.earth_masses < 1.0E-6));
+    }
+}
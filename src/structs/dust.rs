@@ -1,4 +1,5 @@
 use crate::consts::*;
+use crate::structs::disk_profile::DiskProfile;
 use crate::utils::*;
 
 use serde::{Deserialize, Serialize};
@@ -33,8 +34,17 @@ pub fn dust_availible(dust_bands: &[DustBand], inside_range: &f64, outside_range
     })
 }
 
+/// Gas of disk is present at given distance from star
+pub fn gas_present_at(dust_bands: &[DustBand], radius: &f64) -> bool {
+    dust_bands
+        .iter()
+        .any(|band| band.gas_present && &band.inner_edge <= radius && &band.outer_edge > radius)
+}
+
 /// "The center of mass is occupied by a star with a mass of one unit (one solar mass). All particles in the cloud are moving on elliptical orbits, with the center of mass at one focus. The density of dust (p1) within the cloud depends on a function of the form p1 = A exp (-arl/n). The overall density of gas and dust (p2) within the cloud equals Kpl, where r is distance from the center of mass (in astronomical units, a.u.) and A. a. n. and K (the vas/dust ratio) are constants."
 /// "There is a spherically symmetrical cloud of dust and gas with a constant ratio of gas to dust, the density decreasing with distance from the center."
+/// Dust density at orbit of planetesimal is given by disk_profile, Dole's cloud is [DiskModel::Dole](crate::structs::disk_profile::DiskModel::Dole).
+/// Returns mass of gas accreted by planetesimal.
 pub fn accrete_dust(
     mass: &mut f64,
     a: &f64,
@@ -42,44 +52,60 @@ pub fn accrete_dust(
     crit_mass: &f64,
     dust_bands: &mut [DustBand],
     cloud_eccentricity: &f64,
-    dust_density: &f64,
+    disk_profile: &dyn DiskProfile,
+    dust_density_coeff: &f64,
+    stellar_mass: &f64,
     k: &f64,
-) {
+) -> f64 {
     let mut new_mass = *mass;
+    let mut gas_mass;
     loop {
         *mass = new_mass;
         new_mass = 0.0;
+        gas_mass = 0.0;
 
         for d in dust_bands.iter_mut() {
-            new_mass += collect_dust(
+            let (collected_mass, collected_gas) = collect_dust(
                 mass,
                 a,
                 e,
                 crit_mass,
                 cloud_eccentricity,
-                dust_density,
+                disk_profile,
+                dust_density_coeff,
+                stellar_mass,
                 k,
                 d,
             );
+            new_mass += collected_mass;
+            gas_mass += collected_gas;
         }
 
-        if !((new_mass - *mass) >= (0.0001 * *mass)) {
+        let keep_accreting = (new_mass - *mass) >= (0.0001 * *mass);
+        if !keep_accreting {
             break;
         }
     }
-    *mass = new_mass;
+    // Planetesimal keeps its mass in part of cloud without dust
+    if new_mass > 0.0 {
+        *mass = new_mass;
+    }
+    gas_mass
 }
 
+/// Mass of dust and gas swept by planetesimal from dust band, and mass of gas in it
 pub fn collect_dust(
     mass: &f64,
     a: &f64,
     e: &f64,
     crit_mass: &f64,
     cloud_eccentricity: &f64,
-    dust_density: &f64,
+    disk_profile: &dyn DiskProfile,
+    dust_density_coeff: &f64,
+    stellar_mass: &f64,
     k: &f64,
     band: &mut DustBand,
-) -> f64 {
+) -> (f64, f64) {
     let mut r_inner = inner_swept_limit(a, e, mass, cloud_eccentricity);
     let r_outer = outer_swept_limit(a, e, mass, cloud_eccentricity);
 
@@ -88,12 +114,16 @@ pub fn collect_dust(
     }
 
     if band.outer_edge <= r_inner || band.inner_edge >= r_outer || !band.dust_present {
-        return 0.0;
+        return (0.0, 0.0);
     };
 
+    let dust_density = disk_profile.dust_density(dust_density_coeff, stellar_mass, a);
     let density = match !band.gas_present || mass < crit_mass {
-        true => *dust_density,
-        false => get_mass_density(k, dust_density, crit_mass, mass),
+        true => dust_density,
+        false => {
+            let k = disk_profile.gas_to_dust_ratio(k, stellar_mass, a);
+            get_mass_density(&k, &dust_density, crit_mass, mass)
+        }
     };
     let bandwidth = r_outer - r_inner;
     let temp1 = match r_outer - band.outer_edge > 0.0 {
@@ -109,7 +139,7 @@ pub fn collect_dust(
     let term2 = 1.0 - e * (temp1 - temp2) / bandwidth;
     let volume = term1 * reduced_mass(mass) * width * term2;
 
-    volume * density
+    (volume * density, volume * (density - dust_density))
 }
 
 pub fn update_dust_lanes(
@@ -179,7 +209,3 @@ pub fn compress_dust_lanes(dust_bands: &mut Vec<DustBand>) {
 pub fn get_mass_density(k: &f64, dust_density: &f64, critical_mass: &f64, mass: &f64) -> f64 {
     k * dust_density / (1.0 + (critical_mass / mass).sqrt() * (k - 1.0))
 }
-
-pub fn dust_density(dust_density_coeff: &f64, stellar_mass: &f64, oribital_radius: &f64) -> f64 {
-    dust_density_coeff * stellar_mass.sqrt() * (-ALPHA * oribital_radius.powf(1.0 / N)).exp()
-}
@@ -0,0 +1,110 @@
+use crate::consts::*;
+use crate::enviro::*;
+use crate::structs::Planetesimal;
+use crate::utils::*;
+
+use serde::{Deserialize, Serialize};
+
+/// Gas giant class by equilibrium temperature
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum GasGiantClass {
+    // equilibrium temperature above 1000 K, close-in orbit
+    Hot,
+    // equilibrium temperature 300-1000 K
+    Warm,
+    // equilibrium temperature below 300 K, like Jupiter and Saturn
+    Cold,
+}
+
+/// Atmosphere and internal heat of gas giant. Gas giants have no solid surface, temperature and gravity are given at 1-bar level.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct GasGiantStructure {
+    // temperature from stellar irradiation only, Kelvin
+    pub equilibrium_temp_kelvin: f64,
+    // temperature of internal heat flux, Kelvin
+    pub intrinsic_temp_kelvin: f64,
+    // temperature of emitted radiation, stellar and internal, Kelvin
+    pub effective_temp_kelvin: f64,
+    // temperature at 1-bar pressure level, Kelvin
+    pub one_bar_temp_kelvin: f64,
+    // units of Earth gravities
+    pub cloud_top_gravity: f64,
+    // units of W/m2
+    pub internal_heat_flux: f64,
+    // ratio of emitted to absorbed energy, Jupiter is ~1.7, none without stellar irradiation
+    pub internal_heat_ratio: Option<f64>,
+    pub class: GasGiantClass,
+}
+
+impl GasGiantStructure {
+    /// Derive gas giant structure, mass, radius and albedo of planet must be known. Age of system is in years.
+    pub fn new(planet: &Planetesimal, stellar_luminosity: &f64, system_age: &f64) -> Self {
+        let equilibrium_temp_kelvin = equilibrium_temp(
+            stellar_luminosity,
+            &planet.distance_to_primary_star,
+            &planet.albedo,
+        );
+        let intrinsic_temp_kelvin =
+            gas_giant_intrinsic_temp(&get_earth_mass(planet.mass), &planet.radius, system_age);
+        let effective_temp_kelvin =
+            (equilibrium_temp_kelvin.powf(4.0) + intrinsic_temp_kelvin.powf(4.0)).powf(0.25);
+        let one_bar_temp_kelvin = effective_temp_kelvin * ONE_BAR_TO_EFFECTIVE_TEMP_RATIO;
+        let internal_heat_ratio = match equilibrium_temp_kelvin > 0.0 {
+            true => Some((effective_temp_kelvin / equilibrium_temp_kelvin).powf(4.0)),
+            false => None,
+        };
+        let class = match equilibrium_temp_kelvin {
+            t if t > HOT_JUPITER_TEMP => GasGiantClass::Hot,
+            t if t > WARM_JUPITER_TEMP => GasGiantClass::Warm,
+            _ => GasGiantClass::Cold,
+        };
+
+        GasGiantStructure {
+            equilibrium_temp_kelvin: float_to_precision(equilibrium_temp_kelvin),
+            intrinsic_temp_kelvin: float_to_precision(intrinsic_temp_kelvin),
+            effective_temp_kelvin: float_to_precision(effective_temp_kelvin),
+            one_bar_temp_kelvin: float_to_precision(one_bar_temp_kelvin),
+            cloud_top_gravity: gravity(&acceleration(&planet.mass, &planet.radius)),
+            internal_heat_flux: SIGMA * intrinsic_temp_kelvin.powf(4.0),
+            internal_heat_ratio,
+            class,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn jupiter_intrinsic_heat() {
+        let intrinsic_temp =
+            gas_giant_intrinsic_temp(&JUPITER_EARTH_MASSES, &JUPITER_RADIUS_IN_KM, &JUPITER_AGE);
+        assert!((intrinsic_temp - JUPITER_INTRINSIC_TEMP).abs() < 1e-6);
+        // Jupiter, Bond albedo 0.343
+        let equilibrium_temp = equilibrium_temp(&1.0, &5.2, &0.343);
+        assert!(equilibrium_temp > 105.0 && equilibrium_temp < 112.0);
+    }
+
+    #[test]
+    fn rogue_gas_giant_has_no_heat_ratio() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let mut planet = Planetesimal::new(&0.3, &50.0, &mut rng);
+        planet.mass = JUPITER_EARTH_MASSES / EARTH_MASSES_PER_SOLAR_MASS;
+        planet.radius = JUPITER_RADIUS_IN_KM;
+        planet.albedo = 0.343;
+
+        let rogue = GasGiantStructure::new(&planet, &0.0, &JUPITER_AGE);
+        assert_eq!(rogue.internal_heat_ratio, None);
+        assert!(rogue.intrinsic_temp_kelvin > 0.0);
+
+        planet.distance_to_primary_star = 5.2;
+        let jupiter = GasGiantStructure::new(&planet, &1.0, &JUPITER_AGE);
+        let ratio = jupiter
+            .internal_heat_ratio
+            .expect("Failed to derive internal heat ratio");
+        assert!(ratio > 1.0);
+    }
+}
@@ -0,0 +1,124 @@
+use crate::consts::*;
+use crate::enviro::*;
+use crate::structs::Planetesimal;
+
+use serde::{Deserialize, Serialize};
+
+/// Whether atmosphere can be breathed by humans, following StarGen
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum Breathability {
+    // no atmosphere
+    #[default]
+    None,
+    Breathable,
+    // not enough oxygen or too much inert gases
+    Unbreathable,
+    // one or more gases over toxicity limits
+    Poisonous,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum HabitabilityCategory {
+    // breathable atmosphere, liquid water and moderate surface temperature
+    Habitable,
+    // rocky world with atmosphere and liquid water possible at some part of surface
+    Marginal,
+    #[default]
+    Hostile,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub struct Habitability {
+    // Earth Similarity Index, 0.0-1.0, Earth is 1.0
+    pub esi: f64,
+    pub breathability: Breathability,
+    pub category: HabitabilityCategory,
+    // orbit lies inside of primary star ecosphere
+    pub in_ecosphere: bool,
+}
+
+impl Habitability {
+    /// Derive habitability from planetary environment, must be called after environment is generated
+    pub fn new(planet: &Planetesimal, ecosphere: &(f64, f64)) -> Self {
+        let in_ecosphere = planet.distance_to_primary_star >= ecosphere.0
+            && planet.distance_to_primary_star <= ecosphere.1;
+
+        if planet.is_gas_giant {
+            return Habitability {
+                in_ecosphere,
+                ..Default::default()
+            };
+        }
+
+        let esi = earth_similarity_index(
+            &planet.earth_radii,
+            &planet.density,
+            &planet.escape_velocity_km_per_sec,
+            &planet.surface_temp_kelvin,
+        );
+        let breathability = breathability(&planet.atmosphere);
+        let category = habitability_category(planet, &breathability);
+
+        Habitability {
+            esi,
+            breathability,
+            category,
+            in_ecosphere,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::atmosphere::*;
+
+    #[test]
+    fn earth_similarity_index_of_earth() {
+        let esi = earth_similarity_index(
+            &1.0,
+            &EARTH_DENSITY,
+            &EARTH_ESCAPE_VELOCITY_KM_PER_SEC,
+            &EARTH_AVERAGE_KELVIN,
+        );
+        assert_eq!(esi, 1.0);
+        // Mars
+        let esi = earth_similarity_index(&0.53, &3.93, &5.03, &210.0);
+        assert!(esi > 0.6 && esi < 0.75);
+    }
+
+    fn earth_like_atmosphere(surface_pressure_bar: f64) -> Vec<AtmosphericGas> {
+        [
+            (AN_N, 0.78),
+            (AN_O, 0.21),
+            (AN_AR, 0.0093),
+            (AN_CO2, 0.0004),
+        ]
+        .iter()
+        .map(|(num, fraction)| {
+            let gas = GASES
+                .iter()
+                .find(|g| g.num == *num)
+                .expect("Failed to find gas");
+            AtmosphericGas::new(gas, surface_pressure_bar * fraction, surface_pressure_bar)
+        })
+        .collect()
+    }
+
+    #[test]
+    fn breathability_of_earth_like_atmosphere() {
+        assert_eq!(breathability(&[]), Breathability::None);
+        assert_eq!(
+            breathability(&earth_like_atmosphere(1.0)),
+            Breathability::Breathable
+        );
+        assert_eq!(
+            breathability(&earth_like_atmosphere(0.2)),
+            Breathability::Unbreathable
+        );
+        assert_eq!(
+            breathability(&earth_like_atmosphere(10.0)),
+            Breathability::Poisonous
+        );
+    }
+}
@@ -1,17 +1,47 @@
 #![allow(unused_imports)]
+pub mod atmosphere;
+pub mod belt;
+pub mod companion_star;
+pub mod composition;
+pub mod condensation;
+pub mod disk_profile;
 pub mod dust;
+pub mod gas_giant;
+pub mod habitability;
+pub mod planet_type;
 pub mod planetesimal;
 pub mod primary_star;
 pub mod ring;
 pub mod system;
 
+pub use atmosphere::AtmosphericGas;
+pub use belt::Belt;
+pub use companion_star::CompanionStar;
+pub use companion_star::CompanionStarConfig;
+pub use composition::BulkComposition;
+pub use condensation::CondensationConfig;
+pub use condensation::CondensationLines;
+pub use disk_profile::DiskModel;
+pub use disk_profile::DiskProfile;
+pub use disk_profile::TabulatedProfile;
 pub use dust::DustBand;
+pub use gas_giant::GasGiantStructure;
+pub use habitability::Habitability;
+pub use planet_type::PlanetType;
+pub use planetesimal::MoonClass;
 pub use planetesimal::Planetesimal;
+pub use primary_star::EvolutionaryStage;
 pub use primary_star::PrimaryStar;
 pub use ring::Ring;
 pub use system::System;
 
+use atmosphere::*;
+use belt::*;
+use companion_star::*;
 use dust::*;
+use gas_giant::*;
+use habitability::*;
+use planet_type::*;
 use planetesimal::*;
 use primary_star::*;
 use ring::*;
@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+
+/// Kind of planet by its surface conditions, following StarGen planet types
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum PlanetType {
+    // planetary environment is not derived yet
+    #[default]
+    Unknown,
+    // airless rocky body
+    Rock,
+    // hot world with boiling oceans and dense atmosphere
+    Venusian,
+    // rocky world with oceans covering more than 5% of surface
+    Terrestrial,
+    // cold world with thin atmosphere
+    Martian,
+    // oceans cover more than 95% of surface
+    WaterWorld,
+    // ice cover more than 95% of surface, or frozen world with dense atmosphere
+    Ice,
+    // gas dwarf, rocky core with dense hydrogen atmosphere
+    SubGasGiant,
+    // gas giant heavier than 20 Earth masses
+    GasGiant,
+    // gas giant lighter than 20 Earth masses
+    SubJovian,
+    // airless body too small to clear its orbit, represents asteroid belt
+    AsteroidBelt,
+    // tidally locked or in spin-orbit resonance with one side always facing primary
+    OneFace,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enviro::planet_type;
+    use crate::Accrete;
+
+    #[test]
+    fn planet_type_follows_surface_conditions() {
+        let mut accrete = Accrete::new(1);
+        let system = accrete
+            .planetary_system()
+            .expect("Failed to generate planetary system");
+        for planet in system.planets.iter() {
+            assert_ne!(planet.planet_type, PlanetType::Unknown);
+            assert_eq!(
+                planet.is_gas_giant,
+                matches!(
+                    planet.planet_type,
+                    PlanetType::GasGiant | PlanetType::SubJovian
+                )
+            );
+        }
+
+        let mut planet = system
+            .planets
+            .iter()
+            .find(|p| !p.is_gas_giant)
+            .expect("Failed to find terrestrial planet")
+            .clone();
+        planet.is_tidally_locked = false;
+        planet.resonant_period = false;
+        planet.surface_pressure_bar = Some(1.0);
+        planet.molecule_weight = 28.0;
+        planet.hydrosphere = Some(0.97);
+        assert_eq!(planet_type(&planet), PlanetType::WaterWorld);
+        planet.hydrosphere = Some(0.7);
+        planet.ice_cover = 0.0;
+        assert_eq!(planet_type(&planet), PlanetType::Terrestrial);
+        planet.surface_pressure_bar = Some(0.0);
+        assert_eq!(planet_type(&planet), PlanetType::Rock);
+    }
+}
@@ -1,6 +1,8 @@
 use crate::consts::*;
+use crate::dynamics::orbit::*;
+use crate::dynamics::Resonance;
 use crate::enviro::*;
-use crate::events_log::accrete_event::AccreteEvents;
+use crate::events_log::observer::AccreteObserver;
 use crate::structs::*;
 use crate::utils::*;
 
@@ -8,6 +10,15 @@ use rand::distributions::Alphanumeric;
 use rand::{Rng, RngCore};
 use serde::{Deserialize, Serialize};
 
+/// Origin of moon
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum MoonClass {
+    /// Accreted from circumplanetary disk of gas giant, prograde orbit close to equatorial plane of planet
+    Regular,
+    /// Captured planetesimal, randomly inclined orbit, often retrograde
+    Irregular,
+}
+
 // http://orbitsimulator.com/formulas/
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Planetesimal {
@@ -18,9 +29,13 @@ pub struct Planetesimal {
     pub b: f64,
     // eccentricity of the orbit, unitless
     pub e: f64,
+    // orbit orientation and mean anomaly at epoch
+    pub orbital_elements: OrbitalElements,
     pub distance_to_primary_star: f64,
     pub mass: f64,
     pub earth_masses: f64,
+    // masses of accreted iron, silicates, ices and gas
+    pub bulk_composition: BulkComposition,
     pub is_gas_giant: bool,
     pub orbit_zone: i32,
     // equatorial radius, km
@@ -35,7 +50,7 @@ pub struct Planetesimal {
     pub escape_velocity: f64,
     // units of cm/sec2
     pub surface_accel: f64,
-    // units of Earth gravities
+    // units of Earth gravities, at 1-bar level for gas giants
     pub surface_grav: f64,
     // units of cm/sec
     pub rms_velocity: f64,
@@ -44,23 +59,30 @@ pub struct Planetesimal {
     pub day_hours: f64,
     pub length_of_year: f64,
     pub molecule_weight: f64,
-    pub volatile_gas_inventory: f64,
+    // not defined for gas giants
+    pub volatile_gas_inventory: Option<f64>,
     pub greenhouse_effect: bool,
     pub albedo: f64,
     pub is_tidally_locked: bool,
-    pub surface_pressure_bar: f64,
+    // not defined for gas giants
+    pub surface_pressure_bar: Option<f64>,
+    // temperature at 1-bar level for gas giants
     pub surface_temp_kelvin: f64,
     pub day_temp_kelvin: f64,
     pub night_temp_kelvin: f64,
     pub max_temp_kelvin: f64,
     pub min_temp_kelvin: f64,
-    pub boiling_point_kelvin: f64,
-    pub hydrosphere: f64,
+    // not defined for gas giants
+    pub boiling_point_kelvin: Option<f64>,
+    // not defined for gas giants
+    pub hydrosphere: Option<f64>,
     pub cloud_cover: f64,
     pub ice_cover: f64,
     pub moons: Vec<Planetesimal>,
     pub rings: Vec<Ring>,
     pub is_moon: bool,
+    // none for planets
+    pub moon_class: Option<MoonClass>,
     // orbit clearing less than 1.0 is threshold between planet and dearf planet
     pub orbit_clearing: f64,
     // dwarf planet may have an astroid field or other objects on its orbit
@@ -70,6 +92,15 @@ pub struct Planetesimal {
     pub magnetosphere: bool,
     // if planet had collision with other objects
     pub has_collision: bool,
+    // P-type orbit around both stars of binary system
+    pub is_circumbinary: bool,
+    // mean-motion resonances with neighbouring planets or moons
+    pub resonances: Vec<Resonance>,
+    // gases sorted by diminishing partial pressure
+    pub atmosphere: Vec<AtmosphericGas>,
+    pub planet_type: PlanetType,
+    pub habitability: Habitability,
+    pub gas_giant_structure: Option<GasGiantStructure>,
     pub id: String,
 }
 
@@ -88,9 +119,11 @@ impl Planetesimal {
             a,
             b,
             e,
+            orbital_elements: OrbitalElements::default(),
             distance_to_primary_star: a,
             mass: PROTOPLANET_MASS,
             earth_masses: 0.0,
+            bulk_composition: BulkComposition::accreted(&PROTOPLANET_MASS, &0.0, &0.0),
             orbit_zone: 0,
             radius: 0.0,
             earth_radii: 0.0,
@@ -104,7 +137,7 @@ impl Planetesimal {
             surface_grav: 0.0,
             rms_velocity: 0.0,
             molecule_weight: 0.0,
-            volatile_gas_inventory: 0.0,
+            volatile_gas_inventory: None,
             greenhouse_effect: false,
             albedo: 0.0,
             surface_temp_kelvin: 0.0,
@@ -112,9 +145,9 @@ impl Planetesimal {
             night_temp_kelvin: 0.0,
             max_temp_kelvin: 0.0,
             min_temp_kelvin: 0.0,
-            surface_pressure_bar: 0.0,
-            boiling_point_kelvin: 0.0,
-            hydrosphere: 0.0,
+            surface_pressure_bar: None,
+            boiling_point_kelvin: None,
+            hydrosphere: None,
             cloud_cover: 0.0,
             ice_cover: 0.0,
             moons: Vec::new(),
@@ -123,6 +156,7 @@ impl Planetesimal {
             escape_velocity_km_per_sec: 0.0,
             is_tidally_locked: false,
             is_moon: false,
+            moon_class: None,
             is_gas_giant: false,
             is_dwarf_planet: false,
             orbit_clearing: 0.0,
@@ -130,6 +164,12 @@ impl Planetesimal {
             tectonic_activity: false,
             magnetosphere: false,
             has_collision: false,
+            is_circumbinary: false,
+            resonances: Vec::new(),
+            atmosphere: Vec::new(),
+            planet_type: PlanetType::default(),
+            habitability: Habitability::default(),
+            gas_giant_structure: None,
             id,
         }
     }
@@ -138,14 +178,14 @@ impl Planetesimal {
         &mut self,
         stellar_luminosity: &f64,
         stellar_mass: &f64,
-        main_seq_age: &f64,
+        system_age: &f64,
         ecosphere: &(f64, f64),
         rng: &mut dyn RngCore,
     ) {
         if !self.is_moon {
             self.orbit_zone = orbital_zone(stellar_luminosity, self.a);
         }
-        if self.is_gas_giant {
+        if self.is_gas_giant && !is_sub_neptune(&self.mass) {
             self.density = empirical_density(
                 &self.mass,
                 &self.distance_to_primary_star,
@@ -154,11 +194,17 @@ impl Planetesimal {
             );
             self.radius = volume_radius(&self.mass, &self.density);
         } else {
+            let stellar_flux = stellar_luminosity / self.distance_to_primary_star.powf(2.0);
+            self.radius = self.bulk_composition.solid_radius();
+            if self.bulk_composition.gas > 0.0 {
+                self.radius += self
+                    .bulk_composition
+                    .envelope_thickness(&stellar_flux, system_age);
+            }
             self.density = volume_density(&self.mass, &self.radius);
-            self.radius = kothari_radius(&self.mass, &self.is_gas_giant, &self.orbit_zone);
         }
         self.orbital_period_days = period(&self.a, &self.mass, stellar_mass);
-        self.day_hours = day_length(self, stellar_mass, main_seq_age);
+        self.day_hours = day_length(self, stellar_mass, system_age);
         self.axial_tilt = inclination(&self.a, rng);
         self.escape_velocity = escape_vel(&self.mass, &self.radius);
         self.surface_accel = acceleration(&self.mass, &self.radius);
@@ -166,23 +212,25 @@ impl Planetesimal {
         self.molecule_weight = molecule_limit(&self.mass, &self.radius);
 
         if self.is_gas_giant {
-            self.surface_grav = INCREDIBLY_LARGE_NUMBER;
+            self.surface_grav = gravity(&self.surface_accel);
             self.greenhouse_effect = false;
-            self.volatile_gas_inventory = INCREDIBLY_LARGE_NUMBER;
-            self.surface_pressure_bar = INCREDIBLY_LARGE_NUMBER;
-            self.boiling_point_kelvin = INCREDIBLY_LARGE_NUMBER;
-            self.hydrosphere = INCREDIBLY_LARGE_NUMBER;
+            self.volatile_gas_inventory = None;
+            self.surface_pressure_bar = None;
+            self.boiling_point_kelvin = None;
+            self.hydrosphere = None;
             self.albedo = about(GAS_GIANT_ALBEDO, 0.1, rng);
-            self.surface_temp_kelvin = INCREDIBLY_LARGE_NUMBER;
+            let structure = GasGiantStructure::new(self, stellar_luminosity, system_age);
+            self.surface_temp_kelvin = structure.one_bar_temp_kelvin;
+            self.gas_giant_structure = Some(structure);
         } else {
             self.surface_grav = gravity(&self.surface_accel);
             self.greenhouse_effect = greenhouse(
                 &self.distance_to_primary_star,
                 &self.orbit_zone,
-                &self.surface_pressure_bar,
+                &self.surface_pressure_bar.unwrap_or_default(),
                 &ecosphere.1,
             );
-            self.volatile_gas_inventory = vol_inventory(
+            let volatile_gas_inventory = vol_inventory(
                 &self.mass,
                 &self.escape_velocity,
                 &self.rms_velocity,
@@ -191,20 +239,29 @@ impl Planetesimal {
                 &self.greenhouse_effect,
                 rng,
             );
-            self.surface_pressure_bar = pressure(
-                &self.volatile_gas_inventory,
-                &self.radius,
-                &self.surface_grav,
-            );
-            if self.surface_pressure_bar == 0.0 {
-                self.boiling_point_kelvin = 0.0;
+            let surface_pressure_bar =
+                pressure(&volatile_gas_inventory, &self.radius, &self.surface_grav);
+            self.volatile_gas_inventory = Some(volatile_gas_inventory);
+            self.surface_pressure_bar = Some(surface_pressure_bar);
+            if surface_pressure_bar == 0.0 {
+                self.boiling_point_kelvin = Some(0.0);
+                self.hydrosphere = Some(0.0);
             } else {
-                self.boiling_point_kelvin = boiling_point_kelvin(&self.surface_pressure_bar);
+                self.boiling_point_kelvin = Some(boiling_point_kelvin(&surface_pressure_bar));
                 iterate_surface_temp(self, &ecosphere.1, rng);
             }
         }
 
-        get_day_night_temp_kelvin(self);
+        match self.is_gas_giant {
+            // Deep atmosphere of gas giant evens out temperature differences
+            true => {
+                self.day_temp_kelvin = self.surface_temp_kelvin;
+                self.night_temp_kelvin = self.surface_temp_kelvin;
+                self.max_temp_kelvin = self.surface_temp_kelvin;
+                self.min_temp_kelvin = self.surface_temp_kelvin;
+            }
+            false => get_day_night_temp_kelvin(self),
+        }
 
         self.hill_sphere = hill_sphere_au(&self.a, &self.e, &self.mass, stellar_mass);
         self.earth_masses = get_earth_mass(self.mass);
@@ -229,9 +286,55 @@ impl Planetesimal {
         // 1) planet is gas giant
         // 2) planet have tectonic activity
         // 3) planet have atmosphere
-        if self.is_gas_giant || self.tectonic_activity || self.surface_pressure_bar > 0.0 {
+        if self.is_gas_giant
+            || self.tectonic_activity
+            || self.surface_pressure_bar.is_some_and(|p| p > 0.0)
+        {
             self.magnetosphere = true;
         }
+
+        self.atmosphere = match self.is_gas_giant {
+            true => Vec::new(),
+            false => atmosphere_composition(self, system_age),
+        };
+        self.planet_type = planet_type(self);
+        self.habitability = Habitability::new(self, ecosphere);
+    }
+
+    /// Move body to new orbit, moons follow their planet
+    pub fn set_orbit(&mut self, a: f64, e: f64) {
+        self.a = a;
+        self.e = e;
+        self.b = semi_minor_axis(a, e);
+        if !self.is_moon {
+            self.distance_to_primary_star = a;
+            for moon in self.moons.iter_mut() {
+                moon.distance_to_primary_star = a;
+            }
+        }
+    }
+
+    /// Random orbit orientation for planet and its moons, must be called after accretion is finished. Captured moons are inclined at random.
+    pub fn orient_orbit(&mut self, rng: &mut dyn RngCore) {
+        self.orbital_elements = OrbitalElements::random(&self.e, rng);
+        for moon in self.moons.iter_mut() {
+            moon.orbital_elements = match moon.moon_class {
+                Some(MoonClass::Irregular) => OrbitalElements::random_isotropic(rng),
+                _ => OrbitalElements::random(&moon.e, rng),
+            };
+        }
+    }
+
+    /// Position and velocity relative to central body at time t in days since epoch. Central mass is stellar mass for planets and planet mass for moons, in solar masses.
+    pub fn state_at(&self, central_mass: &f64, t: &f64) -> StateVector {
+        state_vector(
+            &self.a,
+            &self.e,
+            &self.orbital_elements,
+            central_mass,
+            &self.mass,
+            t,
+        )
     }
 
     pub fn random_outer_body(
@@ -243,10 +346,14 @@ impl Planetesimal {
             Planetesimal::new(planetesimal_inner_bound, planetesimal_outer_bound, rng);
         random_body.mass = float_to_precision(rng.gen_range(PLANETESIMAL_MASS..PROTOPLANET_MASS * 1.0e5));
         random_body.orbit_zone = 3;
-        random_body.radius = kothari_radius(
+        // Icy body from outer cloud
+        random_body.bulk_composition =
+            BulkComposition::accreted(&random_body.mass, &0.0, &WATER_ICE_TO_ROCK);
+        random_body.radius = bulk_radius(
             &random_body.mass,
             &random_body.is_gas_giant,
             &random_body.orbit_zone,
+            &random_body.bulk_composition,
         );
 
         random_body
@@ -259,10 +366,12 @@ impl Planetesimal {
         e: f64,
         mass: f64,
         post_accretion_intensity: u32,
+        system_age: Option<f64>,
         rng: &mut dyn RngCore,
-        events_log: &mut AccreteEvents,
+        observer: &mut dyn AccreteObserver,
     ) -> Planetesimal {
         let main_seq_age = main_sequence_age(stellar_mass, stellar_luminosity);
+        let system_age = system_age.unwrap_or_else(|| random_system_age(&main_seq_age, rng));
         let stellar_radius_au = stellar_radius_au(stellar_mass);
         let stellar_surface_temp = stellar_surface_temp(stellar_radius_au, stellar_luminosity);
         let spectral_class = spectral_class(&stellar_surface_temp);
@@ -281,14 +390,24 @@ impl Planetesimal {
         }
         let id = random_id(rng);
         let b = semi_minor_axis(a, e);
+        // Gas giant envelope is accreted on core of critical mass
+        let gas_mass = match is_gas_giant {
+            true => mass - crit_mass,
+            false => 0.0,
+        };
+        let condensation_lines = CondensationLines::new(&stellar_luminosity, false);
+        let bulk_composition =
+            BulkComposition::accreted(&mass, &gas_mass, &condensation_lines.ice_to_rock(&a));
 
         let mut random_planet = Planetesimal {
             a,
             b,
             e,
+            orbital_elements: OrbitalElements::default(),
             distance_to_primary_star: a,
             mass,
             earth_masses: 0.0,
+            bulk_composition,
             orbit_zone: 0,
             radius: 0.0,
             earth_radii: 0.0,
@@ -302,7 +421,7 @@ impl Planetesimal {
             surface_grav: 0.0,
             rms_velocity: 0.0,
             molecule_weight: 0.0,
-            volatile_gas_inventory: 0.0,
+            volatile_gas_inventory: None,
             greenhouse_effect: false,
             albedo: 0.0,
             surface_temp_kelvin: 0.0,
@@ -310,9 +429,9 @@ impl Planetesimal {
             night_temp_kelvin: 0.0,
             max_temp_kelvin: 0.0,
             min_temp_kelvin: 0.0,
-            surface_pressure_bar: 0.0,
-            boiling_point_kelvin: 0.0,
-            hydrosphere: 0.0,
+            surface_pressure_bar: None,
+            boiling_point_kelvin: None,
+            hydrosphere: None,
             cloud_cover: 0.0,
             ice_cover: 0.0,
             moons: Vec::new(),
@@ -321,6 +440,7 @@ impl Planetesimal {
             escape_velocity_km_per_sec: 0.0,
             is_tidally_locked: false,
             is_moon: false,
+            moon_class: None,
             is_gas_giant,
             is_dwarf_planet,
             orbit_clearing,
@@ -328,6 +448,12 @@ impl Planetesimal {
             tectonic_activity: false,
             magnetosphere: false,
             has_collision: false,
+            is_circumbinary: false,
+            resonances: Vec::new(),
+            atmosphere: Vec::new(),
+            planet_type: PlanetType::default(),
+            habitability: Habitability::default(),
+            gas_giant_structure: None,
             id,
         };
 
@@ -342,14 +468,14 @@ impl Planetesimal {
                 &stellar_luminosity,
                 &stellar_mass,
                 rng,
-                events_log,
+                observer,
             );
         }
 
         random_planet.derive_planetary_environment(
             &stellar_luminosity,
             &stellar_mass,
-            &main_seq_age,
+            &system_age,
             &ecosphere,
             rng,
         );
@@ -358,11 +484,12 @@ impl Planetesimal {
             moon.derive_planetary_environment(
                 &stellar_luminosity,
                 &random_planet.mass,
-                &main_seq_age,
+                &system_age,
                 &ecosphere,
                 rng,
             );
         }
+        random_planet.orient_orbit(rng);
 
         random_planet
     }
@@ -1,22 +1,44 @@
 use crate::consts::*;
 
+use rand::{Rng, RngCore};
 use serde::{Deserialize, Serialize};
 
+/// Phase of stellar evolution along track of star
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum EvolutionaryStage {
+    #[default]
+    MainSequence,
+    // hydrogen exhausted in core, star expands and cools
+    Subgiant,
+    // red giant branch up to asymptotic giant branch, star swells to ~1 AU
+    RedGiant,
+    // envelope is lost, cooling core is left
+    WhiteDwarf,
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
 pub struct PrimaryStar {
     pub stellar_mass: f64,
+    // [Fe/H], dex relative to the Sun
+    pub metallicity: f64,
     pub stellar_luminosity: f64,
     pub stellar_surface_temp: f64,
     pub stellar_radius_au: f64,
     pub spectral_class: SpectralClass,
+    // 0-9, from hottest to coolest star of spectral class
+    pub spectral_subclass: u8,
+    pub luminosity_class: LuminosityClass,
     pub bv_color_index: f64,
     pub color: [f64; 3],
     pub main_seq_age: f64,
     pub ecosphere: (f64, f64),
+    // units of years, zero for star used during accretion
+    pub age: f64,
+    pub evolutionary_stage: EvolutionaryStage,
 }
 
 impl PrimaryStar {
-    pub fn new(stellar_mass: f64) -> Self {
+    pub fn new(stellar_mass: f64, metallicity: f64) -> Self {
         // Warning - stallar mass higher than 10 solar masses probably refer to non-regular and supermassive stars (short lived giants, black holes, wolf-rayet stars, etc..) and require other calculation methods
         // Possibly can be useful https://www.fabiopacucci.com/resources/black-hole-calculator/formulas-black-hole-calculator/
         let stellar_luminosity = luminosity(stellar_mass);
@@ -24,27 +46,187 @@ impl PrimaryStar {
         let stellar_radius_au = stellar_radius_au(stellar_mass);
         let stellar_surface_temp = stellar_surface_temp(stellar_radius_au, stellar_luminosity);
         let spectral_class = spectral_class(&stellar_surface_temp);
+        let spectral_subclass = spectral_subclass(&stellar_surface_temp, &spectral_class);
         let bv_color_index = bv_color_index(stellar_surface_temp);
         let color = bv_to_rgb(bv_color_index);
         let ecosphere = ecosphere(&stellar_luminosity, &spectral_class);
 
         Self {
             stellar_mass,
+            metallicity,
             stellar_luminosity,
             main_seq_age,
             ecosphere,
             stellar_surface_temp,
             stellar_radius_au,
             spectral_class,
+            spectral_subclass,
+            luminosity_class: LuminosityClass::MainSequence,
             bv_color_index,
             color,
+            age: 0.0,
+            evolutionary_stage: EvolutionaryStage::MainSequence,
+        }
+    }
+
+    /// Star of given initial mass evolved to age in years. Luminosity, radius and derived surface temperature, spectral class, color and ecosphere follow evolutionary track of star, white dwarf keeps only part of initial mass.
+    pub fn at_age(stellar_mass: f64, metallicity: f64, age: f64) -> Self {
+        let initial_star = PrimaryStar::new(stellar_mass, metallicity);
+        let (evolutionary_stage, stellar_luminosity, stellar_radius_au, stellar_mass) =
+            evolutionary_track(stellar_mass, age);
+        let stellar_surface_temp = stellar_surface_temp(stellar_radius_au, stellar_luminosity);
+        let spectral_class = spectral_class(&stellar_surface_temp);
+        let spectral_subclass = spectral_subclass(&stellar_surface_temp, &spectral_class);
+        let luminosity_class = luminosity_class(&evolutionary_stage, &stellar_luminosity);
+        let bv_color_index = bv_color_index(stellar_surface_temp);
+        let color = bv_to_rgb(bv_color_index);
+        let ecosphere = ecosphere(&stellar_luminosity, &spectral_class);
+
+        Self {
+            stellar_mass,
+            metallicity,
+            stellar_luminosity,
+            main_seq_age: initial_star.main_seq_age,
+            ecosphere,
+            stellar_surface_temp,
+            stellar_radius_au,
+            spectral_class,
+            spectral_subclass,
+            luminosity_class,
+            bv_color_index,
+            color,
+            age,
+            evolutionary_stage,
+        }
+    }
+
+    /// Full spectral type, for example G2V or M4V. White dwarfs are typed by temperature index, for example D7.1
+    pub fn spectral_type(&self) -> String {
+        match (self.luminosity_class, self.spectral_class) {
+            (LuminosityClass::WhiteDwarf, _) => format!(
+                "D{:.1}",
+                WHITE_DWARF_TEMPERATURE_INDEX / self.stellar_surface_temp
+            ),
+            (_, SpectralClass::Rogue) => format!("{:?}", SpectralClass::Rogue),
+            (luminosity_class, spectral_class) => format!(
+                "{:?}{}{}",
+                spectral_class,
+                self.spectral_subclass,
+                luminosity_class.symbol()
+            ),
+        }
+    }
+}
+
+/// Heavy element content of star relative to the Sun from metallicity [Fe/H]
+pub fn metal_content(metallicity: &f64) -> f64 {
+    10.0_f64.powf(*metallicity)
+}
+
+/// Random age of system in years, as in StarGen: between 1 and 6 billion years, but not older than main sequence lifetime of star
+pub fn random_system_age(main_seq_age: &f64, rng: &mut dyn RngCore) -> f64 {
+    let max_age = main_seq_age.min(MAX_SYSTEM_AGE);
+    // Short-lived massive stars
+    let min_age = MIN_SYSTEM_AGE.min(max_age / 2.0);
+    rng.gen_range(min_age..max_age)
+}
+
+/// Stage, luminosity, radius in AU and mass of star with given initial mass at age in years.
+/// Main sequence luminosity brightens with age and matches mass-luminosity relation at present age of the Sun, subgiant and red giant phases are interpolated geometrically, white dwarf cools by Mestel law.
+pub fn evolutionary_track(stellar_mass: f64, age: f64) -> (EvolutionaryStage, f64, f64, f64) {
+    let reference_luminosity = luminosity(stellar_mass);
+    let reference_radius = stellar_radius_au(stellar_mass);
+    let main_seq_age = main_sequence_age(stellar_mass, reference_luminosity);
+    let main_seq_fraction = age / main_seq_age;
+    let subgiant_end = 1.0 + SUBGIANT_DURATION;
+    let red_giant_end = subgiant_end + RED_GIANT_DURATION;
+
+    let main_seq_luminosity = |fraction: f64| {
+        reference_luminosity
+            / (1.0 + MAIN_SEQUENCE_BRIGHTENING * (1.0 - fraction / SUN_MAIN_SEQUENCE_AGE_FRACTION))
+    };
+    // Surface temperature is kept on main sequence
+    let main_seq_radius =
+        |luminosity: f64| reference_radius * (luminosity / reference_luminosity).sqrt();
+    let subgiant_luminosity = main_seq_luminosity(1.0);
+    let subgiant_radius = main_seq_radius(subgiant_luminosity);
+    let red_giant_luminosity = subgiant_luminosity * SUBGIANT_LUMINOSITY_GROWTH;
+    let red_giant_radius = subgiant_radius * SUBGIANT_RADIUS_GROWTH;
+    let red_giant_tip_luminosity = RED_GIANT_TIP_LUMINOSITY.max(red_giant_luminosity * 10.0);
+
+    match main_seq_fraction {
+        f if f < 1.0 => {
+            let luminosity = main_seq_luminosity(f);
+            (
+                EvolutionaryStage::MainSequence,
+                luminosity,
+                main_seq_radius(luminosity),
+                stellar_mass,
+            )
+        }
+        f if f < subgiant_end => {
+            let progress = (f - 1.0) / SUBGIANT_DURATION;
+            (
+                EvolutionaryStage::Subgiant,
+                subgiant_luminosity * SUBGIANT_LUMINOSITY_GROWTH.powf(progress),
+                subgiant_radius * SUBGIANT_RADIUS_GROWTH.powf(progress),
+                stellar_mass,
+            )
+        }
+        f if f < red_giant_end => {
+            let progress = (f - subgiant_end) / RED_GIANT_DURATION;
+            let luminosity = red_giant_luminosity
+                * (red_giant_tip_luminosity / red_giant_luminosity).powf(progress);
+            (
+                EvolutionaryStage::RedGiant,
+                luminosity,
+                red_giant_radius
+                    * (luminosity / red_giant_luminosity).powf(RED_GIANT_RADIUS_EXPONENT),
+                stellar_mass,
+            )
+        }
+        f => {
+            let mass = white_dwarf_mass(stellar_mass);
+            let cooling_age = ((f - red_giant_end) * main_seq_age).max(WHITE_DWARF_MIN_COOLING_AGE);
+            let luminosity = WHITE_DWARF_LUMINOSITY
+                * (mass / WHITE_DWARF_TYPICAL_MASS)
+                * (cooling_age / 1.0e9).powf(WHITE_DWARF_COOLING_EXPONENT);
+            let radius = WHITE_DWARF_RADIUS
+                * (mass / WHITE_DWARF_TYPICAL_MASS).powf(-1.0 / 3.0)
+                * SOLAR_RADIUS;
+            (EvolutionaryStage::WhiteDwarf, luminosity, radius, mass)
         }
     }
 }
 
-/// Approximated luminosity from mass
+/// Largest radius in AU reached by star of given initial mass up to age in years, at tip of asymptotic giant branch for white dwarfs
+pub fn max_stellar_radius_au(stellar_mass: f64, age: f64) -> f64 {
+    let main_seq_age = main_sequence_age(stellar_mass, luminosity(stellar_mass));
+    let red_giant_tip_age =
+        main_seq_age * (1.0 + SUBGIANT_DURATION + RED_GIANT_DURATION) * (1.0 - f64::EPSILON);
+    let (_, _, radius, _) = evolutionary_track(stellar_mass, age.min(red_giant_tip_age));
+    radius
+}
+
+/// Mass of white dwarf left by star of given initial mass, in solar masses
+pub fn white_dwarf_mass(stellar_mass: f64) -> f64 {
+    (WHITE_DWARF_MASS_SLOPE * stellar_mass + WHITE_DWARF_MASS_INTERCEPT)
+        .min(CHANDRASEKHAR_LIMIT)
+        .min(stellar_mass)
+}
+
+/// Main sequence luminosity from mass, piecewise mass-luminosity relation of detached eclipsing binaries (Eker et al. 2018).
+/// Relation is extrapolated below 0.179 and above 31 solar masses.
 pub fn luminosity(mass: f64) -> f64 {
-    mass.powf(3.5)
+    let (slope, intercept) = match mass {
+        m if m <= 0.45 => (2.028, -0.976),
+        m if m <= 0.72 => (4.572, -0.102),
+        m if m <= 1.05 => (5.743, -0.007),
+        m if m <= 2.4 => (4.329, 0.010),
+        m if m <= 7.0 => (3.967, 0.093),
+        _ => (2.865, 1.105),
+    };
+    10.0_f64.powf(slope * mass.log10() + intercept)
 }
 
 /// Star min-max habitable zone
@@ -79,12 +261,22 @@ pub fn main_sequence_age(stellar_mass: f64, stellar_luminosity: f64) -> f64 {
     1.0e10 * stellar_mass / stellar_luminosity
 }
 
-/// Empirical star radius from mass (for main sequence only)
+/// Main sequence star radius from mass (Eker et al. 2018): quadratic mass-radius relation up to 1.5 solar masses, radius of more massive stars is derived from luminosity and mass-temperature relation
 pub fn stellar_radius_au(mass: f64) -> f64 {
-    if mass <= 1.66 {
-        return 1.06 * mass.powf(0.945) * SOLAR_RADIUS;
+    match mass <= 1.5 {
+        true => (0.438 * mass.powf(2.0) + 0.479 * mass + 0.075) * SOLAR_RADIUS,
+        false => {
+            let luminosity_watt = luminosity(mass) * WATT_PER_SOLAR_LUMINOSITY;
+            let temp = main_sequence_temp(mass);
+            (luminosity_watt / (4.0 * PI * SIGMA * temp.powf(4.0))).sqrt() / M_PER_AU
+        }
     }
-    1.33 * mass.powf(0.555) * SOLAR_RADIUS
+}
+
+/// Main sequence star surface temperature in Kelvin from mass, for stars more massive than 1.5 solar masses (Eker et al. 2018)
+pub fn main_sequence_temp(mass: f64) -> f64 {
+    let log_mass = mass.log10();
+    10.0_f64.powf(-0.170 * log_mass.powf(2.0) + 0.888 * log_mass + 3.671)
 }
 
 /// Star surface temperature in Kelvin, derived from [Stefan–Boltzmann law](https://en.wikipedia.org/wiki/Stefan%E2%80%93Boltzmann_law)
@@ -106,17 +298,9 @@ pub fn bv_to_rgb(bv: f64) -> [f64; 3] {
     let mut r = 0.0;
     let mut g = 0.0;
     let mut b = 0.0;
-    let mut bv = bv;
+    let bv = bv.clamp(-0.4, 2.0);
     let mut t;
 
-    if bv < -0.4 {
-        bv = -0.4;
-    }
-
-    if bv > 2.0 {
-        bv = 2.0;
-    }
-
     if (-0.40..0.00).contains(&bv) {
         t = (bv + 0.40) / 0.40;
         r = 0.61 + (0.11 * t) + (0.1 * t * t);
@@ -171,15 +355,75 @@ pub fn spectral_class(stellar_surface_temp: &f64) -> SpectralClass {
     }
 }
 
+/// Range of surface temperature of spectral class in Kelvin, same as in spectral_class. Upper limit of O class is taken for hottest O2 stars.
+fn spectral_class_temp_range(spectral_class: &SpectralClass) -> (f64, f64) {
+    match spectral_class {
+        SpectralClass::O => (30000.0, 50000.0),
+        SpectralClass::B => (10000.0, 30000.0),
+        SpectralClass::A => (7500.0, 10000.0),
+        SpectralClass::F => (6000.0, 7500.0),
+        SpectralClass::G => (5200.0, 6000.0),
+        SpectralClass::K => (3700.0, 5200.0),
+        SpectralClass::M => (2400.0, 3700.0),
+        SpectralClass::L => (1300.0, 2400.0),
+        SpectralClass::T => (550.0, 1300.0),
+        SpectralClass::Y => (273.15, 550.0),
+        SpectralClass::Rogue => (0.0, 273.15),
+    }
+}
+
+/// Spectral subclass 0-9 from temperature, interpolated linearly from hot to cool edge of spectral class
+pub fn spectral_subclass(stellar_surface_temp: &f64, spectral_class: &SpectralClass) -> u8 {
+    let (min_temp, max_temp) = spectral_class_temp_range(spectral_class);
+    let subclass = 10.0 * (max_temp - stellar_surface_temp) / (max_temp - min_temp);
+    subclass.clamp(0.0, 9.0) as u8
+}
+
+/// Luminosity class from evolutionary stage, giants are split by luminosity
+pub fn luminosity_class(
+    evolutionary_stage: &EvolutionaryStage,
+    luminosity: &f64,
+) -> LuminosityClass {
+    match evolutionary_stage {
+        EvolutionaryStage::MainSequence => LuminosityClass::MainSequence,
+        EvolutionaryStage::Subgiant => LuminosityClass::Subgiant,
+        EvolutionaryStage::RedGiant => match *luminosity {
+            l if l >= SUPERGIANT_LUMINOSITY => LuminosityClass::Supergiant,
+            l if l >= BRIGHT_GIANT_LUMINOSITY => LuminosityClass::BrightGiant,
+            _ => LuminosityClass::Giant,
+        },
+        EvolutionaryStage::WhiteDwarf => LuminosityClass::WhiteDwarf,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::AccreteBuilder;
 
     #[test]
     fn check_sun_temperature() {
-        let sun_radius = stellar_radius_au(1.0);
-        let sun_temp = stellar_surface_temp(sun_radius, 1.0);
-        assert_eq!(5606, sun_temp as usize);
+        let sun = PrimaryStar::new(1.0, 0.0);
+        assert!((sun.stellar_surface_temp - 5772.0).abs() < 20.0);
+        assert_eq!(sun.spectral_type(), "G2V");
+    }
+
+    #[test]
+    fn main_sequence_spectral_types() {
+        for (mass, spectral_type, min_temp, max_temp) in [
+            (30.0, "O", 35000.0, 45000.0),
+            (10.0, "B", 20000.0, 30000.0),
+            (2.0, "A", 8000.0, 10000.0),
+            (0.7, "K", 4200.0, 4800.0),
+            (0.2, "M", 2900.0, 3400.0),
+        ] {
+            let star = PrimaryStar::new(mass, 0.0);
+            assert!(star.spectral_type().starts_with(spectral_type));
+            assert!(star.spectral_type().ends_with('V'));
+            assert!((min_temp..max_temp).contains(&star.stellar_surface_temp));
+        }
+        assert_eq!(spectral_subclass(&3200.0, &SpectralClass::M), 3);
+        assert_eq!(spectral_subclass(&50000.0, &SpectralClass::O), 0);
     }
 
     #[test]
@@ -188,6 +432,64 @@ mod tests {
         assert_eq!(0.6, bv_sun);
     }
 
+    #[test]
+    fn sun_evolutionary_track() {
+        let young_sun = PrimaryStar::at_age(1.0, 0.0, 0.0);
+        let present_sun = PrimaryStar::at_age(1.0, 0.0, 4.6e9);
+        assert!(young_sun.stellar_luminosity < 0.75);
+        assert!((present_sun.stellar_luminosity - 1.0).abs() < 0.03);
+        assert_eq!(
+            present_sun.evolutionary_stage,
+            EvolutionaryStage::MainSequence
+        );
+        assert!(present_sun.ecosphere.0 > young_sun.ecosphere.0);
+
+        let subgiant = PrimaryStar::at_age(1.0, 0.0, 1.03e10);
+        assert_eq!(subgiant.evolutionary_stage, EvolutionaryStage::Subgiant);
+
+        let red_giant = PrimaryStar::at_age(1.0, 0.0, 1.12e10);
+        assert_eq!(red_giant.evolutionary_stage, EvolutionaryStage::RedGiant);
+        assert!(red_giant.stellar_luminosity > 100.0);
+        assert!(red_giant.stellar_surface_temp < 5200.0);
+        assert!(red_giant.spectral_type().ends_with("III"));
+
+        let white_dwarf = PrimaryStar::at_age(1.0, 0.0, 1.2e10);
+        assert_eq!(
+            white_dwarf.evolutionary_stage,
+            EvolutionaryStage::WhiteDwarf
+        );
+        assert!((white_dwarf.stellar_mass - 0.503).abs() < 1e-9);
+        assert!(white_dwarf.stellar_luminosity < 0.01);
+        assert!(white_dwarf.stellar_radius_au < SOLAR_RADIUS * 0.02);
+        assert!(white_dwarf.spectral_type().starts_with('D'));
+        // Tip of asymptotic giant branch reaches about 1 AU
+        let max_radius = max_stellar_radius_au(1.0, 1.2e10);
+        assert!(max_radius > 0.5 && max_radius < 1.5);
+    }
+
+    #[test]
+    fn planets_of_dying_star() {
+        let mut accrete = AccreteBuilder::new(1)
+            .stellar_mass(1.0)
+            .system_age(1.2e10)
+            .build()
+            .expect("Invalid configuration");
+        let system = accrete
+            .planetary_system()
+            .expect("Failed to generate planetary system");
+        assert_eq!(
+            system.primary_star.evolutionary_stage,
+            EvolutionaryStage::WhiteDwarf
+        );
+        // Orbits expanded by mass loss beyond engulfment radius
+        let engulfment_radius =
+            max_stellar_radius_au(1.0, 1.2e10) / system.primary_star.stellar_mass;
+        assert!(system
+            .planets
+            .iter()
+            .all(|p| p.a * (1.0 - p.e) > engulfment_radius));
+    }
+
     // #[test]
     // fn check_sun_color() {
     //     let color_sun = bv_to_rgb(0.6);
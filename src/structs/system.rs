@@ -1,6 +1,14 @@
+use crate::consts::*;
+use crate::dynamics::collision::largest_remnant_mass;
+use crate::dynamics::migration::*;
+use crate::dynamics::orbit::elements_from_state;
+use crate::dynamics::resonance::*;
+use crate::dynamics::stability::*;
+use crate::dynamics::StateVector;
 use crate::enviro::*;
-use crate::events_log::accrete_event::AccreteEvents;
-use crate::events_log::event_source::EventSource;
+use crate::error::AccreteError;
+use crate::events_log::accrete_event::{AccreteEvent, AccreteEventKind};
+use crate::events_log::observer::{AccreteObserver, NullObserver};
 use crate::structs::*;
 use crate::utils::*;
 
@@ -11,11 +19,21 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct System {
     pub primary_star: PrimaryStar,
+    pub companion_star: Option<CompanionStar>,
     pub planets: Vec<Planetesimal>,
+    // asteroid and kuiper belts, sorted by distance to primary star
+    pub belts: Vec<Belt>,
+    // chains of three and more planets or moons in mean-motion resonance
+    pub resonant_chains: Vec<ResonantChain>,
+    // none if stability was not checked
+    pub stability: Option<StabilityReport>,
     pub cloud_eccentricity: f64,
+    // dust density, dust-to-gas ratio and critical mass coefficients are adjusted for metallicity of primary star
     pub dust_density_coeff: f64,
     pub k: f64,
     pub b: f64,
+    // ice lines of cloud around young primary star
+    pub condensation_lines: CondensationLines,
     pub planetesimal_inner_bound: f64,
     pub planetesimal_outer_bound: f64,
     pub inner_dust: f64,
@@ -27,24 +45,51 @@ pub struct System {
 impl System {
     pub fn set_initial_conditions(
         stellar_mass: f64,
+        metallicity: f64,
         dust_density_coeff: f64,
         k: f64,
         cloud_eccentricity: f64,
         b: f64,
+        companion_star: Option<CompanionStarConfig>,
+        condensation: Option<CondensationConfig>,
     ) -> Self {
-        let primary_star = PrimaryStar::new(stellar_mass);
+        let primary_star = PrimaryStar::new(stellar_mass, metallicity);
+        let companion_star =
+            companion_star.map(|c| CompanionStar::new(stellar_mass, metallicity, &c));
+        // Solids of cloud scale with heavy element content, amount of gas is kept
+        let metal_content = metal_content(&metallicity);
+        let dust_density_coeff = dust_density_coeff * metal_content;
+        let k = k / metal_content;
+        let b = b * metal_content.powf(CRITICAL_MASS_METALLICITY_EXPONENT);
+        let total_mass = match &companion_star {
+            Some(c) => stellar_mass + c.star.stellar_mass,
+            None => stellar_mass,
+        };
         let planetesimal_inner_bound = innermost_planet(&stellar_mass);
-        let planetesimal_outer_bound = outermost_planet(&stellar_mass);
+        let planetesimal_outer_bound = outermost_planet(&total_mass);
         let inner_dust = 0.0;
-        let outer_dust = stellar_dust_limit(&stellar_mass);
-        let dust_band = DustBand::new(outer_dust, inner_dust, true, true);
-        let dust_bands = vec![dust_band];
+        let outer_dust = stellar_dust_limit(&total_mass);
+        let dust_bands = initial_dust_bands(inner_dust, outer_dust, &companion_star);
+        let dust_left = dust_availible(
+            &dust_bands,
+            &planetesimal_inner_bound,
+            &planetesimal_outer_bound,
+        );
+        let condensation_lines = CondensationLines::new(
+            &primary_star.stellar_luminosity,
+            condensation.is_some_and(|c| c.volatile_ices),
+        );
 
         Self {
             primary_star,
+            companion_star,
             planets: Vec::new(),
+            belts: Vec::new(),
+            resonant_chains: Vec::new(),
+            stability: None,
             k,
             b,
+            condensation_lines,
             dust_density_coeff,
             cloud_eccentricity,
             planetesimal_inner_bound,
@@ -52,54 +97,67 @@ impl System {
             inner_dust,
             outer_dust,
             dust_bands,
-            dust_left: true,
+            dust_left,
         }
     }
 
     pub fn distribute_planetary_masses(
         &mut self,
+        disk_profile: &dyn DiskProfile,
         rng: &mut dyn RngCore,
-        events_log: &mut AccreteEvents,
+        observer: &mut dyn AccreteObserver,
     ) {
         let Self {
             primary_star,
+            companion_star,
             planets,
             k,
             b,
             dust_density_coeff,
             cloud_eccentricity,
+            condensation_lines,
             planetesimal_inner_bound,
             planetesimal_outer_bound,
             dust_bands,
             dust_left,
             ..
         } = self;
-        let PrimaryStar {
-            stellar_mass,
-            stellar_luminosity,
-            ..
-        } = primary_star;
 
         while *dust_left {
             let mut p = Planetesimal::new(planetesimal_inner_bound, planetesimal_outer_bound, rng);
-            p.event("planetesimal_created", events_log);
-
+            let (stellar_mass, stellar_luminosity) =
+                &central_body(primary_star, companion_star, &p.a);
+            p.is_circumbinary = companion_star
+                .as_ref()
+                .is_some_and(|c| c.is_circumbinary(&p.a));
             let inside_range = inner_swept_limit(&p.a, &p.e, &p.mass, cloud_eccentricity);
             let outside_range = outer_swept_limit(&p.a, &p.e, &p.mass, cloud_eccentricity);
-            let dust_density = dust_density(dust_density_coeff, stellar_mass, &p.a);
             let crit_mass = critical_limit(b, &p.a, &p.e, stellar_luminosity);
 
             if dust_availible(dust_bands, &inside_range, &outside_range) {
-                accrete_dust(
+                observer.emit(AccreteEventKind::PlanetesimalCreated, || {
+                    AccreteEvent::PlanetesimalCreated {
+                        planetesimal: p.clone(),
+                    }
+                });
+
+                let gas_mass = accrete_dust(
                     &mut p.mass,
                     &p.a,
                     &p.e,
                     &crit_mass,
                     dust_bands,
                     cloud_eccentricity,
-                    &dust_density,
+                    disk_profile,
+                    dust_density_coeff,
+                    stellar_mass,
                     k,
                 );
+                p.bulk_composition = BulkComposition::accreted(
+                    &p.mass,
+                    &gas_mass,
+                    &condensation_lines.ice_to_rock(&p.a),
+                );
 
                 let min = inner_swept_limit(&p.a, &p.e, &p.mass, cloud_eccentricity);
                 let max = outer_swept_limit(&p.a, &p.e, &p.mass, cloud_eccentricity);
@@ -107,11 +165,19 @@ impl System {
                 update_dust_lanes(dust_bands, min, max, &p.mass, &crit_mass);
                 compress_dust_lanes(dust_bands);
 
-                dust_bands.event("dust_bands_updated", events_log);
+                observer.emit(AccreteEventKind::DustBandsUpdated, || {
+                    AccreteEvent::DustBandsUpdated {
+                        dust_bands: dust_bands.clone(),
+                    }
+                });
 
                 if p.mass > crit_mass {
                     p.is_gas_giant = true;
-                    p.event("planetesimal_to_gas_giant", events_log);
+                    observer.emit(AccreteEventKind::PlanetesimalToGasGiant, || {
+                        AccreteEvent::PlanetesimalToGasGiant {
+                            planetesimal: p.clone(),
+                        }
+                    });
                 }
 
                 p.orbit_clearing = clearing_neightbourhood(&p.mass, &p.a, stellar_mass);
@@ -119,13 +185,23 @@ impl System {
                     p.is_dwarf_planet = true;
                 }
                 p.orbit_zone = orbital_zone(stellar_luminosity, p.distance_to_primary_star);
-                p.radius = kothari_radius(&p.mass, &p.is_gas_giant, &p.orbit_zone);
+                p.radius =
+                    bulk_radius(&p.mass, &p.is_gas_giant, &p.orbit_zone, &p.bulk_composition);
 
-                p.event("planetesimal_updated", events_log);
+                observer.emit(AccreteEventKind::PlanetesimalUpdated, || {
+                    AccreteEvent::PlanetesimalUpdated {
+                        planetesimal: p.clone(),
+                    }
+                });
 
                 planets.push(p);
-                planets.sort_by(|p1, p2| p1.a.partial_cmp(&p2.a).unwrap());
-                coalesce_planetesimals(stellar_luminosity, stellar_mass, planets, rng, events_log);
+                planets.sort_by(|p1, p2| p1.a.total_cmp(&p2.a));
+                coalesce_planetesimals(
+                    &|a| central_body(primary_star, companion_star, a),
+                    planets,
+                    rng,
+                    observer,
+                );
             }
 
             *dust_left = dust_availible(
@@ -136,16 +212,103 @@ impl System {
         }
     }
 
+    /// Type I and type II migration of planets through remaining gas of disk, must be called after accretion is finished. Gas giants clear gas along their path, planets with crossing orbits coalesce.
+    pub fn migrate_planets(
+        &mut self,
+        config: &MigrationConfig,
+        disk_profile: &dyn DiskProfile,
+        rng: &mut dyn RngCore,
+        observer: &mut dyn AccreteObserver,
+    ) {
+        let Self {
+            primary_star,
+            companion_star,
+            planets,
+            k,
+            b,
+            dust_density_coeff,
+            cloud_eccentricity,
+            dust_bands,
+            ..
+        } = self;
+
+        for planet in planets.iter_mut() {
+            let (stellar_mass, stellar_luminosity) =
+                &central_body(primary_star, companion_star, &planet.a);
+            let from_a = planet.a;
+            let (a, regime) = migrate(
+                planet,
+                dust_bands,
+                stellar_mass,
+                disk_profile,
+                dust_density_coeff,
+                k,
+                cloud_eccentricity,
+                config,
+            );
+            if a >= from_a {
+                continue;
+            }
+
+            if planet.is_gas_giant {
+                let crit_mass = critical_limit(b, &from_a, &planet.e, stellar_luminosity);
+                let min = inner_swept_limit(&a, &planet.e, &planet.mass, cloud_eccentricity);
+                let max = inner_swept_limit(&from_a, &planet.e, &planet.mass, cloud_eccentricity);
+                update_dust_lanes(dust_bands, min, max, &planet.mass, &crit_mass);
+                compress_dust_lanes(dust_bands);
+
+                observer.emit(AccreteEventKind::DustBandsUpdated, || {
+                    AccreteEvent::DustBandsUpdated {
+                        dust_bands: dust_bands.clone(),
+                    }
+                });
+            }
+
+            planet.set_orbit(a, planet.e);
+            planet.orbit_zone = orbital_zone(stellar_luminosity, a);
+            planet.radius = bulk_radius(
+                &planet.mass,
+                &planet.is_gas_giant,
+                &planet.orbit_zone,
+                &planet.bulk_composition,
+            );
+            planet.orbit_clearing = clearing_neightbourhood(&planet.mass, &a, stellar_mass);
+            planet.is_dwarf_planet = planet.orbit_clearing < 1.0;
+
+            observer.emit(AccreteEventKind::PlanetesimalMigrated, || {
+                AccreteEvent::PlanetesimalMigrated {
+                    from_a,
+                    regime,
+                    result: planet.clone(),
+                }
+            });
+        }
+
+        planets.sort_by(|p1, p2| p1.a.total_cmp(&p2.a));
+        coalesce_planetesimals(
+            &|a| central_body(primary_star, companion_star, a),
+            planets,
+            rng,
+            observer,
+        );
+    }
+
     pub fn post_accretion(
         &mut self,
         intensity: u32,
         rng: &mut dyn RngCore,
-        events_log: &mut AccreteEvents,
-    ) {
-        self.event("post_accretion_started", events_log);
+        observer: &mut dyn AccreteObserver,
+    ) -> Result<(), AccreteError> {
+        observer.emit(AccreteEventKind::PostAccretionStarted, || {
+            AccreteEvent::PostAccretionStarted
+        });
+        if intensity == 0 {
+            return Ok(());
+        }
 
         let Self {
             primary_star,
+            companion_star,
             planets,
             ..
         } = self;
@@ -155,46 +318,97 @@ impl System {
             weights.push(p.mass * p.a);
         }
 
-        let dist = WeightedIndex::new(&weights).unwrap();
+        let dist =
+            WeightedIndex::new(&weights).map_err(|e| AccreteError::PostAccretion(e.to_string()))?;
         for _i in 0..intensity {
             let p = &mut planets[dist.sample(rng)];
             let Planetesimal { a, e, mass, .. } = p;
             let r_inner = inner_effect_limit(a, e, mass);
             let r_outer = outer_effect_limit(a, e, mass);
             let mut outer_body = Planetesimal::random_outer_body(&r_inner, &r_outer, rng);
+            let (stellar_mass, stellar_luminosity) =
+                central_body(primary_star, companion_star, &p.a);
 
-            outer_body.event("outer_body_injected", events_log);
+            observer.emit(AccreteEventKind::OuterBodyInjected, || {
+                AccreteEvent::OuterBodyInjected {
+                    planetesimal: outer_body.clone(),
+                }
+            });
 
             planetesimals_intersect(
                 &mut outer_body,
                 p,
-                &primary_star.stellar_luminosity,
-                &primary_star.stellar_mass,
+                &stellar_luminosity,
+                &stellar_mass,
                 rng,
-                events_log,
+                observer,
             );
         }
+
+        Ok(())
+    }
+
+    /// Regular moons of gas giants accreted from circumplanetary disks, must be called after post-accretion. Captured moons are kept.
+    pub fn form_regular_moons(
+        &mut self,
+        rng: &mut dyn RngCore,
+        observer: &mut dyn AccreteObserver,
+    ) {
+        let Self {
+            primary_star,
+            companion_star,
+            planets,
+            cloud_eccentricity,
+            condensation_lines,
+            ..
+        } = self;
+
+        for planet in planets.iter_mut().filter(|p| p.is_gas_giant) {
+            let (stellar_mass, _) = central_body(primary_star, companion_star, &planet.a);
+            planet.hill_sphere = hill_sphere_au(&planet.a, &planet.e, &planet.mass, &stellar_mass);
+            let ice_to_rock = condensation_lines.ice_to_rock(&planet.a);
+            let mut moons = accrete_regular_moons(planet, cloud_eccentricity, &ice_to_rock, rng);
+            if moons.is_empty() {
+                continue;
+            }
+            planet.moons.append(&mut moons);
+            planet.moons.sort_by(|p1, p2| p1.a.total_cmp(&p2.a));
+
+            observer.emit(AccreteEventKind::RegularMoonsFormed, || {
+                AccreteEvent::RegularMoonsFormed {
+                    planet_id: planet.id.clone(),
+                    result: planet.clone(),
+                }
+            });
+            moons_to_rings(planet, observer);
+        }
     }
 
     pub fn process_planets(&mut self, rng: &mut dyn RngCore) {
         let System {
             primary_star,
+            companion_star,
             planets,
             ..
         } = self;
-        let PrimaryStar {
-            stellar_luminosity,
-            stellar_mass,
-            main_seq_age,
-            ecosphere,
-            ..
-        } = primary_star;
+        let primary_star = &*primary_star;
+        let system_age = &primary_star.age;
 
         for planet in planets.iter_mut() {
+            planet.is_circumbinary = companion_star
+                .as_ref()
+                .is_some_and(|c| c.is_circumbinary(&planet.a));
+            let (stellar_mass, stellar_luminosity) =
+                &central_body(primary_star, companion_star, &planet.a);
+            let ecosphere = &match planet.is_circumbinary {
+                true => ecosphere(stellar_luminosity, &primary_star.spectral_class),
+                false => primary_star.ecosphere,
+            };
+
             planet.derive_planetary_environment(
                 stellar_luminosity,
                 stellar_mass,
-                main_seq_age,
+                system_age,
                 ecosphere,
                 rng,
             );
@@ -202,22 +416,237 @@ impl System {
                 moon.derive_planetary_environment(
                     stellar_luminosity,
                     &planet.mass,
-                    main_seq_age,
+                    system_age,
                     ecosphere,
                     rng,
                 );
             }
         }
     }
+
+    /// Detect mean-motion resonances among planets and among moons of each planet, optionally capture near-resonant orbits into exact resonance.
+    /// Planets with changed resonances or orbits of their own or of their moons are updated.
+    pub fn process_resonances(&mut self, capture: bool, observer: &mut dyn AccreteObserver) {
+        let initial_orbits = self.planets.iter().map(resonant_orbits).collect::<Vec<_>>();
+        let mut chains = resolve_resonances(&mut self.planets, capture, None);
+        for planet in self.planets.iter_mut() {
+            chains.extend(resolve_resonances(
+                &mut planet.moons,
+                capture,
+                Some(&planet.id),
+            ));
+        }
+        self.resonant_chains = chains;
+
+        for (planet, initial_orbits) in self.planets.iter().zip(initial_orbits) {
+            if resonant_orbits(planet) != initial_orbits {
+                observer.emit(AccreteEventKind::PlanetesimalUpdated, || {
+                    AccreteEvent::PlanetesimalUpdated {
+                        planetesimal: planet.clone(),
+                    }
+                });
+            }
+        }
+    }
+
+    /// Check spacing of neighbouring planets and optionally integrate their orbits, unstable planets are reported or resolved by ejection, collision and scattering.
+    /// Must be called after orbits are oriented and stars are evolved, so that report describes final orbits.
+    /// S-type planets around primary star and circumbinary planets around both stars are separated by orbit of companion, each group is checked on its own with mass of its central body.
+    pub fn process_stability(
+        &mut self,
+        config: &StabilityConfig,
+        observer: &mut dyn AccreteObserver,
+    ) {
+        let StabilityConfig {
+            resolve,
+            integration_years,
+        } = *config;
+        let System {
+            primary_star,
+            companion_star,
+            planets,
+            ..
+        } = self;
+        let (circumbinary_planets, s_type_planets): (Vec<_>, Vec<_>) =
+            std::mem::take(planets).into_iter().partition(|p| {
+                companion_star
+                    .as_ref()
+                    .is_some_and(|c| c.is_circumbinary(&p.a))
+            });
+        let mut report = StabilityReport {
+            hill_spacings: Vec::new(),
+            instabilities: Vec::new(),
+            integration_years: 0.0,
+            resolved: resolve,
+        };
+        // Integrated time of all groups, shortest one if step limit was reached
+        let mut integrated_years: Option<f64> = None;
+
+        for mut group in [s_type_planets, circumbinary_planets] {
+            if group.is_empty() {
+                continue;
+            }
+            let (central_mass, _) = &central_body(primary_star, companion_star, &group[0].a);
+            let instabilities = match integration_years > 0.0 {
+                true => {
+                    let integration = integrate(&group, central_mass, &integration_years);
+                    if resolve {
+                        apply_integration(&mut group, &integration, central_mass, observer);
+                    }
+                    integrated_years = Some(
+                        integrated_years.map_or(integration.years, |y| y.min(integration.years)),
+                    );
+                    integration.instabilities
+                }
+                false => match resolve {
+                    true => resolve_hill_instabilities(&mut group, central_mass, observer),
+                    false => hill_instabilities(&group, central_mass),
+                },
+            };
+            report.instabilities.extend(instabilities);
+            report
+                .hill_spacings
+                .extend(hill_spacings(&group, central_mass));
+            planets.extend(group);
+        }
+        planets.sort_by(|p1, p2| p1.a.total_cmp(&p2.a));
+
+        report.integration_years = integrated_years.unwrap_or(0.0);
+        self.stability = Some(report);
+    }
+
+    /// Evolve primary and companion stars to age of the system in years, must be called after accretion is finished.
+    /// Planets within largest radius reached by giant star are engulfed. Orbits of remaining planets and leftover dust expand as stars lose mass to white dwarf, binary orbit is kept and its stability limits follow new masses.
+    pub fn evolve_stars(&mut self, system_age: &f64, observer: &mut dyn AccreteObserver) {
+        let System {
+            primary_star,
+            companion_star,
+            planets,
+            dust_bands,
+            ..
+        } = self;
+        let initial_primary_star = *primary_star;
+        let initial_companion_star = *companion_star;
+        let PrimaryStar {
+            stellar_mass: initial_mass,
+            metallicity,
+            ..
+        } = initial_primary_star;
+        *primary_star = PrimaryStar::at_age(initial_mass, metallicity, *system_age);
+        if let Some(companion) = companion_star.as_mut() {
+            // Companion is not evolved yet, its mass is initial mass
+            let config = CompanionStarConfig::new(
+                companion.star.stellar_mass,
+                companion.separation,
+                companion.eccentricity,
+            );
+            *companion =
+                CompanionStar::at_age(primary_star.stellar_mass, metallicity, *system_age, &config);
+        }
+
+        let max_radius = max_stellar_radius_au(initial_mass, *system_age);
+        let (engulfed, remaining): (Vec<_>, Vec<_>) = std::mem::take(planets)
+            .into_iter()
+            .partition(|p| !p.is_circumbinary && p.a * (1.0 - p.e) <= max_radius);
+        *planets = remaining;
+        for planet in engulfed {
+            observer.emit(AccreteEventKind::PlanetesimalEngulfed, || {
+                AccreteEvent::PlanetesimalEngulfed {
+                    planetesimal_id: planet.id.clone(),
+                }
+            });
+        }
+
+        // Adiabatic mass loss, a * M is conserved. Orbit keeps central body it was formed around, limits of evolved binary are not used.
+        let expansion = |a: &f64| {
+            let (initial_central_mass, _) =
+                central_body(&initial_primary_star, &initial_companion_star, a);
+            let central_mass = match (initial_companion_star, &companion_star) {
+                (Some(initial), Some(companion)) if initial.is_circumbinary(a) => {
+                    primary_star.stellar_mass + companion.star.stellar_mass
+                }
+                _ => primary_star.stellar_mass,
+            };
+            initial_central_mass / central_mass
+        };
+
+        for planet in planets.iter_mut() {
+            let expansion = expansion(&planet.a);
+            if expansion > 1.0 {
+                planet.set_orbit(planet.a * expansion, planet.e);
+                observer.emit(AccreteEventKind::PlanetesimalUpdated, || {
+                    AccreteEvent::PlanetesimalUpdated {
+                        planetesimal: planet.clone(),
+                    }
+                });
+            }
+        }
+
+        let initial_dust_bands = dust_bands.clone();
+        for band in dust_bands.iter_mut() {
+            band.inner_edge *= expansion(&band.inner_edge);
+            band.outer_edge *= expansion(&band.outer_edge);
+        }
+        if *dust_bands != initial_dust_bands {
+            observer.emit(AccreteEventKind::DustBandsUpdated, || {
+                AccreteEvent::DustBandsUpdated {
+                    dust_bands: dust_bands.clone(),
+                }
+            });
+        }
+    }
+
+    /// Random orbit orientation of planets and moons
+    pub fn process_orbits(&mut self, rng: &mut dyn RngCore, observer: &mut dyn AccreteObserver) {
+        for planet in self.planets.iter_mut() {
+            planet.orient_orbit(rng);
+            observer.emit(AccreteEventKind::PlanetesimalUpdated, || {
+                AccreteEvent::PlanetesimalUpdated {
+                    planetesimal: planet.clone(),
+                }
+            });
+        }
+    }
+
+    /// Position and velocity of planet or moon with given id relative to primary star at time t in days since epoch.
+    /// Circumbinary planets orbit barycenter of binary, which is taken as primary star position. Moon orbits are rotated from orbital plane of planet.
+    pub fn state_vector(&self, id: &str, t: &f64) -> Option<StateVector> {
+        self.planets.iter().find_map(|planet| {
+            let (stellar_mass, _) =
+                central_body(&self.primary_star, &self.companion_star, &planet.a);
+            let planet_state = planet.state_at(&stellar_mass, t);
+            match planet.id == id {
+                true => Some(planet_state),
+                false => planet.moons.iter().find(|moon| moon.id == id).map(|moon| {
+                    let moon_state = moon.state_at(&planet.mass, t);
+                    planet_state.add(&moon_state.rotate(&planet.orbital_elements))
+                }),
+            }
+        })
+    }
+
+    /// Form belts from leftover dust and dwarf planets, must be called after accretion is finished
+    pub fn process_belts(&mut self, disk_profile: &dyn DiskProfile, rng: &mut dyn RngCore) {
+        self.belts = form_belts(
+            &self.planets,
+            &self.dust_bands,
+            &self.planetesimal_inner_bound,
+            disk_profile,
+            &self.dust_density_coeff,
+            &self.primary_star.stellar_mass,
+            &self.condensation_lines,
+            rng,
+        );
+    }
 }
 
 /// Check planetesimal coalescence
+/// Central body gives mass and luminosity of body orbited at given distance
 pub fn coalesce_planetesimals(
-    primary_star_luminosity: &f64,
-    primary_star_mass: &f64,
+    central_body: &dyn Fn(&f64) -> (f64, f64),
     planets: &mut Vec<Planetesimal>,
     rng: &mut dyn RngCore,
-    events_log: &mut AccreteEvents,
+    observer: &mut dyn AccreteObserver,
 ) {
     let mut next_planets = Vec::new();
     for (i, p) in planets.iter_mut().enumerate() {
@@ -225,13 +654,14 @@ pub fn coalesce_planetesimals(
             next_planets.push(p.clone());
         } else if let Some(prev_p) = next_planets.last_mut() {
             if check_orbits_intersect(p.a, p.e, p.mass, prev_p.a, prev_p.e, prev_p.mass) {
+                let (central_mass, central_luminosity) = central_body(&prev_p.a);
                 planetesimals_intersect(
                     p,
                     prev_p,
-                    primary_star_luminosity,
-                    primary_star_mass,
+                    &central_luminosity,
+                    &central_mass,
                     rng,
-                    events_log,
+                    observer,
                 );
             } else {
                 next_planets.push(p.clone());
@@ -241,6 +671,64 @@ pub fn coalesce_planetesimals(
     *planets = next_planets;
 }
 
+/// Dole's dust algorithm on circumplanetary disk of gas giant. Cloud is accreted around body of one solar mass, then distances are scaled to Hill sphere of planet and masses to planet mass.
+/// Disk is starved of gas, moons grow from dust and ices only and never become gas giants.
+fn accrete_regular_moons(
+    planet: &Planetesimal,
+    cloud_eccentricity: &f64,
+    ice_to_rock: &f64,
+    rng: &mut dyn RngCore,
+) -> Vec<Planetesimal> {
+    let inner_bound = innermost_planet(&1.0);
+    let outer_bound = outermost_planet(&1.0);
+    let mut dust_bands = vec![DustBand::new(stellar_dust_limit(&1.0), 0.0, true, false)];
+    let mut moons: Vec<Planetesimal> = Vec::new();
+
+    while dust_availible(&dust_bands, &inner_bound, &outer_bound) {
+        let mut moon = Planetesimal::new(&inner_bound, &outer_bound, rng);
+        moon.is_moon = true;
+        moon.moon_class = Some(MoonClass::Regular);
+        let inside_range = inner_swept_limit(&moon.a, &moon.e, &moon.mass, cloud_eccentricity);
+        let outside_range = outer_swept_limit(&moon.a, &moon.e, &moon.mass, cloud_eccentricity);
+        if !dust_availible(&dust_bands, &inside_range, &outside_range) {
+            continue;
+        }
+
+        accrete_dust(
+            &mut moon.mass,
+            &moon.a,
+            &moon.e,
+            &f64::INFINITY,
+            &mut dust_bands,
+            cloud_eccentricity,
+            &DiskModel::default(),
+            &CIRCUMPLANETARY_DUST_DENSITY_COEFF,
+            &1.0,
+            &K,
+        );
+        let min = inner_swept_limit(&moon.a, &moon.e, &moon.mass, cloud_eccentricity);
+        let max = outer_swept_limit(&moon.a, &moon.e, &moon.mass, cloud_eccentricity);
+        update_dust_lanes(&mut dust_bands, min, max, &moon.mass, &f64::INFINITY);
+        compress_dust_lanes(&mut dust_bands);
+
+        moons.push(moon);
+        moons.sort_by(|p1, p2| p1.a.total_cmp(&p2.a));
+        coalesce_planetesimals(&|_| (1.0, 1.0), &mut moons, rng, &mut NullObserver);
+    }
+
+    let length_scale = CIRCUMPLANETARY_DISK_RADIUS * planet.hill_sphere / outer_bound;
+    for moon in moons.iter_mut() {
+        moon.set_orbit(moon.a * length_scale, moon.e);
+        moon.distance_to_primary_star = planet.a;
+        moon.mass = float_to_precision(moon.mass * planet.mass);
+        moon.bulk_composition = BulkComposition::accreted(&moon.mass, &0.0, ice_to_rock);
+        moon.orbit_zone = planet.orbit_zone;
+        moon.radius = bulk_radius(&moon.mass, &false, &moon.orbit_zone, &moon.bulk_composition);
+    }
+    moons.retain(|m| m.mass > 0.0);
+    moons
+}
+
 /// Two planetesimals intersect
 pub fn planetesimals_intersect(
     p: &mut Planetesimal,
@@ -248,11 +736,11 @@ pub fn planetesimals_intersect(
     primary_star_luminosity: &f64,
     primary_star_mass: &f64,
     rng: &mut dyn RngCore,
-    events_log: &mut AccreteEvents,
+    observer: &mut dyn AccreteObserver,
 ) {
     // Moon is not likely to capture other moon in a presence of planet
     if p.is_moon {
-        *prev_p = coalesce_two_planets(prev_p, p, events_log);
+        *prev_p = coalesce_two_planets(prev_p, p, primary_star_mass, observer);
     } else {
         // Check for larger/smaller planetesimal
         let (larger, smaller) = match p.mass >= prev_p.mass {
@@ -262,20 +750,17 @@ pub fn planetesimals_intersect(
         let roche_limit = roche_limit_au(&larger.mass, &smaller.mass, &smaller.radius);
         // Planetesimals collide or one capture another as moon
         if (prev_p.a - p.a).abs() <= roche_limit * 2.0 {
-            *prev_p = coalesce_two_planets(prev_p, p, events_log);
+            *prev_p = coalesce_two_planets(prev_p, p, primary_star_mass, observer);
         } else {
-            *prev_p = capture_moon(&larger, &smaller, primary_star_mass, rng, events_log);
-            prev_p
-                .moons
-                .sort_by(|p1, p2| p1.a.partial_cmp(&p2.a).unwrap());
+            *prev_p = capture_moon(&larger, &smaller, primary_star_mass, rng, observer);
+            prev_p.moons.sort_by(|p1, p2| p1.a.total_cmp(&p2.a));
             coalesce_planetesimals(
-                primary_star_luminosity,
-                primary_star_mass,
+                &|_| (*primary_star_mass, *primary_star_luminosity),
                 &mut prev_p.moons,
                 rng,
-                events_log,
+                observer,
             );
-            moons_to_rings(prev_p, events_log);
+            moons_to_rings(prev_p, observer);
         }
     }
 }
@@ -305,13 +790,19 @@ fn check_orbits_intersect(
     diff.abs() < dist1.abs() || diff.abs() < dist2.abs()
 }
 
-/// Two planetesimals collide and form one planet
+/// Two planetesimals collide and form one planet. Mass eroded by violent collision of planets is lost, stripped from envelope and mantle of remnant.
+/// Orbits of moons around planet are not tracked by eccentricity, colliding moons merge perfectly.
 fn coalesce_two_planets(
     a: &Planetesimal,
     b: &Planetesimal,
-    events_log: &mut AccreteEvents,
+    central_mass: &f64,
+    observer: &mut dyn AccreteObserver,
 ) -> Planetesimal {
     let new_mass = a.mass + b.mass;
+    let remnant_mass = match a.is_moon && b.is_moon {
+        true => new_mass,
+        false => largest_remnant_mass(a, b, central_mass),
+    };
     let new_axis = new_mass / (a.mass / a.a + b.mass / b.a);
     let term1 = a.mass * (a.a * (1.0 - a.e.powf(2.0))).sqrt();
     let term2 = b.mass * (b.a * (1.0 - b.e.powf(2.0))).sqrt();
@@ -320,39 +811,172 @@ fn coalesce_two_planets(
     let new_eccn = term4.abs().sqrt();
     let mut coalesced = a.clone();
 
-    coalesced.mass = float_to_precision(new_mass);
+    coalesced.bulk_composition = a
+        .bulk_composition
+        .merge(&b.bulk_composition)
+        .stripped(&(new_mass - remnant_mass));
+    coalesced.mass = float_to_precision(remnant_mass);
     coalesced.a = new_axis;
     coalesced.e = new_eccn;
     coalesced.is_gas_giant = a.is_gas_giant || b.is_gas_giant;
-    coalesced.radius = kothari_radius(
+    coalesced.radius = bulk_radius(
         &coalesced.mass,
         &coalesced.is_gas_giant,
         &coalesced.orbit_zone,
+        &coalesced.bulk_composition,
     );
     coalesced.has_collision = true;
 
-    let event_type = match a.is_moon && b.is_moon {
-        true => format!("moons_coalesced:{}:{}", a.id, b.id),
-        false => format!("planetesimals_coalesced:{}:{}", a.id, b.id),
-    };
-
-    coalesced.event(event_type.as_str(), events_log);
+    match a.is_moon && b.is_moon {
+        true => observer.emit(AccreteEventKind::MoonsCoalesced, || {
+            AccreteEvent::MoonsCoalesced {
+                target_id: a.id.clone(),
+                source_id: b.id.clone(),
+                a: coalesced.a,
+                mass_transferred: b.mass,
+                result: coalesced.clone(),
+            }
+        }),
+        false => observer.emit(AccreteEventKind::PlanetesimalsCoalesced, || {
+            AccreteEvent::PlanetesimalsCoalesced {
+                target_id: a.id.clone(),
+                source_id: b.id.clone(),
+                a: coalesced.a,
+                mass_transferred: b.mass,
+                result: coalesced.clone(),
+            }
+        }),
+    }
 
     coalesced
 }
 
+/// Resolve unstable neighbouring planets one by one, until all planets are spaced by more than critical number of mutual Hill radii
+fn resolve_hill_instabilities(
+    planets: &mut Vec<Planetesimal>,
+    central_mass: &f64,
+    observer: &mut dyn AccreteObserver,
+) -> Vec<Instability> {
+    let mut instabilities = Vec::new();
+    // Scattered planet may end up near another one, number of attempts is limited
+    for _ in 0..planets.len() * 2 {
+        let i = match hill_spacings(planets, central_mass)
+            .iter()
+            .position(|s| !s.is_stable)
+        {
+            Some(i) => i,
+            None => break,
+        };
+        let (heavier, lighter) = match planets[i].mass >= planets[i + 1].mass {
+            true => (i, i + 1),
+            false => (i + 1, i),
+        };
+        let kind = encounter_outcome(&planets[heavier], central_mass);
+        instabilities.push(Instability {
+            kind,
+            body_id: planets[lighter].id.clone(),
+            partner_id: Some(planets[heavier].id.clone()),
+        });
+
+        match kind {
+            InstabilityKind::Collision => {
+                planets[heavier] = coalesce_two_planets(
+                    &planets[heavier],
+                    &planets[lighter],
+                    central_mass,
+                    observer,
+                );
+                planets.remove(lighter);
+            }
+            InstabilityKind::Ejection => eject_planet(planets, lighter, observer),
+            InstabilityKind::Scattering => {
+                let (a, e) = scattered_orbit(&planets[lighter], &planets[heavier], central_mass);
+                planets[lighter].set_orbit(a, e);
+                scatter_planet(&planets[lighter], observer);
+                planets.sort_by(|p1, p2| p1.a.total_cmp(&p2.a));
+            }
+        }
+    }
+    instabilities
+}
+
+/// Apply outcome of N-body integration: merge collided planets, remove ejected ones and move scattered planets to their final orbits
+fn apply_integration(
+    planets: &mut Vec<Planetesimal>,
+    integration: &Integration,
+    central_mass: &f64,
+    observer: &mut dyn AccreteObserver,
+) {
+    for instability in integration.instabilities.iter() {
+        let body = match planets.iter().position(|p| p.id == instability.body_id) {
+            Some(body) => body,
+            None => continue,
+        };
+        match instability.kind {
+            InstabilityKind::Collision => {
+                if let Some(partner) = planets
+                    .iter()
+                    .position(|p| Some(&p.id) == instability.partner_id.as_ref())
+                {
+                    planets[partner] = coalesce_two_planets(
+                        &planets[partner],
+                        &planets[body],
+                        central_mass,
+                        observer,
+                    );
+                    planets.remove(body);
+                }
+            }
+            InstabilityKind::Ejection => eject_planet(planets, body, observer),
+            InstabilityKind::Scattering => {
+                let planet = &mut planets[body];
+                let orbit = integration
+                    .states
+                    .iter()
+                    .find(|(id, _)| id == &planet.id)
+                    .and_then(|(_, state)| elements_from_state(state, central_mass, &planet.mass));
+                if let Some((a, e, elements)) = orbit {
+                    planet.set_orbit(a, e);
+                    planet.orbital_elements = elements;
+                    scatter_planet(planet, observer);
+                }
+            }
+        }
+    }
+    planets.sort_by(|p1, p2| p1.a.total_cmp(&p2.a));
+}
+
+fn eject_planet(planets: &mut Vec<Planetesimal>, i: usize, observer: &mut dyn AccreteObserver) {
+    let ejected = planets.remove(i);
+    observer.emit(AccreteEventKind::PlanetesimalEjected, || {
+        AccreteEvent::PlanetesimalEjected {
+            planetesimal_id: ejected.id.clone(),
+        }
+    });
+}
+
+fn scatter_planet(planet: &Planetesimal, observer: &mut dyn AccreteObserver) {
+    observer.emit(AccreteEventKind::PlanetesimalScattered, || {
+        AccreteEvent::PlanetesimalScattered {
+            planetesimal: planet.clone(),
+        }
+    });
+}
+
 /// Larger planetsimal capture smaller as moon
 fn capture_moon(
     larger: &Planetesimal,
     smaller: &Planetesimal,
     stellar_mass: &f64,
     rng: &mut dyn RngCore,
-    events_log: &mut AccreteEvents,
+    observer: &mut dyn AccreteObserver,
 ) -> Planetesimal {
     let mut planet = larger.clone();
     let mut moon = smaller.clone();
     moon.is_moon = true;
+    moon.moon_class = Some(MoonClass::Irregular);
     let moon_id = moon.id.clone();
+    let moon_mass = moon.mass;
 
     // Recalcualte planetary axis
     let new_mass = planet.mass + moon.mass;
@@ -373,31 +997,42 @@ fn capture_moon(
     planet.moons.push(moon);
 
     for m in planet.moons.iter_mut() {
-        m.a = rng.gen_range(0.0..planet.hill_sphere);
+        m.a = match planet.hill_sphere > 0.0 {
+            true => rng.gen_range(0.0..planet.hill_sphere),
+            false => 0.0,
+        };
         m.e = random_eccentricity(rng);
         m.b = m.a * (1.0 - m.e.powf(2.0)).sqrt();
         m.distance_to_primary_star = planet.a;
     }
 
-    planet.event(
-        format!("planetesimal_capture_moon:{}:{}", planet.id, moon_id).as_str(),
-        events_log,
-    );
+    observer.emit(AccreteEventKind::PlanetesimalCaptureMoon, || {
+        AccreteEvent::PlanetesimalCaptureMoon {
+            planet_id: planet.id.clone(),
+            moon_id,
+            a: planet.a,
+            mass_transferred: moon_mass,
+            result: planet.clone(),
+        }
+    });
 
     planet
 }
 
-fn moons_to_rings(planet: &mut Planetesimal, events_log: &mut AccreteEvents) {
+fn moons_to_rings(planet: &mut Planetesimal, observer: &mut dyn AccreteObserver) {
     let mut next_moons = Vec::new();
     for m in planet.moons.iter_mut() {
         let roche_limit = roche_limit_au(&planet.mass, &m.mass, &m.radius);
         let moon_perhelion = perihelion_distance(&m.a, &m.e);
         if moon_perhelion <= roche_limit * 2.0 {
             let ring = Ring::from_planet(roche_limit, m);
-            ring.event(
-                format!("moon_to_ring:{}:{}", planet.id, m.id).as_str(),
-                events_log,
-            );
+            observer.emit(AccreteEventKind::PlanetesimalMoonToRing, || {
+                AccreteEvent::PlanetesimalMoonToRing {
+                    planet_id: planet.id.clone(),
+                    moon_id: m.id.clone(),
+                    ring: ring.clone(),
+                }
+            });
             planet.rings.push(ring);
         } else {
             next_moons.push(m.clone());
@@ -407,6 +1042,56 @@ fn moons_to_rings(planet: &mut Planetesimal, events_log: &mut AccreteEvents) {
     planet.moons = next_moons;
 }
 
+/// Mass and luminosity of the body planet is orbiting: primary star for S-type orbits, both stars for P-type orbits
+fn central_body(
+    primary_star: &PrimaryStar,
+    companion_star: &Option<CompanionStar>,
+    a: &f64,
+) -> (f64, f64) {
+    match companion_star {
+        Some(c) if c.is_circumbinary(a) => (
+            primary_star.stellar_mass + c.star.stellar_mass,
+            primary_star.stellar_luminosity + c.star.stellar_luminosity,
+        ),
+        _ => (primary_star.stellar_mass, primary_star.stellar_luminosity),
+    }
+}
+
+/// Semi-major axes and resonances of planet and its moons
+fn resonant_orbits(planet: &Planetesimal) -> Vec<(f64, Vec<Resonance>)> {
+    std::iter::once(planet)
+        .chain(planet.moons.iter())
+        .map(|p| (p.a, p.resonances.clone()))
+        .collect()
+}
+
+/// Companion star clears dust cloud between S-type and P-type stability limits
+fn initial_dust_bands(
+    inner_dust: f64,
+    outer_dust: f64,
+    companion_star: &Option<CompanionStar>,
+) -> Vec<DustBand> {
+    let companion_star = match companion_star {
+        Some(c) => c,
+        None => return vec![DustBand::new(outer_dust, inner_dust, true, true)],
+    };
+    let s_type_limit = companion_star.s_type_limit.clamp(inner_dust, outer_dust);
+    let p_type_limit = companion_star.p_type_limit.clamp(s_type_limit, outer_dust);
+    let mut dust_bands = Vec::new();
+
+    if s_type_limit > inner_dust {
+        dust_bands.push(DustBand::new(s_type_limit, inner_dust, true, true));
+    }
+    if p_type_limit > s_type_limit {
+        dust_bands.push(DustBand::new(p_type_limit, s_type_limit, false, false));
+    }
+    if outer_dust > p_type_limit {
+        dust_bands.push(DustBand::new(outer_dust, p_type_limit, true, true));
+    }
+
+    dust_bands
+}
+
 fn stellar_dust_limit(stellar_mass_ratio: &f64) -> f64 {
     200.0 * stellar_mass_ratio.powf(1.0 / 3.0)
 }
@@ -419,3 +1104,75 @@ fn outermost_planet(stellar_mass_ratio: &f64) -> f64 {
 fn innermost_planet(stellar_mass_ratio: &f64) -> f64 {
     0.3 * stellar_mass_ratio.powf(0.33)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AccreteBuilder;
+
+    #[test]
+    fn circumbinary_orbits_expand_with_mass_of_both_stars() {
+        let system_at_age = |system_age: f64| {
+            AccreteBuilder::new(1)
+                .stellar_mass(1.0)
+                .companion_star(CompanionStarConfig::new(0.5, 0.5, 0.0))
+                .system_age(system_age)
+                .build()
+                .expect("Invalid configuration")
+                .planetary_system()
+                .expect("Failed to generate planetary system")
+        };
+        let young_system = system_at_age(1.0e9);
+        let old_system = system_at_age(1.2e10);
+        let old_companion = old_system
+            .companion_star
+            .expect("Failed to find companion star");
+        let young_companion = young_system
+            .companion_star
+            .expect("Failed to find companion star");
+        let expansion = (1.0 + 0.5) / (old_system.primary_star.stellar_mass + 0.5);
+
+        assert!(old_companion.mass_ratio > young_companion.mass_ratio);
+        assert!(!young_system.planets.is_empty());
+        for (young, old) in young_system.planets.iter().zip(old_system.planets.iter()) {
+            assert!(young.is_circumbinary);
+            assert_eq!(young.id, old.id);
+            assert!((old.a / young.a - expansion).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn gas_giants_accrete_regular_moons() {
+        let mut accrete = AccreteBuilder::new(1)
+            .regular_moons(true)
+            .build()
+            .expect("Invalid configuration");
+        let system = accrete
+            .planetary_system()
+            .expect("Failed to generate planetary system");
+        let gas_giants = system
+            .planets
+            .iter()
+            .filter(|p| p.is_gas_giant)
+            .collect::<Vec<_>>();
+
+        assert!(!gas_giants.is_empty());
+        for planet in gas_giants {
+            let regular_moons = planet
+                .moons
+                .iter()
+                .filter(|m| m.moon_class == Some(MoonClass::Regular))
+                .collect::<Vec<_>>();
+            let moons_mass: f64 = regular_moons.iter().map(|m| m.mass).sum();
+            assert!(!regular_moons.is_empty());
+            assert!(moons_mass / planet.mass > 1e-5 && moons_mass / planet.mass < 1e-3);
+            for moon in regular_moons {
+                assert!(moon.a <= CIRCUMPLANETARY_DISK_RADIUS * planet.hill_sphere * 1.0001);
+                assert!(moon.orbital_elements.inclination < 90.0);
+            }
+        }
+        for moon in system.planets.iter().flat_map(|p| p.moons.iter()) {
+            assert!(moon.moon_class.is_some());
+        }
+    }
+}
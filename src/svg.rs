@@ -0,0 +1,325 @@
+use crate::consts::EARTH_MASSES_PER_SOLAR_MASS;
+use crate::error::AccreteError;
+use crate::events_log::accrete_event::{AccreteEvent, AccreteEvents};
+use crate::events_log::accrete_replay::AccreteReplay;
+use crate::structs::{DustBand, Planetesimal, System};
+
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+const MARGIN: f64 = 40.0;
+const BAND_HEIGHT: f64 = 14.0;
+const MIN_PLANET_RADIUS: f64 = 1.5;
+const MAX_PLANET_RADIUS: f64 = 30.0;
+
+/// Options of SVG rendering of planetary system.
+/// **max_distance** - outer edge of plot in AU, fitted to planets and planetesimal bounds if not set.
+/// **log_scale** - logarithmic scale of distance from star, spreads out inner system.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct SvgOptions {
+    pub width: u32,
+    pub height: u32,
+    pub max_distance: Option<f64>,
+    pub log_scale: bool,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        Self {
+            width: 1200,
+            height: 400,
+            max_distance: None,
+            log_scale: true,
+        }
+    }
+}
+
+/// Radial plot of planetary system: gas and dust bands of cloud, ecosphere, snow line and planets sized by mass, with lines from perihelion to aphelion of their orbits.
+///
+/// ```
+/// use accrete::Accrete;
+/// use accrete::svg::{render_system, SvgOptions};
+///
+/// let mut accrete = Accrete::new(1);
+/// let system = accrete.planetary_system().unwrap();
+/// let svg = render_system(&system, &SvgOptions::default());
+/// ```
+pub fn render_system(system: &System, options: &SvgOptions) -> String {
+    let scale = Scale::new(system, options);
+    render(system, &scale, options, None)
+}
+
+/// Frames of accretion replayed from events log, one SVG every `step_interval` events and one of final state.
+/// All frames share distance scale of final planetary system.
+pub fn render_frames(
+    events: &AccreteEvents,
+    step_interval: usize,
+    options: &SvgOptions,
+) -> Result<Vec<String>, AccreteError> {
+    let final_system = events
+        .iter()
+        .rev()
+        .find_map(|event| match event {
+            AccreteEvent::PlanetarySystemComplete { system } => Some(system),
+            _ => None,
+        })
+        .ok_or(AccreteError::NoSystemData)?;
+    let scale = Scale::new(final_system, options);
+    let mut replay = AccreteReplay::new(events.clone())?;
+    let last_step = replay.len() - 1;
+
+    let mut steps: Vec<usize> = (0..last_step).step_by(step_interval.max(1)).collect();
+    steps.push(last_step);
+    steps
+        .into_iter()
+        .map(|step| {
+            let state = replay.seek(step)?;
+            let label = format!("step {} / {}", step, last_step);
+            Ok(render(&state.system, &scale, options, Some(&label)))
+        })
+        .collect()
+}
+
+/// Horizontal distance axis of plot, AU to pixels
+#[derive(Debug, Clone, Copy)]
+struct Scale {
+    min: f64,
+    max: f64,
+    log_scale: bool,
+    width: f64,
+}
+
+impl Scale {
+    fn new(system: &System, options: &SvgOptions) -> Self {
+        let aphelion = system
+            .planets
+            .iter()
+            .map(|p| p.a * (1.0 + p.e))
+            .fold(system.planetesimal_outer_bound, f64::max);
+        let max = options.max_distance.unwrap_or(aphelion * 1.2);
+        let min = match options.log_scale {
+            true => (system.planetesimal_inner_bound / 2.0).min(max / 10.0),
+            false => 0.0,
+        };
+
+        Self {
+            min,
+            max,
+            log_scale: options.log_scale,
+            width: options.width as f64,
+        }
+    }
+
+    fn x(&self, r: f64) -> f64 {
+        let position = match self.log_scale {
+            true => (r.max(self.min).ln() - self.min.ln()) / (self.max.ln() - self.min.ln()),
+            false => (r - self.min) / (self.max - self.min),
+        };
+        MARGIN + position.clamp(0.0, 1.0) * (self.width - 2.0 * MARGIN)
+    }
+
+    /// Distances of labeled ticks, powers of ten for log scale
+    fn ticks(&self) -> Vec<f64> {
+        let step = match self.log_scale {
+            true => 10.0,
+            false => 10.0_f64.powf((self.max / 2.0).log10().floor()),
+        };
+        let mut tick = match self.log_scale {
+            true => 10.0_f64.powf(self.min.log10().ceil()),
+            false => step,
+        };
+        let mut ticks = Vec::new();
+        while tick <= self.max {
+            ticks.push(tick);
+            tick = match self.log_scale {
+                true => tick * step,
+                false => tick + step,
+            };
+        }
+        ticks
+    }
+}
+
+fn render(system: &System, scale: &Scale, options: &SvgOptions, label: Option<&str>) -> String {
+    let width = options.width as f64;
+    let height = options.height as f64;
+    let gas_y = MARGIN;
+    let dust_y = gas_y + BAND_HEIGHT + 4.0;
+    let axis_y = height - MARGIN;
+    let orbit_y = (dust_y + BAND_HEIGHT + axis_y) / 2.0;
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}" font-family="sans-serif" font-size="11">"#,
+        options.width, options.height, options.width, options.height
+    );
+    let _ = writeln!(
+        svg,
+        r##"<rect width="{}" height="{}" fill="#0b0d17"/>"##,
+        width, height
+    );
+
+    let (eco_inner, eco_outer) = system.primary_star.ecosphere;
+    let _ = writeln!(
+        svg,
+        r##"<rect class="ecosphere" x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="#2e8b57" fill-opacity="0.25"/>"##,
+        scale.x(eco_inner),
+        gas_y,
+        scale.x(eco_outer) - scale.x(eco_inner),
+        axis_y - gas_y
+    );
+
+    for band in system.dust_bands.iter() {
+        render_band(&mut svg, scale, band, band.gas_present, gas_y, "#4a78c2");
+        render_band(&mut svg, scale, band, band.dust_present, dust_y, "#c2914a");
+    }
+
+    let snow_line = scale.x(system.condensation_lines.water);
+    let _ = writeln!(
+        svg,
+        r##"<line class="snow-line" x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="#e0f0ff" stroke-dasharray="4 4"/>"##,
+        snow_line, gas_y, snow_line, axis_y
+    );
+    let _ = writeln!(
+        svg,
+        r##"<text x="{:.1}" y="{:.1}" fill="#e0f0ff">snow line</text>"##,
+        snow_line + 4.0,
+        axis_y - 6.0
+    );
+
+    let _ = writeln!(
+        svg,
+        r##"<line x1="{}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="#a0a0a0"/>"##,
+        MARGIN,
+        axis_y,
+        width - MARGIN,
+        axis_y
+    );
+    for tick in scale.ticks() {
+        let x = scale.x(tick);
+        let _ = writeln!(
+            svg,
+            r##"<line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="#a0a0a0"/><text x="{:.1}" y="{:.1}" fill="#a0a0a0" text-anchor="middle">{} AU</text>"##,
+            x,
+            axis_y,
+            x,
+            axis_y + 5.0,
+            x,
+            axis_y + 18.0,
+            tick
+        );
+    }
+
+    for planet in system.planets.iter() {
+        render_planet(&mut svg, scale, planet, orbit_y);
+    }
+
+    let title = match label {
+        Some(label) => format!("{}, {}", system.primary_star.spectral_type(), label),
+        None => system.primary_star.spectral_type(),
+    };
+    let _ = writeln!(
+        svg,
+        r##"<text x="{}" y="{}" fill="#ffffff">{}</text>"##,
+        MARGIN,
+        MARGIN - 14.0,
+        title
+    );
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn render_band(
+    svg: &mut String,
+    scale: &Scale,
+    band: &DustBand,
+    present: bool,
+    y: f64,
+    color: &str,
+) {
+    if !present {
+        return;
+    }
+    let _ = writeln!(
+        svg,
+        r#"<rect class="band" x="{:.1}" y="{:.1}" width="{:.1}" height="{}" fill="{}"/>"#,
+        scale.x(band.inner_edge),
+        y,
+        scale.x(band.outer_edge) - scale.x(band.inner_edge),
+        BAND_HEIGHT,
+        color
+    );
+}
+
+fn render_planet(svg: &mut String, scale: &Scale, planet: &Planetesimal, y: f64) {
+    let earth_masses = planet.mass * EARTH_MASSES_PER_SOLAR_MASS;
+    let radius = (3.0 * earth_masses.powf(1.0 / 3.0)).clamp(MIN_PLANET_RADIUS, MAX_PLANET_RADIUS);
+    let color = match (
+        planet.is_gas_giant,
+        planet.bulk_composition.ice_fraction() > 0.3,
+    ) {
+        (true, _) => "#e8a55c",
+        (false, true) => "#9fd3f0",
+        (false, false) => "#b0a090",
+    };
+
+    let _ = writeln!(
+        svg,
+        r##"<line class="orbit" x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="#ffffff" stroke-opacity="0.5"/>"##,
+        scale.x(planet.a * (1.0 - planet.e)),
+        y,
+        scale.x(planet.a * (1.0 + planet.e)),
+        y
+    );
+    let _ = writeln!(
+        svg,
+        r#"<circle class="planet" cx="{:.1}" cy="{:.1}" r="{:.1}" fill="{}"><title>{:.2} AU, {:.3} Earth masses</title></circle>"#,
+        scale.x(planet.a),
+        y,
+        radius,
+        color,
+        planet.a,
+        earth_masses
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Accrete;
+
+    #[test]
+    fn system_plot_has_every_planet() {
+        let mut accrete = Accrete::new(1);
+        accrete.post_accretion_intensity = 10;
+        let system = accrete
+            .planetary_system()
+            .expect("Failed to generate planetary system");
+        for options in [
+            SvgOptions::default(),
+            SvgOptions {
+                max_distance: Some(10.0),
+                log_scale: false,
+                ..SvgOptions::default()
+            },
+        ] {
+            let svg = render_system(&system, &options);
+            assert!(svg.starts_with("<svg") && svg.ends_with("</svg>\n"));
+            assert_eq!(
+                svg.matches(r#"class="planet""#).count(),
+                system.planets.len()
+            );
+            assert!(!svg.contains("NaN"));
+        }
+
+        let frames = render_frames(&accrete.events_log, 100, &SvgOptions::default())
+            .expect("Failed to render frames");
+        assert_eq!(frames.len(), (accrete.events_log.len() + 98) / 100 + 1);
+        let last_frame = frames.last().expect("Failed to render last frame");
+        assert_eq!(
+            last_frame.matches(r#"class="planet""#).count(),
+            system.planets.len()
+        );
+    }
+}
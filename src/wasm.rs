@@ -1,23 +1,90 @@
 use crate::accrete::Accrete;
+use crate::error::AccreteError;
+use crate::events_log::accrete_event::{AccreteEvent, AccreteEventKind};
+use crate::events_log::observer::AccreteObserver;
 use crate::utils::set_panic_hook;
 use wasm_bindgen::prelude::*;
 
 /// Generate planetary system from seed and primary star mass
+/// Throws JS Error if configuration is invalid or generation failed
 #[wasm_bindgen]
-pub fn planetary_system(seed: u64, stellar_mass: f64) -> JsValue {
+pub fn planetary_system(seed: u64, stellar_mass: f64) -> Result<JsValue, JsError> {
     set_panic_hook();
     let mut accrete = Accrete::new(seed);
     accrete.stellar_mass = stellar_mass;
-    let planetary_system = accrete.planetary_system();
-    serde_wasm_bindgen::to_value(&planetary_system).unwrap()
+    let planetary_system = accrete.planetary_system()?;
+    let output = serde_wasm_bindgen::to_value(&planetary_system)
+        .map_err(|e| AccreteError::Serialization(e.to_string()))?;
+    Ok(output)
+}
+
+/// Generate planetary system from seed and primary star mass, calling `on_event` with every accretion event as it happens.
+/// Optional `event_types` limits events to given types, for example `["planetesimals_coalesced", "dust_bands_updated"]`.
+/// Throws JS Error if configuration is invalid, generation failed or `on_event` thrown.
+#[wasm_bindgen]
+pub fn planetary_system_with_events(
+    seed: u64,
+    stellar_mass: f64,
+    on_event: &js_sys::Function,
+    event_types: Option<Vec<String>>,
+) -> Result<JsValue, JsValue> {
+    set_panic_hook();
+    let mut accrete = Accrete::new(seed);
+    accrete.stellar_mass = stellar_mass;
+    let mut observer = JsObserver {
+        callback: on_event,
+        event_types,
+        error: None,
+    };
+    let planetary_system = accrete
+        .planetary_system_with_observer(&mut observer)
+        .map_err(|e| JsValue::from(JsError::from(e)))?;
+    if let Some(error) = observer.error {
+        return Err(error);
+    }
+    let output = serde_wasm_bindgen::to_value(&planetary_system)
+        .map_err(|e| AccreteError::Serialization(e.to_string()))
+        .map_err(|e| JsValue::from(JsError::from(e)))?;
+    Ok(output)
 }
 
 /// Generate random planet from seed and primary star mass
+/// Throws JS Error if configuration is invalid or generation failed
 #[wasm_bindgen]
-pub fn planet(seed: u64, stellar_mass: f64) -> JsValue {
+pub fn planet(seed: u64, stellar_mass: f64) -> Result<JsValue, JsError> {
     set_panic_hook();
     let mut accrete = Accrete::new(seed);
     accrete.stellar_mass = stellar_mass;
-    let planet = accrete.planet();
-    serde_wasm_bindgen::to_value(&planet).unwrap()
+    let planet = accrete.planet()?;
+    let output = serde_wasm_bindgen::to_value(&planet)
+        .map_err(|e| AccreteError::Serialization(e.to_string()))?;
+    Ok(output)
+}
+
+/// Passes serialized events to JS callback, keeps first error thrown by callback
+struct JsObserver<'a> {
+    callback: &'a js_sys::Function,
+    event_types: Option<Vec<String>>,
+    error: Option<JsValue>,
+}
+
+impl AccreteObserver for JsObserver<'_> {
+    fn accepts(&self, kind: AccreteEventKind) -> bool {
+        if self.error.is_some() {
+            return false;
+        }
+        match &self.event_types {
+            Some(event_types) => event_types.iter().any(|t| t == kind.name()),
+            None => true,
+        }
+    }
+
+    fn on_event(&mut self, event: AccreteEvent) {
+        let result = serde_wasm_bindgen::to_value(&event)
+            .map_err(JsValue::from)
+            .and_then(|value| self.callback.call1(&JsValue::NULL, &value));
+        if let Err(error) = result {
+            self.error = Some(error);
+        }
+    }
 }